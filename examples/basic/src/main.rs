@@ -3,6 +3,7 @@ use p4runtime_client::p4runtime::p4::config::v1::P4Info;
 use p4runtime_client::p4runtime::p4::v1::{self as p4_v1, Uint128};
 use p4runtime_client::utils::de::from_p4data;
 use prost::Message;
+use std::time::Duration;
 
 #[allow(dead_code)]
 #[derive(Debug, serde::Deserialize)]
@@ -76,16 +77,16 @@ async fn main() -> anyhow::Result<()> {
     );
 
     client
-        .table_mut()
+        .table()
         .insert_entries(vec![table_entry_1, table_entry_2])
         .await?;
 
     println!("Table entries inserted.");
 
     let digest_entry = client.digest().new_entry("ipv4_digest_t", 0, 1, 0);
-    client.digest_mut().insert_entry(digest_entry).await?;
+    client.digest().insert_entry(digest_entry).await?;
 
-    while let Ok(digest) = client.get_digest(1).await {
+    while let Ok(digest) = client.get_digest(Duration::from_secs(1)).await {
         let data = digest.data;
         for d in data {
             let ipv4_digest: Ipv4Digest = from_p4data(&d)?;
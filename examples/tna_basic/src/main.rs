@@ -33,7 +33,7 @@ async fn main() -> anyhow::Result<()> {
     loop {
         select! {
             _ = sleep(Duration::from_secs(1)) => {
-                let entries = client.counter_mut().read_entries(counter_entry).await?;
+                let entries = client.counter().read_entries(counter_entry).await?;
 
                 println!("Counter entries: {:?}", entries);
             },
@@ -41,7 +41,7 @@ async fn main() -> anyhow::Result<()> {
     client.set_forwarding_pipeline_config(p4config).await?;
 
     let digest_entry = client.digest().new_entry("digest_a", 0, 1, 0);
-    client.digest_mut().insert_entry(digest_entry).await?;
+    client.digest().insert_entry(digest_entry).await?;
 
     loop {
         select! {
@@ -49,7 +49,7 @@ async fn main() -> anyhow::Result<()> {
                 println!("Received Ctrl-C, shutting down...");
                 break;
             },
-            msg = client.get_digest(1) => {
+            msg = client.get_digest(Duration::from_secs(1)) => {
                 match msg {
                     Ok(digest) => {
                         let data = digest.data;
@@ -0,0 +1,354 @@
+//! Client-side shadow cache of installed entities
+//!
+//! A [`ShadowCache`] set on the client mirrors the successful writes of table
+//! entries, action profile members and groups, PRE entries and value sets, so
+//! that control loops can query the installed state without a Read round
+//! trip. Other entities, e.g. counters and registers, change on the device and
+//! are not cached.
+//!
+//! The cache only knows what this client wrote. It is resynced from the device
+//! by [`Client::resync_cache`](crate::client::Client::resync_cache), which
+//! [`Client::run`](crate::client::Client::run) calls on every (re)connection.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use p4runtime_client::{cache::ShadowCache, client::Client};
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut client = Client::builder()
+//!     .cache(Some(ShadowCache::default()))
+//!     .build()?;
+//! client.connect("http://localhost:9559").await?;
+//! client.run().await?;
+//!
+//! // No round trip
+//! let routes = client.cached_table_entries("MyIngress.ipv4_lpm");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::{Arc, RwLock};
+
+use log::warn;
+use p4runtime::p4::v1 as p4_v1;
+use prost::Message;
+
+use crate::{error::ClientError, sim::EntityStore};
+
+/// Write-through cache of installed entities
+///
+/// Clones share the same cache.
+#[derive(Clone, Debug, Default)]
+pub struct ShadowCache {
+    state: Arc<RwLock<CacheState>>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    store: EntityStore,
+    stale: bool,
+}
+
+impl ShadowCache {
+    /// Whether an entity is mirrored by the cache
+    pub fn is_cached(entity: &p4_v1::Entity) -> bool {
+        use p4_v1::entity::Entity;
+
+        matches!(
+            entity.entity,
+            Some(
+                Entity::TableEntry(_)
+                    | Entity::ActionProfileMember(_)
+                    | Entity::ActionProfileGroup(_)
+                    | Entity::PacketReplicationEngineEntry(_)
+                    | Entity::ValueSetEntry(_)
+            )
+        )
+    }
+
+    /// Read requests fetching all cached entities from a device
+    pub fn resync_entities() -> Vec<p4_v1::Entity> {
+        use p4_v1::entity::Entity;
+        use p4_v1::packet_replication_engine_entry::Type;
+
+        let pre = |r#type| {
+            Entity::PacketReplicationEngineEntry(p4_v1::PacketReplicationEngineEntry {
+                r#type: Some(r#type),
+            })
+        };
+
+        [
+            Entity::TableEntry(Default::default()),
+            Entity::ActionProfileMember(Default::default()),
+            Entity::ActionProfileGroup(Default::default()),
+            pre(Type::MulticastGroupEntry(Default::default())),
+            pre(Type::CloneSessionEntry(Default::default())),
+            Entity::ValueSetEntry(Default::default()),
+        ]
+        .into_iter()
+        .map(|entity| p4_v1::Entity {
+            entity: Some(entity),
+        })
+        .collect()
+    }
+
+    /// Number of cached entities
+    pub fn len(&self) -> usize {
+        self.state.read().unwrap().store.len()
+    }
+
+    /// Whether no entity is cached
+    pub fn is_empty(&self) -> bool {
+        self.state.read().unwrap().store.is_empty()
+    }
+
+    /// Whether the cache may differ from the device
+    ///
+    /// It happens when a write fails without per-update errors, when a resync
+    /// fails, or after a `ReconcileAndCommit` of the pipeline. Resyncing clears
+    /// it.
+    pub fn is_stale(&self) -> bool {
+        self.state.read().unwrap().stale
+    }
+
+    /// Mark the cache as possibly differing from the device
+    pub fn mark_stale(&self) {
+        self.state.write().unwrap().stale = true;
+    }
+
+    /// Remove all entities
+    pub fn clear(&self) {
+        let mut state = self.state.write().unwrap();
+        state.store.clear();
+        state.stale = false;
+    }
+
+    /// Replace the cached entities with the ones read from the device
+    pub fn replace(&self, entities: Vec<p4_v1::Entity>) {
+        let mut store = EntityStore::default();
+        for entity in entities.into_iter().filter(Self::is_cached) {
+            store.insert(entity);
+        }
+
+        let mut state = self.state.write().unwrap();
+        state.store = store;
+        state.stale = false;
+    }
+
+    /// Read the cached entities matching a read request entity
+    ///
+    /// Wildcards work like in a Read request.
+    pub fn read(&self, entity: &p4_v1::Entity) -> Vec<p4_v1::Entity> {
+        self.state.read().unwrap().store.read(entity)
+    }
+
+    /// Get the cached entries of a table, `0` meaning all tables
+    pub fn table_entries(&self, table_id: u32) -> Vec<p4_v1::TableEntry> {
+        self.read_table_entries(p4_v1::TableEntry {
+            table_id,
+            ..Default::default()
+        })
+    }
+
+    /// Get the cached entry with the key, i.e. the match fields and priority,
+    /// of an entry
+    pub fn table_entry(&self, entry: &p4_v1::TableEntry) -> Option<p4_v1::TableEntry> {
+        let key = p4_v1::TableEntry {
+            table_id: entry.table_id,
+            r#match: entry.r#match.clone(),
+            priority: entry.priority,
+            is_default_action: entry.is_default_action,
+
+            ..Default::default()
+        };
+
+        self.read_table_entries(key).into_iter().next()
+    }
+
+    fn read_table_entries(&self, filter: p4_v1::TableEntry) -> Vec<p4_v1::TableEntry> {
+        self.read(&p4_v1::Entity {
+            entity: Some(p4_v1::entity::Entity::TableEntry(filter)),
+        })
+        .into_iter()
+        .filter_map(|e| match e.entity {
+            Some(p4_v1::entity::Entity::TableEntry(entry)) => Some(entry),
+            _ => None,
+        })
+        .collect()
+    }
+
+    /// Mirror the outcome of a Write request
+    ///
+    /// On failure, only the updates reported as successful in the error
+    /// details are applied. If the error has no per-update details, e.g. a
+    /// timeout, the cache is marked stale.
+    pub fn record_write<T>(&self, updates: &[p4_v1::Update], result: &Result<T, ClientError>) {
+        let applied = match result {
+            Ok(_) => vec![true; updates.len()],
+            Err(e) => match write_error_codes(e) {
+                Some(codes) if codes.len() == updates.len() => {
+                    codes.into_iter().map(|code| code == 0).collect()
+                }
+                _ => {
+                    self.mark_stale();
+                    return;
+                }
+            },
+        };
+
+        let mut state = self.state.write().unwrap();
+        for (update, _) in updates
+            .iter()
+            .zip(applied)
+            .filter(|(update, applied)| *applied && is_cached_update(update))
+        {
+            apply(&mut state.store, update);
+        }
+    }
+}
+
+fn is_cached_update(update: &p4_v1::Update) -> bool {
+    update.entity.as_ref().is_some_and(ShadowCache::is_cached)
+}
+
+/// Apply a successful update, trusting the device
+fn apply(store: &mut EntityStore, update: &p4_v1::Update) {
+    use p4_v1::update::Type;
+
+    let Some(entity) = update.entity.clone() else {
+        return;
+    };
+
+    let reset_default = matches!(
+        &entity.entity,
+        Some(p4_v1::entity::Entity::TableEntry(e)) if e.is_default_action && e.action.is_none()
+    );
+
+    match update.r#type() {
+        Type::Insert | Type::Modify if !reset_default => {
+            store.insert(entity);
+        }
+        Type::Modify | Type::Delete => {
+            let delete = p4_v1::Update {
+                r#type: Type::Delete as i32,
+                entity: Some(entity),
+            };
+            // Entities unknown to the cache have nothing to remove
+            let _ = store.apply(&delete);
+        }
+        _ => warn!("Not caching an update without type"),
+    }
+}
+
+/// Canonical codes of the per-update errors of a failed Write request
+fn write_error_codes(error: &ClientError) -> Option<Vec<i32>> {
+    let ClientError::Status(status) = error else {
+        return None;
+    };
+    let status = p4runtime::google::rpc::Status::decode(status.details()).ok()?;
+    if status.details.is_empty() {
+        return None;
+    }
+
+    status
+        .details
+        .iter()
+        .map(|any| {
+            p4_v1::Error::decode(&any.value[..])
+                .ok()
+                .map(|e| e.canonical_code)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(r#type: p4_v1::update::Type, group_id: u32, instances: &[u32]) -> p4_v1::Update {
+        let replicas = instances
+            .iter()
+            .map(|&instance| p4_v1::Replica {
+                instance,
+                ..Default::default()
+            })
+            .collect();
+
+        p4_v1::Update {
+            r#type: r#type as i32,
+            entity: Some(p4_v1::Entity {
+                entity: Some(p4_v1::entity::Entity::PacketReplicationEngineEntry(
+                    p4_v1::PacketReplicationEngineEntry {
+                        r#type: Some(
+                            p4_v1::packet_replication_engine_entry::Type::MulticastGroupEntry(
+                                p4_v1::MulticastGroupEntry {
+                                    multicast_group_id: group_id,
+                                    replicas,
+                                    ..Default::default()
+                                },
+                            ),
+                        ),
+                    },
+                )),
+            }),
+        }
+    }
+
+    #[test]
+    fn mirror_writes() {
+        use p4_v1::update::Type;
+
+        let cache = ShadowCache::default();
+        cache.record_write(
+            &[update(Type::Insert, 1, &[1]), update(Type::Insert, 2, &[2])],
+            &Ok(()),
+        );
+        cache.record_write(
+            &[
+                update(Type::Modify, 1, &[1, 2]),
+                update(Type::Delete, 2, &[]),
+            ],
+            &Ok(()),
+        );
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.read(&update(Type::Insert, 1, &[]).entity.unwrap()),
+            vec![update(Type::Insert, 1, &[1, 2]).entity.unwrap()]
+        );
+
+        // Per-update errors: only the second update succeeded
+        let details = p4runtime::google::rpc::Status {
+            code: tonic::Code::Unknown as i32,
+            details: [6, 0]
+                .map(|canonical_code| pbjson_types::Any {
+                    value: p4_v1::Error {
+                        canonical_code,
+                        ..Default::default()
+                    }
+                    .encode_to_vec()
+                    .into(),
+                    ..Default::default()
+                })
+                .into(),
+            ..Default::default()
+        };
+        let status = tonic::Status::with_details(
+            tonic::Code::Unknown,
+            "write failed",
+            details.encode_to_vec().into(),
+        );
+        cache.record_write(
+            &[update(Type::Insert, 1, &[3]), update(Type::Insert, 3, &[3])],
+            &Err::<(), _>(status.into()),
+        );
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.is_stale());
+
+        cache.record_write(
+            &[update(Type::Insert, 4, &[4])],
+            &Err::<(), _>(tonic::Status::unavailable("down").into()),
+        );
+        assert_eq!(cache.len(), 2);
+        assert!(cache.is_stale());
+    }
+}
@@ -1,13 +1,65 @@
 //! The client wrapper for P4Runtime
 
 use log::{debug, error, info, warn};
+use p4runtime::p4::config::v1 as p4_cfg_v1;
 use p4runtime::p4::v1::{self as p4_v1, p4_runtime_client::P4RuntimeClient};
 use tokio_util::sync::CancellationToken;
 use tonic::{codegen::*, transport::Channel};
 
-use crate::{counter::Counter, digest::Digest, error::ClientError, p4info::P4Info, table::Table};
+use crate::{
+    cache::ShadowCache,
+    config::pipeline_cookie,
+    counter::Counter,
+    digest::Digest,
+    error::ClientError,
+    externs::Extern,
+    p4info::{named::NamedEntity, P4Info},
+    record::{recorded_event::Event as RecordedEvent, Recorder},
+    retry::RetryPolicy,
+    role::RoleScope,
+    stream::{
+        correlation::{ErrorCorrelator, StreamOutcome},
+        extension::{encode_any, ExtensionRegistry},
+        BroadcastHandler, DispatchPolicy, Dispatcher, StreamHandler, StreamHandlers,
+    },
+    table::Table,
+};
+
+/// How [`Client::bootstrap_pipeline`] brought up the pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipelineBootstrap {
+    /// The device already ran the expected pipeline, which was adopted
+    Adopted,
+
+    /// The pipeline was pushed to the device
+    Pushed,
+}
 
 /// P4Runtime client wrapper
+///
+/// Reads, writes and stream sends take `&self`, and the client is a cheap
+/// handle: clones share the connection, the stream channel and the P4Info, so
+/// one connection can serve many concurrent tasks. Connect and
+/// [`run`](Client::run) once, then clone.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use p4runtime_client::client::Client;
+/// # use p4runtime_client::p4runtime::p4::v1 as p4_v1;
+/// # async fn example(client: Client, batches: Vec<Vec<p4_v1::Update>>) {
+/// let tasks = batches
+///     .into_iter()
+///     .map(|updates| {
+///         let client = client.clone();
+///         tokio::spawn(async move { client.write_update_batch(updates).await })
+///     })
+///     .collect::<Vec<_>>();
+/// for task in tasks {
+///     task.await.unwrap().unwrap();
+/// }
+/// # }
+/// ```
 #[derive(Debug, Default, derive_builder::Builder)]
 #[builder(default)]
 pub struct Client {
@@ -23,19 +75,99 @@ pub struct Client {
     /// Role
     pub role: Option<p4_v1::Role>,
 
+    /// TLS options
+    ///
+    /// If set, the client connects over TLS.
+    #[cfg(feature = "tls")]
+    pub tls: Option<crate::tls::TlsOptions>,
+
+    /// P4 objects owned by the role
+    ///
+    /// If set, writes to entities outside of it are rejected locally.
+    pub role_scope: Option<RoleScope>,
+
     /// P4Info
-    pub p4info: P4Info,
+    ///
+    /// It is shared by clones of the client until one of them modifies it.
+    #[builder(setter(into))]
+    pub p4info: std::sync::Arc<P4Info>,
 
     /// stream channel buffer size
     #[builder(default = 10000)]
     pub channel_buffer_size: usize,
 
+    /// Number of sent stream requests remembered for error correlation
+    #[builder(default = 1024)]
+    pub stream_request_history: usize,
+
+    /// How long a sent stream request waits for a correlated stream error
+    #[builder(default = std::time::Duration::from_secs(1))]
+    pub stream_error_window: std::time::Duration,
+
+    /// Timeout of establishing the connection
+    pub connect_timeout: Option<std::time::Duration>,
+
+    /// Interval of HTTP/2 keepalive pings
+    ///
+    /// Keepalive detects dead links, which otherwise hang RPCs until the
+    /// TCP connection times out.
+    pub keepalive_interval: Option<std::time::Duration>,
+
+    /// How long to wait for the acknowledgement of a keepalive ping
+    pub keepalive_timeout: Option<std::time::Duration>,
+
+    /// Send keepalive pings even when no RPC is active
+    pub keepalive_while_idle: bool,
+
+    /// Deadline of Read RPCs
+    pub read_timeout: Option<std::time::Duration>,
+
+    /// Deadline of Write RPCs
+    pub write_timeout: Option<std::time::Duration>,
+
+    /// Deadline of SetForwardingPipelineConfig RPCs
+    pub pipeline_config_timeout: Option<std::time::Duration>,
+
+    /// Maximum size of a received message, 4MB by default
+    pub max_decoding_message_size: Option<usize>,
+
+    /// Maximum size of a sent message, unlimited by default
+    pub max_encoding_message_size: Option<usize>,
+
+    /// Compress requests with gzip and accept gzip-compressed responses
+    pub gzip: bool,
+
+    /// Retry policy of idempotent RPCs (Read, Capabilities and GetForwardingPipelineConfig)
+    pub retry_policy: RetryPolicy,
+
+    /// Session recorder
+    ///
+    /// If set, requests and stream messages are recorded, see [`crate::record`].
+    pub recorder: Option<Recorder>,
+
+    /// Shadow cache of installed entities
+    ///
+    /// If set, successful writes are mirrored in it, see [`crate::cache`].
+    pub cache: Option<ShadowCache>,
+
     /// cancel token
     ///
     /// This is used to cancel inner threads
     #[builder(setter(skip))]
     cancel_token: CancellationToken,
 
+    /// stream message handlers
+    #[builder(setter(skip))]
+    stream_handlers: StreamHandlers,
+
+    /// stream extension message handlers
+    #[builder(setter(skip))]
+    extensions: ExtensionRegistry,
+
+    /// stream error correlator
+    #[builder(setter(skip))]
+    correlator: Option<std::sync::Arc<ErrorCorrelator>>,
+
     /// stream message request sender
     #[builder(setter(skip))]
     stream_message_sender: Option<tokio::sync::mpsc::Sender<p4_v1::StreamMessageRequest>>,
@@ -56,6 +188,51 @@ pub struct Client {
     error_rx: Option<tokio::sync::broadcast::Receiver<p4_v1::StreamError>>,
 }
 
+/// Clones share the connection, the stream channel and the stream handlers
+///
+/// Each clone receives the stream messages arriving after it was created.
+/// Quitting any clone stops the stream channel of all of them.
+impl Clone for Client {
+    fn clone(&self) -> Self {
+        Client {
+            p4rt_client: self.p4rt_client.clone(),
+            device_id: self.device_id,
+            election_id: self.election_id,
+            role: self.role.clone(),
+            #[cfg(feature = "tls")]
+            tls: self.tls.clone(),
+            role_scope: self.role_scope.clone(),
+            p4info: self.p4info.clone(),
+            channel_buffer_size: self.channel_buffer_size,
+            stream_request_history: self.stream_request_history,
+            stream_error_window: self.stream_error_window,
+            connect_timeout: self.connect_timeout,
+            keepalive_interval: self.keepalive_interval,
+            keepalive_timeout: self.keepalive_timeout,
+            keepalive_while_idle: self.keepalive_while_idle,
+            read_timeout: self.read_timeout,
+            write_timeout: self.write_timeout,
+            pipeline_config_timeout: self.pipeline_config_timeout,
+            max_decoding_message_size: self.max_decoding_message_size,
+            max_encoding_message_size: self.max_encoding_message_size,
+            gzip: self.gzip,
+            retry_policy: self.retry_policy.clone(),
+            recorder: self.recorder.clone(),
+            cache: self.cache.clone(),
+            cancel_token: self.cancel_token.clone(),
+            stream_handlers: self.stream_handlers.clone(),
+            extensions: self.extensions.clone(),
+            correlator: self.correlator.clone(),
+            stream_message_sender: self.stream_message_sender.clone(),
+            arbitration_rx: self.subscribe_arbitration(),
+            packet_rx: self.subscribe_packet_in(),
+            digest_rx: self.subscribe_digest(),
+            idle_timeout_rx: self.subscribe_idle_timeout(),
+            error_rx: self.subscribe_error(),
+        }
+    }
+}
+
 impl Client {
     /// Create the client builder
     pub fn builder() -> ClientBuilder {
@@ -68,11 +245,62 @@ impl Client {
         D: TryInto<tonic::transport::Endpoint>,
         D::Error: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
     {
-        let p4rt_client = P4RuntimeClient::connect(dst).await?;
-        self.p4rt_client = Some(p4rt_client);
+        let endpoint = tonic::transport::Endpoint::new(dst)?;
+
+        #[cfg(feature = "tls")]
+        let channel = match &self.tls {
+            Some(tls) => {
+                let endpoint = self.configure_endpoint(tls.prepare(endpoint)?);
+                tls.connect(endpoint).await?
+            }
+            None => self.configure_endpoint(endpoint).connect().await?,
+        };
+        #[cfg(not(feature = "tls"))]
+        let channel = self.configure_endpoint(endpoint).connect().await?;
+
+        self.connect_with_channel(channel);
         Ok(())
     }
 
+    /// Use an established channel to the P4Runtime server
+    ///
+    /// The message size and compression options are applied, the connection
+    /// options are not.
+    pub fn connect_with_channel(&mut self, channel: Channel) {
+        let mut p4rt_client = P4RuntimeClient::new(channel);
+        if let Some(size) = self.max_decoding_message_size {
+            p4rt_client = p4rt_client.max_decoding_message_size(size);
+        }
+        if let Some(size) = self.max_encoding_message_size {
+            p4rt_client = p4rt_client.max_encoding_message_size(size);
+        }
+        if self.gzip {
+            p4rt_client = p4rt_client
+                .send_compressed(tonic::codec::CompressionEncoding::Gzip)
+                .accept_compressed(tonic::codec::CompressionEncoding::Gzip);
+        }
+
+        self.p4rt_client = Some(p4rt_client);
+    }
+
+    /// Apply the connection options to an endpoint
+    fn configure_endpoint(
+        &self,
+        mut endpoint: tonic::transport::Endpoint,
+    ) -> tonic::transport::Endpoint {
+        if let Some(timeout) = self.connect_timeout {
+            endpoint = endpoint.connect_timeout(timeout);
+        }
+        if let Some(interval) = self.keepalive_interval {
+            endpoint = endpoint.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.keepalive_timeout {
+            endpoint = endpoint.keep_alive_timeout(timeout);
+        }
+
+        endpoint.keep_alive_while_idle(self.keepalive_while_idle)
+    }
+
     /// Get the role name of this client
     pub fn role_name(&self) -> Option<String> {
         self.role.as_ref().map(|r| r.name.clone())
@@ -84,8 +312,10 @@ impl Client {
     }
 
     /// Get the mutable p4info helper
+    ///
+    /// The P4Info is copied first if it is shared with clones of the client.
     pub fn p4info_mut(&mut self) -> &mut P4Info {
-        &mut self.p4info
+        std::sync::Arc::make_mut(&mut self.p4info)
     }
 
     /// Get the table helper
@@ -94,6 +324,7 @@ impl Client {
     }
 
     /// Get the mutable table helper
+    #[deprecated(note = "operations take `&self`, use `table()` instead")]
     pub fn table_mut(&mut self) -> Table<&mut Self> {
         Table::new(self)
     }
@@ -104,6 +335,7 @@ impl Client {
     }
 
     /// Get the mutable counter helper
+    #[deprecated(note = "operations take `&self`, use `counter()` instead")]
     pub fn counter_mut(&mut self) -> Counter<&mut Self> {
         Counter::new(self)
     }
@@ -114,12 +346,24 @@ impl Client {
     }
 
     /// Get the mutable digest helper
+    #[deprecated(note = "operations take `&self`, use `digest()` instead")]
     pub fn digest_mut(&mut self) -> Digest<&mut Self> {
         Digest::new(self)
     }
 
+    /// Get the extern helper
+    pub fn externs(&self) -> Extern<&Self> {
+        Extern::new(self)
+    }
+
+    /// Get the mutable extern helper
+    #[deprecated(note = "operations take `&self`, use `externs()` instead")]
+    pub fn externs_mut(&mut self) -> Extern<&mut Self> {
+        Extern::new(self)
+    }
+
     /// Quit the client
-    pub async fn quit(&mut self) {
+    pub async fn quit(&self) {
         info!("Quitting P4Runtime client");
         self.cancel_token.cancel();
     }
@@ -129,117 +373,175 @@ impl Client {
         self.cancel_token.is_cancelled()
     }
 
+    /// Register a handler for stream channel messages
+    ///
+    /// Handlers must be registered before [`run`](Client::run), they are
+    /// invoked in registration order after the built-in subscription channels.
+    pub fn register_handler(&mut self, handler: impl StreamHandler, policy: DispatchPolicy) {
+        self.stream_handlers
+            .register(std::sync::Arc::new(handler), policy);
+    }
+
+    /// Get the stream extension registry
+    pub fn extensions(&self) -> &ExtensionRegistry {
+        &self.extensions
+    }
+
+    /// Get the mutable stream extension registry
+    ///
+    /// Extension handlers must be registered before [`run`](Client::run).
+    pub fn extensions_mut(&mut self) -> &mut ExtensionRegistry {
+        &mut self.extensions
+    }
+
     /// Send a stream message request
+    ///
+    /// Returns `NotRunning` before [`run`](Client::run).
+    ///
+    /// `PacketOut` and `DigestListAck` requests are remembered so that a
+    /// `StreamError` reported for them can be correlated. The returned
+    /// [`StreamOutcome`] resolves to that error, or to `Ok(())` if none is
+    /// reported within [`stream_error_window`](Client::stream_error_window).
     pub async fn send_message_request(
-        &mut self,
+        &self,
         request: p4_v1::StreamMessageRequest,
-    ) -> Result<(), ClientError> {
-        Ok(self
+    ) -> Result<StreamOutcome, ClientError> {
+        let sender = self
             .stream_message_sender
             .as_ref()
-            .unwrap()
-            .send(request)
-            .await?)
+            .ok_or(ClientError::NotRunning)?;
+
+        let outcome = match self.correlator.as_ref() {
+            Some(correlator) => correlator.track(&request),
+            None => StreamOutcome::ready(),
+        };
+
+        let recorded = self.recorder.as_ref().map(|_| request.clone());
+        if let Err(e) = sender.send(request).await {
+            // Don't leave the request pending in the correlator
+            drop(outcome);
+            if let Some(correlator) = &self.correlator {
+                correlator.prune();
+            }
+            return Err(e.into());
+        }
+        if let (Some(recorder), Some(request)) = (&self.recorder, recorded) {
+            recorder.record(RecordedEvent::StreamRequest(request));
+        }
+
+        Ok(outcome)
+    }
+
+    /// Send a packet out message
+    ///
+    /// The returned [`StreamOutcome`] resolves to the `StreamError` reported
+    /// by the server for this packet, if any.
+    pub async fn send_packet_out(
+        &self,
+        packet: p4_v1::PacketOut,
+    ) -> Result<StreamOutcome, ClientError> {
+        let req = p4_v1::StreamMessageRequest {
+            update: Some(p4_v1::stream_message_request::Update::Packet(packet)),
+        };
+
+        self.send_message_request(req).await
+    }
+
+    /// Send an architecture-specific stream message
+    ///
+    /// The message is packed into a `google.protobuf.Any` with the given type
+    /// URL.
+    pub async fn send_extension<M: prost::Message>(
+        &self,
+        type_url: impl Into<String>,
+        message: &M,
+    ) -> Result<StreamOutcome, ClientError> {
+        let req = p4_v1::StreamMessageRequest {
+            update: Some(p4_v1::stream_message_request::Update::Other(encode_any(
+                type_url, message,
+            ))),
+        };
+
+        self.send_message_request(req).await
     }
 
     /// Subscribe to arbitration updates
+    ///
+    /// Returns `None` before [`run`](Client::run).
     pub fn subscribe_arbitration(
         &self,
-    ) -> tokio::sync::broadcast::Receiver<p4_v1::MasterArbitrationUpdate> {
-        self.arbitration_rx.as_ref().unwrap().resubscribe()
+    ) -> Option<tokio::sync::broadcast::Receiver<p4_v1::MasterArbitrationUpdate>> {
+        self.arbitration_rx.as_ref().map(|rx| rx.resubscribe())
     }
 
-    /// Get an arbitration
+    /// Get an arbitration update, waiting at most `timeout`
     pub async fn get_arbitration(
         &mut self,
-        timeout: u64,
+        timeout: std::time::Duration,
     ) -> Result<p4_v1::MasterArbitrationUpdate, ClientError> {
-        tokio::select! {
-            _ = tokio::time::sleep(tokio::time::Duration::from_secs(timeout)) => {
-                Err(ClientError::Timeout)
-            }
-
-            arb = self.arbitration_rx.as_mut().unwrap().recv() => {
-                Ok(arb.unwrap())
-            }
-        }
+        recv_timeout(self.arbitration_rx.as_mut(), timeout).await
     }
 
     /// Subscribe to packet in messages
-    pub fn subscribe_packet_in(&self) -> tokio::sync::broadcast::Receiver<p4_v1::PacketIn> {
-        self.packet_rx.as_ref().unwrap().resubscribe()
+    ///
+    /// Returns `None` before [`run`](Client::run).
+    pub fn subscribe_packet_in(&self) -> Option<tokio::sync::broadcast::Receiver<p4_v1::PacketIn>> {
+        self.packet_rx.as_ref().map(|rx| rx.resubscribe())
     }
 
-    /// Get a packet in message
-    pub async fn get_packet_in(&mut self, timeout: u64) -> Result<p4_v1::PacketIn, ClientError> {
-        tokio::select! {
-            _ = tokio::time::sleep(tokio::time::Duration::from_secs(timeout)) => {
-                Err(ClientError::Timeout)
-            }
-
-            packet = self.packet_rx.as_mut().unwrap().recv() => {
-                Ok(packet.unwrap())
-            }
-        }
+    /// Get a packet in message, waiting at most `timeout`
+    pub async fn get_packet_in(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> Result<p4_v1::PacketIn, ClientError> {
+        recv_timeout(self.packet_rx.as_mut(), timeout).await
     }
 
     /// Subscribe to digest messages
-    pub fn subscribe_digest(&self) -> tokio::sync::broadcast::Receiver<p4_v1::DigestList> {
-        self.digest_rx.as_ref().unwrap().resubscribe()
+    ///
+    /// Returns `None` before [`run`](Client::run).
+    pub fn subscribe_digest(&self) -> Option<tokio::sync::broadcast::Receiver<p4_v1::DigestList>> {
+        self.digest_rx.as_ref().map(|rx| rx.resubscribe())
     }
 
-    /// Get a digest message
-    pub async fn get_digest(&mut self, timeout: u64) -> Result<p4_v1::DigestList, ClientError> {
-        tokio::select! {
-            _ = tokio::time::sleep(tokio::time::Duration::from_secs(timeout)) => {
-                Err(ClientError::Timeout)
-            }
-
-            digest = self.digest_rx.as_mut().unwrap().recv() => {
-                Ok(digest.unwrap())
-            }
-        }
+    /// Get a digest message, waiting at most `timeout`
+    pub async fn get_digest(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> Result<p4_v1::DigestList, ClientError> {
+        recv_timeout(self.digest_rx.as_mut(), timeout).await
     }
 
     /// Subscribe to idle timeout notifications
+    ///
+    /// Returns `None` before [`run`](Client::run).
     pub fn subscribe_idle_timeout(
         &self,
-    ) -> tokio::sync::broadcast::Receiver<p4_v1::IdleTimeoutNotification> {
-        self.idle_timeout_rx.as_ref().unwrap().resubscribe()
+    ) -> Option<tokio::sync::broadcast::Receiver<p4_v1::IdleTimeoutNotification>> {
+        self.idle_timeout_rx.as_ref().map(|rx| rx.resubscribe())
     }
 
-    /// Get an idle timeout notification
+    /// Get an idle timeout notification, waiting at most `timeout`
     pub async fn get_idle_timeout(
         &mut self,
-        timeout: u64,
+        timeout: std::time::Duration,
     ) -> Result<p4_v1::IdleTimeoutNotification, ClientError> {
-        tokio::select! {
-            _ = tokio::time::sleep(tokio::time::Duration::from_secs(timeout)) => {
-                Err(ClientError::Timeout)
-            }
-
-            idle_timeout = self.idle_timeout_rx.as_mut().unwrap().recv() => {
-                Ok(idle_timeout.unwrap())
-            }
-        }
+        recv_timeout(self.idle_timeout_rx.as_mut(), timeout).await
     }
 
     /// Subscribe to stream errors
-    pub fn subscribe_error(&self) -> tokio::sync::broadcast::Receiver<p4_v1::StreamError> {
-        self.error_rx.as_ref().unwrap().resubscribe()
+    ///
+    /// Returns `None` before [`run`](Client::run).
+    pub fn subscribe_error(&self) -> Option<tokio::sync::broadcast::Receiver<p4_v1::StreamError>> {
+        self.error_rx.as_ref().map(|rx| rx.resubscribe())
     }
 
-    /// Get a stream error
-    pub async fn get_error(&mut self, timeout: u64) -> Result<p4_v1::StreamError, ClientError> {
-        tokio::select! {
-            _ = tokio::time::sleep(tokio::time::Duration::from_secs(timeout)) => {
-                Err(ClientError::Timeout)
-            }
-
-            error = self.error_rx.as_mut().unwrap().recv() => {
-                Ok(error.unwrap())
-            }
-        }
+    /// Get a stream error, waiting at most `timeout`
+    pub async fn get_error(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> Result<p4_v1::StreamError, ClientError> {
+        recv_timeout(self.error_rx.as_mut(), timeout).await
     }
 
     /// Rund the client by preparing channels and sending arbitration
@@ -258,6 +560,12 @@ impl Client {
             tokio::sync::mpsc::channel(self.channel_buffer_size);
         self.stream_message_sender = Some(stream_request_sender);
 
+        let correlator = std::sync::Arc::new(ErrorCorrelator::new(
+            self.stream_request_history,
+            self.stream_error_window,
+        ));
+        self.correlator = Some(correlator.clone());
+
         debug!("Sending arbitration request");
 
         // start arbitration
@@ -301,19 +609,44 @@ impl Client {
         self.digest_rx = Some(digest_rx);
         self.idle_timeout_rx = Some(idle_timeout_rx);
         self.error_rx = Some(error_rx);
-        self.set_up_stream_message_channel(
-            channel,
-            arbitration_tx,
-            packet_tx,
-            digest_tx,
-            idle_timeout_tx,
-            error_tx,
+
+        let mut handlers = StreamHandlers::default();
+        handlers.register(correlator, DispatchPolicy::Inline);
+        handlers.register(
+            std::sync::Arc::new(BroadcastHandler {
+                arbitration_tx,
+                packet_tx,
+                digest_tx,
+                idle_timeout_tx,
+                error_tx,
+            }),
+            DispatchPolicy::Inline,
+        );
+        handlers.register(
+            std::sync::Arc::new(self.extensions.clone()),
+            DispatchPolicy::Inline,
         );
+        if let Some(recorder) = &self.recorder {
+            handlers.register(
+                std::sync::Arc::new(recorder.clone()),
+                DispatchPolicy::Inline,
+            );
+        }
+        for (handler, policy) in self.stream_handlers.iter() {
+            handlers.register(handler.clone(), *policy);
+        }
+        let dispatcher = Dispatcher::new(&handlers, &self.cancel_token);
+        self.set_up_stream_message_channel(channel, dispatcher);
 
         // Check if arbitration is successful
-        let res = self.get_arbitration(5).await?;
+        let res = self
+            .get_arbitration(std::time::Duration::from_secs(5))
+            .await?;
         if let Some(status) = res.status {
             if status.code == p4runtime::google::rpc::Code::Ok as i32 {
+                if let Err(e) = self.resync_cache().await {
+                    warn!("Failed to resync the shadow cache: {}", e);
+                }
                 Ok(())
             } else {
                 Err(ClientError::ArbitrationFailed)
@@ -326,11 +659,7 @@ impl Client {
     fn set_up_stream_message_channel(
         &mut self,
         mut channel: tonic::codec::Streaming<p4_v1::StreamMessageResponse>,
-        arbitration_tx: tokio::sync::broadcast::Sender<p4_v1::MasterArbitrationUpdate>,
-        packet_tx: tokio::sync::broadcast::Sender<p4_v1::PacketIn>,
-        digest_tx: tokio::sync::broadcast::Sender<p4_v1::DigestList>,
-        idle_timeout_tx: tokio::sync::broadcast::Sender<p4_v1::IdleTimeoutNotification>,
-        error_tx: tokio::sync::broadcast::Sender<p4_v1::StreamError>,
+        dispatcher: Dispatcher,
     ) {
         let cancel_token = self.cancel_token.clone();
 
@@ -344,31 +673,9 @@ impl Client {
                     msg = channel.message() => {
                         match msg {
                             Ok(Some(res)) => {
-                                use p4_v1::stream_message_response::Update;
-
-                                // TODO: check if the update could be none
-                                let update = res.update.unwrap();
-
-                                // TODO: handle send error
-                                match update {
-                                    Update::Arbitration(arb) => {
-                                        arbitration_tx.send(arb).unwrap();
-                                    }
-                                    Update::Packet(packet) => {
-                                        packet_tx.send(packet).unwrap();
-                                    }
-                                    Update::Digest(digest) => {
-                                        digest_tx.send(digest).unwrap();
-                                    }
-                                    Update::IdleTimeoutNotification(idle_timeout) => {
-                                        idle_timeout_tx.send(idle_timeout).unwrap();
-                                    }
-                                    Update::Error(error) => {
-                                        error_tx.send(error).unwrap();
-                                    }
-                                    _ => {
-                                        warn!("Received unsupported stream message update: {:?}", update);
-                                    }
+                                match res.update {
+                                    Some(update) => dispatcher.dispatch(update).await,
+                                    None => warn!("Received empty stream message"),
                                 }
                             }
 
@@ -390,21 +697,31 @@ impl Client {
     }
 
     /// Get the capabilities
-    pub async fn capabilities(&mut self) -> Result<p4_v1::CapabilitiesResponse, ClientError> {
-        let req = p4_v1::CapabilitiesRequest {};
-
-        Ok(self
+    pub async fn capabilities(&self) -> Result<p4_v1::CapabilitiesResponse, ClientError> {
+        // tonic clients share the underlying channel, so clones are cheap
+        let p4rt_client = self
             .p4rt_client
-            .as_mut()
-            .ok_or(ClientError::MissingP4rtClient)?
-            .capabilities(req)
-            .await?
-            .into_inner())
+            .clone()
+            .ok_or(ClientError::MissingP4rtClient)?;
+
+        let res = self
+            .retry_policy
+            .run(|| {
+                let mut p4rt_client = p4rt_client.clone();
+                async move {
+                    Ok(p4rt_client
+                        .capabilities(p4_v1::CapabilitiesRequest {})
+                        .await?)
+                }
+            })
+            .await?;
+
+        Ok(res.into_inner())
     }
 
     /// Get the forwarding pipeline config
     pub async fn get_forwarding_pipeline_config(
-        &mut self,
+        &self,
         response_type: p4_v1::get_forwarding_pipeline_config_request::ResponseType,
     ) -> Result<tonic::Response<p4_v1::GetForwardingPipelineConfigResponse>, ClientError> {
         let req = p4_v1::GetForwardingPipelineConfigRequest {
@@ -412,16 +729,25 @@ impl Client {
             response_type: response_type as i32,
         };
 
-        Ok(self
+        // tonic clients share the underlying channel, so clones are cheap
+        let p4rt_client = self
             .p4rt_client
-            .as_mut()
-            .ok_or(ClientError::MissingP4rtClient)?
-            .get_forwarding_pipeline_config(req)
-            .await?)
+            .clone()
+            .ok_or(ClientError::MissingP4rtClient)?;
+
+        self.retry_policy
+            .run(|| {
+                let mut p4rt_client = p4rt_client.clone();
+                async move { Ok(p4rt_client.get_forwarding_pipeline_config(req).await?) }
+            })
+            .await
     }
 
     /// Set the forwarding pipeline config
     ///
+    /// The config is verified and committed in one step, and its cookie is
+    /// computed by [`pipeline_cookie`](crate::config::pipeline_cookie).
+    ///
     /// # Arguments
     ///
     /// - `p4_device_config`: The P4 device config
@@ -429,58 +755,216 @@ impl Client {
     ///   - For bmv2, this is the output JSON file
     ///   - For Tofino, this can be built via [`build_tofino_config`](crate::config::build_tofino_config)
     pub async fn set_forwarding_pipeline_config(
-        &mut self,
+        &self,
         p4_device_config: Vec<u8>,
     ) -> Result<tonic::Response<p4_v1::SetForwardingPipelineConfigResponse>, ClientError> {
+        let cookie = pipeline_cookie(self.p4info().as_ref(), &p4_device_config);
+
+        self.set_forwarding_pipeline_config_with(
+            p4_v1::set_forwarding_pipeline_config_request::Action::VerifyAndCommit,
+            Some(p4_device_config),
+            cookie,
+        )
+        .await
+    }
+
+    /// Set the forwarding pipeline config with an explicit action and cookie
+    ///
+    /// # Arguments
+    ///
+    /// - `action`: What the server should do with the config
+    ///   - `Verify` only checks the config
+    ///   - `VerifyAndSave` checks and stages the config without changing forwarding state
+    ///   - `VerifyAndCommit` checks and realizes the config, clearing forwarding state
+    ///   - `Commit` realizes the staged config
+    ///   - `ReconcileAndCommit` checks and realizes the config, preserving forwarding state where possible
+    /// - `p4_device_config`: The P4 device config, sent along with the loaded P4Info
+    ///   - Pass `None` to send no config, e.g. for `Commit`
+    /// - `cookie`: The cookie identifying the config
+    ///   - It is returned by [`get_forwarding_pipeline_config`](Client::get_forwarding_pipeline_config)
+    ///   - [`pipeline_cookie`](crate::config::pipeline_cookie) computes one from the config
+    pub async fn set_forwarding_pipeline_config_with(
+        &self,
+        action: p4_v1::set_forwarding_pipeline_config_request::Action,
+        p4_device_config: Option<Vec<u8>>,
+        cookie: u64,
+    ) -> Result<tonic::Response<p4_v1::SetForwardingPipelineConfigResponse>, ClientError> {
+        let config = p4_device_config.map(|p4_device_config| p4_v1::ForwardingPipelineConfig {
+            p4info: Some(self.p4info().as_ref().clone()),
+            p4_device_config,
+            cookie: Some(p4_v1::forwarding_pipeline_config::Cookie { cookie }),
+        });
+
         let req = p4_v1::SetForwardingPipelineConfigRequest {
             device_id: self.device_id,
             role: self.role_name().unwrap_or_default(),
             election_id: Some(self.election_id),
-            action: p4_v1::set_forwarding_pipeline_config_request::Action::VerifyAndCommit as i32,
-            config: Some(p4_v1::ForwardingPipelineConfig {
-                p4info: Some(self.p4info.as_ref().clone()),
-                p4_device_config,
-                cookie: Some(p4_v1::forwarding_pipeline_config::Cookie { cookie: 0 }),
-            }),
+            action: action as i32,
+            config,
 
             ..Default::default()
         };
+        if let Some(recorder) = &self.recorder {
+            recorder.record(RecordedEvent::SetPipeline(req.clone()));
+        }
 
-        Ok(self
+        let timeout = self.pipeline_config_timeout;
+        let mut p4rt_client = self
             .p4rt_client
-            .as_mut()
-            .ok_or(ClientError::MissingP4rtClient)?
-            .set_forwarding_pipeline_config(req)
-            .await?)
+            .clone()
+            .ok_or(ClientError::MissingP4rtClient)?;
+
+        let res = with_deadline(
+            timeout,
+            p4rt_client.set_forwarding_pipeline_config(with_timeout(req, timeout)),
+        )
+        .await;
+
+        if let (Some(cache), Ok(_)) = (&self.cache, &res) {
+            use p4_v1::set_forwarding_pipeline_config_request::Action;
+
+            match action {
+                Action::VerifyAndCommit | Action::Commit => cache.clear(),
+                Action::ReconcileAndCommit => cache.mark_stale(),
+                _ => {}
+            }
+        }
+
+        res
+    }
+
+    /// Bring up a pipeline, adopting the one installed on the device if it
+    /// matches
+    ///
+    /// The installed P4Info and cookie are fetched first. If the cookie equals
+    /// the one computed by [`pipeline_cookie`](crate::config::pipeline_cookie)
+    /// for the expected pipeline, the installed P4Info is loaded and nothing is
+    /// pushed, preserving forwarding state across controller restarts.
+    /// Otherwise the expected P4Info is loaded and the pipeline is pushed with
+    /// `VerifyAndCommit`.
+    pub async fn bootstrap_pipeline(
+        &mut self,
+        p4info: p4_cfg_v1::P4Info,
+        p4_device_config: Vec<u8>,
+    ) -> Result<PipelineBootstrap, ClientError> {
+        let cookie = pipeline_cookie(&p4info, &p4_device_config);
+        self.bootstrap_pipeline_with_cookie(p4info, p4_device_config, cookie)
+            .await
+    }
+
+    /// Bring up a pipeline identified by a caller-supplied cookie, adopting
+    /// the one installed on the device if the cookie matches
+    ///
+    /// See [`bootstrap_pipeline`](Client::bootstrap_pipeline).
+    pub async fn bootstrap_pipeline_with_cookie(
+        &mut self,
+        p4info: p4_cfg_v1::P4Info,
+        p4_device_config: Vec<u8>,
+        cookie: u64,
+    ) -> Result<PipelineBootstrap, ClientError> {
+        use p4_v1::get_forwarding_pipeline_config_request::ResponseType;
+        use tonic::Code;
+
+        let installed = match self
+            .get_forwarding_pipeline_config(ResponseType::P4infoAndCookie)
+            .await
+        {
+            Ok(res) => res.into_inner().config,
+            // No pipeline has been set yet
+            Err(ClientError::Status(status))
+                if matches!(status.code(), Code::FailedPrecondition | Code::NotFound) =>
+            {
+                None
+            }
+            Err(e) => return Err(e),
+        };
+
+        if let Some(installed) = installed {
+            if installed.cookie.map(|c| c.cookie) == Some(cookie) {
+                info!("Adopting installed pipeline with cookie {:#x}", cookie);
+                self.p4info_mut().load(installed.p4info.unwrap_or(p4info));
+                return Ok(PipelineBootstrap::Adopted);
+            }
+        }
+
+        info!("Pushing pipeline with cookie {:#x}", cookie);
+        self.p4info_mut().load(p4info);
+        self.set_forwarding_pipeline_config_with(
+            p4_v1::set_forwarding_pipeline_config_request::Action::VerifyAndCommit,
+            Some(p4_device_config),
+            cookie,
+        )
+        .await?;
+
+        Ok(PipelineBootstrap::Pushed)
+    }
+
+    /// Commit the forwarding pipeline config saved by a previous
+    /// `VerifyAndSave` action
+    pub async fn commit_forwarding_pipeline_config(
+        &self,
+    ) -> Result<tonic::Response<p4_v1::SetForwardingPipelineConfigResponse>, ClientError> {
+        self.set_forwarding_pipeline_config_with(
+            p4_v1::set_forwarding_pipeline_config_request::Action::Commit,
+            None,
+            0,
+        )
+        .await
     }
 
     /// Write a batch of updates
     #[inline]
     pub async fn write_update_batch(
-        &mut self,
+        &self,
         updates: Vec<p4_v1::Update>,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
+        self.write_update_batch_with(updates, p4_v1::write_request::Atomicity::ContinueOnError)
+            .await
+    }
+
+    /// Write a batch of updates with the given atomicity
+    pub async fn write_update_batch_with(
+        &self,
+        updates: Vec<p4_v1::Update>,
+        atomicity: p4_v1::write_request::Atomicity,
+    ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
+        if let Some(role_scope) = &self.role_scope {
+            role_scope.check_updates(&updates)?;
+        }
+
         let req = p4_v1::WriteRequest {
             device_id: self.device_id,
             role: self.role_name().unwrap_or_default(),
             election_id: Some(self.election_id),
             updates,
+            atomicity: atomicity as i32,
 
             ..Default::default()
         };
+        if let Some(recorder) = &self.recorder {
+            recorder.record(RecordedEvent::Write(req.clone()));
+        }
 
-        Ok(self
+        let timeout = self.write_timeout;
+        let mut p4rt_client = self
             .p4rt_client
-            .as_mut()
-            .ok_or(ClientError::MissingP4rtClient)?
-            .write(req)
-            .await?)
+            .clone()
+            .ok_or(ClientError::MissingP4rtClient)?;
+
+        let updates = self.cache.as_ref().map(|_| req.updates.clone());
+        let res = with_deadline(timeout, p4rt_client.write(with_timeout(req, timeout))).await;
+
+        if let (Some(cache), Some(updates)) = (&self.cache, updates) {
+            cache.record_write(&updates, &res);
+        }
+
+        res
     }
 
     /// Write a single update
     #[inline]
     pub async fn write_update(
-        &mut self,
+        &self,
         update: p4_v1::Update,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         self.write_update_batch(vec![update]).await
@@ -489,7 +973,7 @@ impl Client {
     /// Read a batch of entities, returning a stream of responses
     #[inline]
     pub async fn read_entity_stream_batch(
-        &mut self,
+        &self,
         entities: Vec<p4_v1::Entity>,
     ) -> Result<tonic::Response<tonic::codec::Streaming<p4_v1::ReadResponse>>, ClientError> {
         let req = p4_v1::ReadRequest {
@@ -497,19 +981,29 @@ impl Client {
             role: self.role_name().unwrap_or_default(),
             entities,
         };
+        if let Some(recorder) = &self.recorder {
+            recorder.record(RecordedEvent::Read(req.clone()));
+        }
 
-        Ok(self
+        let timeout = self.read_timeout;
+        let p4rt_client = self
             .p4rt_client
-            .as_mut()
-            .ok_or(ClientError::MissingP4rtClient)?
-            .read(req)
-            .await?)
+            .clone()
+            .ok_or(ClientError::MissingP4rtClient)?;
+
+        self.retry_policy
+            .run(|| {
+                let mut p4rt_client = p4rt_client.clone();
+                let req = with_timeout(req.clone(), timeout);
+                async move { with_deadline(timeout, p4rt_client.read(req)).await }
+            })
+            .await
     }
 
     /// Read a single entity, returning a stream of responses
     #[inline]
     pub async fn read_entity_stream(
-        &mut self,
+        &self,
         entity: p4_v1::Entity,
     ) -> Result<tonic::Response<tonic::codec::Streaming<p4_v1::ReadResponse>>, ClientError> {
         self.read_entity_stream_batch(vec![entity]).await
@@ -518,7 +1012,7 @@ impl Client {
     /// Read exactly one entity
     #[inline]
     pub async fn read_entity_single(
-        &mut self,
+        &self,
         entity: p4_v1::Entity,
     ) -> Result<p4_v1::Entity, ClientError> {
         let mut stream = self.read_entity_stream(entity).await?.into_inner();
@@ -539,7 +1033,7 @@ impl Client {
     /// Read all entities
     #[inline]
     pub async fn read_entities(
-        &mut self,
+        &self,
         entity: p4_v1::Entity,
     ) -> Result<Vec<p4_v1::Entity>, ClientError> {
         let mut stream = self.read_entity_stream(entity).await?.into_inner();
@@ -556,7 +1050,7 @@ impl Client {
     /// Read a batch of entities
     #[inline]
     pub async fn read_entities_batch(
-        &mut self,
+        &self,
         entities: Vec<p4_v1::Entity>,
     ) -> Result<Vec<p4_v1::Entity>, ClientError> {
         let mut stream = self.read_entity_stream_batch(entities).await?.into_inner();
@@ -569,4 +1063,144 @@ impl Client {
 
         Ok(entities)
     }
+
+    /// Replace the shadow cache content with the entities installed on the
+    /// device
+    ///
+    /// Does nothing if no cache is set. If the entities can't be read, the
+    /// cache is marked stale.
+    pub async fn resync_cache(&self) -> Result<(), ClientError> {
+        let Some(cache) = &self.cache else {
+            return Ok(());
+        };
+
+        let entities = match self
+            .read_entities_batch(ShadowCache::resync_entities())
+            .await
+        {
+            Ok(entities) => entities,
+            Err(e) => {
+                cache.mark_stale();
+                return Err(e);
+            }
+        };
+        cache.replace(entities);
+
+        Ok(())
+    }
+
+    /// Get the cached entries of a table by name
+    ///
+    /// Returns `None` if no cache is set or the table is unknown.
+    pub fn cached_table_entries(&self, table_name: &str) -> Option<Vec<p4_v1::TableEntry>> {
+        let table_id = self.p4info().table_id(table_name);
+        if table_id == 0 {
+            return None;
+        }
+
+        self.cache.as_ref().map(|c| c.table_entries(table_id))
+    }
+
+    /// Get the cached entry with the key of an entry
+    ///
+    /// Returns `None` if no cache is set or the entry is not cached.
+    pub fn cached_table_entry(&self, entry: &p4_v1::TableEntry) -> Option<p4_v1::TableEntry> {
+        self.cache.as_ref()?.table_entry(entry)
+    }
+
+    /// Read the installed entities in their name-based form
+    ///
+    /// Reads the entities mirrored by the shadow cache, see
+    /// [`named`](crate::p4info::named). Const table entries are flagged with
+    /// `is_const`.
+    pub async fn dump_entities(&self) -> Result<Vec<NamedEntity>, ClientError> {
+        let entities = self
+            .read_entities_batch(ShadowCache::resync_entities())
+            .await?;
+
+        Ok(self.p4info().to_named(&entities)?)
+    }
+
+    /// Install entities in their name-based form
+    ///
+    /// Entities are written in dependency order, one batch per kind: PRE
+    /// entries, action profile members, groups, value sets and table entries.
+    /// Default table entries and value sets are modified, other entities are
+    /// inserted. Const table entries are part of the P4 program and skipped.
+    pub async fn load_entities(&self, entities: &[NamedEntity]) -> Result<(), ClientError> {
+        use p4_v1::update::Type;
+
+        let rank = |entity: &NamedEntity| match entity {
+            NamedEntity::MulticastGroup(_) | NamedEntity::CloneSession(_) => 0,
+            NamedEntity::ActionProfileMember(_) => 1,
+            NamedEntity::ActionProfileGroup(_) => 2,
+            NamedEntity::ValueSetEntry(_) => 3,
+            NamedEntity::TableEntry(_) => 4,
+        };
+
+        let mut batches: [Vec<p4_v1::Update>; 5] = Default::default();
+        for (named, entity) in entities.iter().zip(self.p4info().from_named(entities)?) {
+            if matches!(named, NamedEntity::TableEntry(e) if e.is_const) {
+                continue;
+            }
+            let r#type = match named {
+                NamedEntity::TableEntry(e) if e.is_default_action => Type::Modify,
+                NamedEntity::ValueSetEntry(_) => Type::Modify,
+                _ => Type::Insert,
+            };
+            batches[rank(named)].push(p4_v1::Update {
+                r#type: r#type as i32,
+                entity: Some(entity),
+            });
+        }
+
+        for updates in batches.into_iter().filter(|b| !b.is_empty()) {
+            self.write_update_batch(updates).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create a request carrying a gRPC deadline
+fn with_timeout<T>(message: T, timeout: Option<std::time::Duration>) -> tonic::Request<T> {
+    let mut req = tonic::Request::new(message);
+    if let Some(timeout) = timeout {
+        req.set_timeout(timeout);
+    }
+    req
+}
+
+/// Enforce a deadline on the client side as well
+///
+/// The server may ignore the gRPC deadline, or the link may be dead.
+async fn with_deadline<T>(
+    timeout: Option<std::time::Duration>,
+    rpc: impl Future<Output = Result<T, tonic::Status>>,
+) -> Result<T, ClientError> {
+    match timeout {
+        Some(timeout) => Ok(tokio::time::timeout(timeout, rpc)
+            .await
+            .map_err(|_| ClientError::Timeout)??),
+        None => Ok(rpc.await?),
+    }
+}
+
+/// Receive from a broadcast channel set up by [`Client::run`]
+///
+/// A receiver which fell behind returns `Lagged` with the number of dropped
+/// messages once, then continues with the oldest retained message.
+async fn recv_timeout<T: Clone>(
+    rx: Option<&mut tokio::sync::broadcast::Receiver<T>>,
+    timeout: std::time::Duration,
+) -> Result<T, ClientError> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let rx = rx.ok_or(ClientError::NotRunning)?;
+    match tokio::time::timeout(timeout, rx.recv()).await {
+        Ok(Ok(message)) => Ok(message),
+        Ok(Err(RecvError::Closed)) => Err(ClientError::Closed),
+        Ok(Err(RecvError::Lagged(n))) => Err(ClientError::Lagged { n }),
+        Err(_) => Err(ClientError::Timeout),
+    }
 }
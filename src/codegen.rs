@@ -0,0 +1,1190 @@
+//! Code generation of typed P4 APIs from a P4Info
+//!
+//! [`Generator`] turns a P4Info into a Rust module with, for each table, a
+//! `Key` struct of its match fields, an `Action` enum of its actions with
+//! typed parameters and an `Entry` struct, plus one struct per digest and per
+//! controller packet metadata header. They convert into the P4Runtime
+//! messages, so that programs no longer refer to P4 objects by name:
+//!
+//! ```rust,ignore
+//! use p4::tables::ipv4_lpm;
+//!
+//! let entry = ipv4_lpm::Entry {
+//!     key: ipv4_lpm::Key {
+//!         hdr_ipv4_dst_addr: Some(Lpm::new(Ipv4Addr::new(10, 0, 1, 2), 32)),
+//!     },
+//!     action: ipv4_lpm::Action::Ipv4Forward {
+//!         dst_addr: [0x08, 0x00, 0x00, 0x00, 0x01, 0x02],
+//!         port: 1,
+//!     },
+//!     priority: 0,
+//! };
+//! client.write_update(p4_v1::Update {
+//!     r#type: p4_v1::update::Type::Insert as i32,
+//!     entity: Some(entry.into()),
+//! }).await?;
+//! ```
+//!
+//! Values are unsigned integers of the smallest fitting width, or `Vec<u8>`
+//! above 128 bits or for an unknown width. Signed `int<N>` values are kept as
+//! `Vec<u8>` two's complement bitstrings. With [`DisplayHints`], addresses are
+//! typed as [`Ipv4Addr`], [`Ipv6Addr`] and `[u8; 6]` MAC addresses.
+//!
+//! # Build script
+//!
+//! ```rust,no_run
+//! // build.rs, after compiling the P4 program
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("p4.rs");
+//! p4runtime_client::codegen::generate_file("build/main.p4info.bin", out).unwrap();
+//! ```
+//!
+//! ```rust,ignore
+//! // main.rs
+//! mod p4 {
+//!     include!(concat!(env!("OUT_DIR"), "/p4.rs"));
+//! }
+//! ```
+
+use std::{
+    collections::HashSet,
+    net::{Ipv4Addr, Ipv6Addr},
+    path::Path,
+};
+
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+use p4runtime::p4::v1 as p4_v1;
+
+pub use crate::table::builder::Bitstring;
+use crate::{
+    error::P4InfoFileError,
+    p4info::{
+        display::{DisplayHints, ValueFormat},
+        P4Info,
+    },
+    utils::bit_len,
+};
+
+/// Generate the typed API module of a P4Info file
+///
+/// For build scripts: the P4Info format is guessed like
+/// [`P4Info::from_file`], and Cargo is told to rerun the script when the file
+/// changes.
+pub fn generate_file(
+    p4info_path: impl AsRef<Path>,
+    out_path: impl AsRef<Path>,
+) -> Result<(), P4InfoFileError> {
+    println!("cargo:rerun-if-changed={}", p4info_path.as_ref().display());
+    let p4info = P4Info::from_file(p4info_path)?;
+
+    Ok(std::fs::write(
+        out_path,
+        Generator::new(&p4info).generate()?,
+    )?)
+}
+
+/// Generator of typed APIs
+pub struct Generator<'a> {
+    p4info: &'a P4Info,
+    hints: DisplayHints,
+}
+
+impl<'a> Generator<'a> {
+    /// Create a generator for a P4Info
+    pub fn new(p4info: &'a P4Info) -> Self {
+        Generator {
+            p4info,
+            hints: DisplayHints::default(),
+        }
+    }
+
+    /// Type values rendered as addresses by the hints as addresses
+    pub fn hints(mut self, hints: DisplayHints) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    /// Generate the module source
+    ///
+    /// The P4Info must be loaded.
+    pub fn generate(&self) -> Result<String, P4InfoFileError> {
+        self.p4info
+            .inner()
+            .ok_or(P4InfoFileError::P4InfoNotLoaded)?;
+
+        let mut w = Writer::default();
+        w.line("// @generated by p4runtime-client from a P4Info, do not edit");
+        w.line("");
+        w.line("#[allow(unused_imports)]");
+        w.line("use ::p4runtime_client::{codegen as rt, p4runtime::p4::v1 as p4_v1};");
+
+        w.line("");
+        w.line("/// Tables");
+        w.line("#[allow(dead_code, unused_imports, unused_mut, clippy::all)]");
+        w.line("pub mod tables {");
+        w.line("use super::{p4_v1, rt};");
+        let mut names = Names::module();
+        for table in &self.p4info.tables {
+            self.table(
+                &mut w,
+                names.unique(snake(&short_name(&table.preamble))),
+                table,
+            );
+        }
+        w.line("}");
+
+        w.line("");
+        w.line("/// Digests");
+        w.line("#[allow(dead_code, unused_imports, unused_mut, clippy::all)]");
+        w.line("pub mod digests {");
+        w.line("use super::{p4_v1, rt};");
+        let mut names = Names::module();
+        for digest in &self.p4info.digests {
+            self.digest(
+                &mut w,
+                names.unique(camel(&short_name(&digest.preamble))),
+                digest,
+            );
+        }
+        w.line("}");
+
+        w.line("");
+        w.line("/// Controller packet metadata");
+        w.line("#[allow(dead_code, unused_imports, unused_mut, clippy::all)]");
+        w.line("pub mod packet_metadata {");
+        w.line("use super::{p4_v1, rt};");
+        let mut names = Names::module();
+        for header in &self.p4info.controller_packet_metadata {
+            self.packet_metadata(
+                &mut w,
+                names.unique(camel(&short_name(&header.preamble))),
+                header,
+            );
+        }
+        w.line("}");
+
+        Ok(w.out)
+    }
+
+    fn value_type(
+        &self,
+        name: &str,
+        type_name: Option<&p4_cfg_v1::P4NamedType>,
+        bitwidth: i32,
+    ) -> String {
+        if type_name.is_some_and(|t| self.is_signed_type(&t.name)) {
+            return "Vec<u8>".to_string();
+        }
+        let format = self
+            .hints
+            .format(name, type_name.map(|t| t.name.as_str()), bitwidth);
+
+        match (format, bitwidth) {
+            (ValueFormat::Ipv4, 32) => "::std::net::Ipv4Addr".to_string(),
+            (ValueFormat::Ipv6, 128) => "::std::net::Ipv6Addr".to_string(),
+            (ValueFormat::Mac, 48) => "[u8; 6]".to_string(),
+            (_, ..=0) => "Vec<u8>".to_string(),
+            (_, ..=8) => "u8".to_string(),
+            (_, ..=16) => "u16".to_string(),
+            (_, ..=32) => "u32".to_string(),
+            (_, ..=64) => "u64".to_string(),
+            (_, ..=128) => "u128".to_string(),
+            _ => "Vec<u8>".to_string(),
+        }
+    }
+
+    /// Whether a new type is a signed `int<N>`
+    fn is_signed_type(&self, name: &str) -> bool {
+        use p4_cfg_v1::p4_bitstring_like_type_spec::TypeSpec as BitstringSpec;
+        use p4_cfg_v1::p4_data_type_spec::TypeSpec;
+        use p4_cfg_v1::p4_new_type_spec::Representation;
+
+        let spec = self
+            .p4info
+            .type_info
+            .as_ref()
+            .and_then(|t| t.new_types.get(name))
+            .and_then(|t| t.representation.as_ref());
+        match spec {
+            Some(Representation::OriginalType(p4_cfg_v1::P4DataTypeSpec {
+                type_spec: Some(TypeSpec::Bitstring(b)),
+            })) => matches!(b.type_spec, Some(BitstringSpec::Int(_))),
+            _ => false,
+        }
+    }
+
+    fn table(&self, w: &mut Writer, module: String, table: &p4_cfg_v1::Table) {
+        use p4_cfg_v1::match_field::{Match, MatchType};
+
+        let preamble = table.preamble.clone().unwrap_or_default();
+        let indirect = table.implementation_id != 0;
+
+        w.line("");
+        w.line(format!("/// Table `{}`", preamble.name));
+        w.line(format!("pub mod {} {{", module));
+        w.line("use super::{p4_v1, rt};");
+        w.line("");
+        w.line("/// Table id");
+        w.line(format!("pub const ID: u32 = {};", preamble.id));
+        if indirect {
+            w.line("");
+            w.line("/// Action profile id of the table");
+            w.line(format!(
+                "pub const ACTION_PROFILE_ID: u32 = {};",
+                table.implementation_id
+            ));
+        }
+
+        // Key
+        let mut fields = Names::default();
+        let fields: Vec<_> = table
+            .match_fields
+            .iter()
+            .map(|field| {
+                let match_type = match field.r#match {
+                    Some(Match::MatchType(mt)) => MatchType::try_from(mt).ok(),
+                    _ => None,
+                };
+                (fields.unique(snake(&field.name)), field, match_type)
+            })
+            .collect();
+
+        w.line("");
+        w.line("/// Match key");
+        w.line("#[derive(Clone, Debug, PartialEq)]");
+        w.line("pub struct Key {");
+        for (ident, field, match_type) in &fields {
+            let value = self.value_type(&field.name, field.type_name.as_ref(), field.bitwidth);
+            let ty = match match_type {
+                Some(MatchType::Exact) => value,
+                Some(MatchType::Lpm) => format!("Option<rt::Lpm<{}>>", value),
+                Some(MatchType::Ternary) => format!("Option<rt::Ternary<{}>>", value),
+                Some(MatchType::Range) => format!("Option<rt::Range<{}>>", value),
+                Some(MatchType::Optional) => format!("Option<{}>", value),
+                _ => "Option<p4_v1::FieldMatch>".to_string(),
+            };
+            w.line(format!(
+                "/// `{}`, {} bits, {}",
+                field.name,
+                field.bitwidth,
+                match_type.map_or("other", |mt| mt.as_str_name())
+            ));
+            w.line(format!("pub {}: {},", ident, ty));
+        }
+        w.line("}");
+
+        w.line("");
+        w.line("impl Key {");
+        w.line("/// Field matches of the key, omitting wildcards");
+        w.line("pub fn to_field_matches(&self) -> Vec<p4_v1::FieldMatch> {");
+        w.line("let mut matches = Vec::new();");
+        for (ident, field, match_type) in &fields {
+            let id = field.id;
+            match match_type {
+                Some(MatchType::Exact) => {
+                    w.line(format!("matches.push(rt::exact({}, &self.{}));", id, ident))
+                }
+                Some(MatchType::Lpm) => w.line(format!(
+                    "matches.extend(self.{}.as_ref().map(|m| m.to_field_match({})));",
+                    ident, id
+                )),
+                Some(MatchType::Ternary) => w.line(format!(
+                    "matches.extend(self.{}.as_ref().map(|m| m.to_field_match({})));",
+                    ident, id
+                )),
+                Some(MatchType::Range) => w.line(format!(
+                    "matches.extend(self.{}.as_ref().map(|m| m.to_field_match({})));",
+                    ident, id
+                )),
+                Some(MatchType::Optional) => w.line(format!(
+                    "matches.extend(self.{}.as_ref().map(|v| rt::optional({}, v)));",
+                    ident, id
+                )),
+                _ => w.line(format!("matches.extend(self.{}.clone());", ident)),
+            }
+        }
+        w.line("matches");
+        w.line("}");
+        w.line("}");
+
+        // Actions
+        let mut variants = Names::default();
+        let actions: Vec<_> = table
+            .action_refs
+            .iter()
+            .filter_map(|r| {
+                self.p4info
+                    .actions
+                    .iter()
+                    .find(|a| a.preamble.as_ref().is_some_and(|p| p.id == r.id))
+            })
+            .map(|action| {
+                let mut params = Names::default();
+                let params: Vec<_> = action
+                    .params
+                    .iter()
+                    .map(|p| (params.unique(snake(&p.name)), p))
+                    .collect();
+                (
+                    variants.unique(camel(&short_name(&action.preamble))),
+                    action,
+                    params,
+                )
+            })
+            .collect();
+
+        w.line("");
+        w.line("/// Actions of the table");
+        w.line("#[derive(Clone, Debug, PartialEq)]");
+        w.line("pub enum Action {");
+        for (variant, action, params) in &actions {
+            w.line(format!("/// `{}`", preamble_name(&action.preamble)));
+            if params.is_empty() {
+                w.line(format!("{},", variant));
+                continue;
+            }
+            w.line(format!("{} {{", variant));
+            for (ident, param) in params {
+                w.line(format!("/// `{}`, {} bits", param.name, param.bitwidth));
+                w.line(format!(
+                    "{}: {},",
+                    ident,
+                    self.value_type(&param.name, param.type_name.as_ref(), param.bitwidth)
+                ));
+            }
+            w.line("},");
+        }
+        w.line("}");
+
+        w.line("");
+        w.line("impl Action {");
+        w.line("/// Action message");
+        w.line("pub fn to_action(&self) -> p4_v1::Action {");
+        w.line("match *self {");
+        for (variant, action, params) in &actions {
+            let action_id = action.preamble.as_ref().map_or(0, |p| p.id);
+            let bindings = params
+                .iter()
+                .map(|(ident, _)| format!("ref {}", ident))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let values = params
+                .iter()
+                .map(|(ident, param)| format!("rt::param({}, {})", param.id, ident))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let pattern = if params.is_empty() {
+                format!("Action::{}", variant)
+            } else {
+                format!("Action::{} {{ {} }}", variant, bindings)
+            };
+            w.line(format!(
+                "{} => p4_v1::Action {{ action_id: {}, params: vec![{}] }},",
+                pattern, action_id, values
+            ));
+        }
+        w.line("}");
+        w.line("}");
+        if indirect {
+            w.line("");
+            w.line("/// Action profile member running the action");
+            w.line("pub fn to_member(&self, member_id: u32) -> p4_v1::ActionProfileMember {");
+            w.line("p4_v1::ActionProfileMember {");
+            w.line("action_profile_id: ACTION_PROFILE_ID,");
+            w.line("member_id,");
+            w.line("action: Some(self.to_action()),");
+            w.line("}");
+            w.line("}");
+        }
+        w.line("}");
+
+        w.line("");
+        w.line("impl From<Action> for p4_v1::TableAction {");
+        w.line("fn from(action: Action) -> Self {");
+        w.line("p4_v1::TableAction {");
+        w.line("r#type: Some(p4_v1::table_action::Type::Action(action.to_action())),");
+        w.line("}");
+        w.line("}");
+        w.line("}");
+
+        // Entry
+        let action_type = if indirect {
+            "rt::ProfileAction"
+        } else {
+            "Action"
+        };
+        w.line("");
+        w.line("/// Table entry");
+        w.line("#[derive(Clone, Debug, PartialEq)]");
+        w.line("pub struct Entry {");
+        w.line("/// Match key");
+        w.line("pub key: Key,");
+        w.line("/// Action");
+        w.line(format!("pub action: {},", action_type));
+        w.line("/// Priority, for keys with ternary, range or optional matches");
+        w.line("pub priority: i32,");
+        w.line("}");
+
+        w.line("");
+        w.line("impl From<Entry> for p4_v1::TableEntry {");
+        w.line("fn from(entry: Entry) -> Self {");
+        w.line("p4_v1::TableEntry {");
+        w.line("table_id: ID,");
+        w.line("r#match: entry.key.to_field_matches(),");
+        w.line("action: Some(entry.action.into()),");
+        w.line("priority: entry.priority,");
+        w.line("..Default::default()");
+        w.line("}");
+        w.line("}");
+        w.line("}");
+
+        w.line("");
+        w.line("impl From<Entry> for p4_v1::Entity {");
+        w.line("fn from(entry: Entry) -> Self {");
+        w.line("p4_v1::Entity {");
+        w.line("entity: Some(p4_v1::entity::Entity::TableEntry(entry.into())),");
+        w.line("}");
+        w.line("}");
+        w.line("}");
+
+        w.line("");
+        w.line("/// Default entry of the table, to be modified");
+        w.line(format!(
+            "pub fn default_entry(action: {}) -> p4_v1::TableEntry {{",
+            action_type
+        ));
+        w.line("p4_v1::TableEntry {");
+        w.line("table_id: ID,");
+        w.line("action: Some(action.into()),");
+        w.line("is_default_action: true,");
+        w.line("..Default::default()");
+        w.line("}");
+        w.line("}");
+
+        w.line("}");
+    }
+
+    fn digest(&self, w: &mut Writer, name: String, digest: &p4_cfg_v1::Digest) {
+        use p4_cfg_v1::p4_data_type_spec::TypeSpec;
+
+        let preamble = digest.preamble.clone().unwrap_or_default();
+        let type_spec = digest.type_spec.as_ref().and_then(|t| t.type_spec.as_ref());
+
+        // A struct digest has one field per member, others a single `value`
+        let members: Vec<(String, Option<&p4_cfg_v1::P4DataTypeSpec>)> = match type_spec {
+            Some(TypeSpec::Struct(s)) => self
+                .p4info
+                .type_info
+                .as_ref()
+                .and_then(|t| t.structs.get(&s.name))
+                .map(|s| {
+                    s.members
+                        .iter()
+                        .map(|m| (m.name.clone(), m.type_spec.as_ref()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            _ => vec![("value".to_string(), digest.type_spec.as_ref())],
+        };
+        let is_struct = matches!(type_spec, Some(TypeSpec::Struct(_)));
+
+        let mut idents = Names::default();
+        let members: Vec<_> = members
+            .into_iter()
+            .map(|(name, spec)| {
+                let kind = self.data_kind(&name, spec);
+                (idents.unique(snake(&name)), name, kind)
+            })
+            .collect();
+
+        w.line("");
+        w.line(format!("/// Digest `{}`", preamble.name));
+        w.line("#[derive(Clone, Debug, PartialEq)]");
+        w.line(format!("pub struct {} {{", name));
+        for (ident, member, kind) in &members {
+            w.line(format!("/// `{}`", member));
+            w.line(format!("pub {}: {},", ident, kind.rust_type()));
+        }
+        w.line("}");
+
+        w.line("");
+        w.line(format!("impl {} {{", name));
+        w.line("/// Digest id");
+        w.line(format!("pub const ID: u32 = {};", preamble.id));
+        w.line("");
+        w.line("/// Decode a digest data");
+        w.line("pub fn from_data(data: &p4_v1::P4Data) -> Option<Self> {");
+        if is_struct {
+            w.line("let members = rt::struct_members(data)?;");
+            w.line(format!("if members.len() != {} {{", members.len()));
+            w.line("return None;");
+            w.line("}");
+            w.line("Some(Self {");
+            for (i, (ident, _, kind)) in members.iter().enumerate() {
+                w.line(format!(
+                    "{}: {},",
+                    ident,
+                    kind.decode(&format!("&members[{}]", i))
+                ));
+            }
+            w.line("})");
+        } else {
+            let (ident, _, kind) = &members[0];
+            w.line(format!(
+                "Some(Self {{ {}: {} }})",
+                ident,
+                kind.decode("data")
+            ));
+        }
+        w.line("}");
+        w.line("");
+        w.line("/// Decode the data of a digest list, if it is of this digest");
+        w.line("pub fn from_digest_list(list: &p4_v1::DigestList) -> Option<Vec<Self>> {");
+        w.line("if list.digest_id != Self::ID {");
+        w.line("return None;");
+        w.line("}");
+        w.line("list.data.iter().map(Self::from_data).collect()");
+        w.line("}");
+        w.line("");
+        w.line("/// Encode as digest data");
+        w.line("pub fn to_data(&self) -> p4_v1::P4Data {");
+        if is_struct {
+            let values = members
+                .iter()
+                .map(|(ident, _, kind)| kind.encode(&format!("self.{}", ident)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            w.line(format!("rt::struct_data(vec![{}])", values));
+        } else {
+            let (ident, _, kind) = &members[0];
+            w.line(kind.encode(&format!("self.{}", ident)));
+        }
+        w.line("}");
+        w.line("}");
+
+        w.line("");
+        w.line(format!("impl From<{}> for p4_v1::P4Data {{", name));
+        w.line(format!("fn from(digest: {}) -> Self {{", name));
+        w.line("digest.to_data()");
+        w.line("}");
+        w.line("}");
+    }
+
+    fn data_kind(&self, name: &str, spec: Option<&p4_cfg_v1::P4DataTypeSpec>) -> DataKind {
+        use p4_cfg_v1::p4_bitstring_like_type_spec::TypeSpec as BitstringSpec;
+        use p4_cfg_v1::p4_data_type_spec::TypeSpec;
+
+        match spec.and_then(|s| s.type_spec.as_ref()) {
+            Some(TypeSpec::Bitstring(b)) => match &b.type_spec {
+                Some(BitstringSpec::Bit(b)) => {
+                    DataKind::Bitstring(self.value_type(name, None, b.bitwidth))
+                }
+                // Two's complement, the unsigned types would lose the sign
+                Some(BitstringSpec::Int(_)) => DataKind::Bitstring("Vec<u8>".to_string()),
+                _ => DataKind::Other,
+            },
+            Some(TypeSpec::Bool(_)) => DataKind::Bool,
+            _ => DataKind::Other,
+        }
+    }
+
+    fn packet_metadata(
+        &self,
+        w: &mut Writer,
+        name: String,
+        header: &p4_cfg_v1::ControllerPacketMetadata,
+    ) {
+        let preamble = header.preamble.clone().unwrap_or_default();
+        let mut idents = Names::default();
+        let fields: Vec<_> = header
+            .metadata
+            .iter()
+            .map(|m| {
+                (
+                    idents.unique(snake(&m.name)),
+                    m,
+                    self.value_type(&m.name, m.type_name.as_ref(), m.bitwidth),
+                )
+            })
+            .collect();
+
+        w.line("");
+        w.line(format!(
+            "/// Controller packet metadata `{}`",
+            preamble.name
+        ));
+        w.line("#[derive(Clone, Debug, PartialEq)]");
+        w.line(format!("pub struct {} {{", name));
+        for (ident, m, ty) in &fields {
+            w.line(format!("/// `{}`, {} bits", m.name, m.bitwidth));
+            w.line(format!("pub {}: {},", ident, ty));
+        }
+        w.line("}");
+
+        w.line("");
+        w.line(format!("impl {} {{", name));
+        w.line("/// Decode packet metadata");
+        w.line("pub fn from_metadata(metadata: &[p4_v1::PacketMetadata]) -> Option<Self> {");
+        w.line("Some(Self {");
+        for (ident, m, _) in &fields {
+            w.line(format!(
+                "{}: rt::find_metadata(metadata, {})?,",
+                ident, m.id
+            ));
+        }
+        w.line("})");
+        w.line("}");
+        w.line("");
+        w.line("/// Encode as packet metadata");
+        w.line("pub fn to_metadata(&self) -> Vec<p4_v1::PacketMetadata> {");
+        let values = fields
+            .iter()
+            .map(|(ident, m, _)| format!("rt::metadata({}, &self.{})", m.id, ident))
+            .collect::<Vec<_>>()
+            .join(", ");
+        w.line(format!("vec![{}]", values));
+        w.line("}");
+        match preamble.name.as_str() {
+            "packet_in" => {
+                w.line("");
+                w.line("/// Decode the metadata of a PacketIn");
+                w.line("pub fn from_packet_in(packet: &p4_v1::PacketIn) -> Option<Self> {");
+                w.line("Self::from_metadata(&packet.metadata)");
+                w.line("}");
+            }
+            "packet_out" => {
+                w.line("");
+                w.line("/// PacketOut carrying the metadata");
+                w.line("pub fn to_packet_out(&self, payload: Vec<u8>) -> p4_v1::PacketOut {");
+                w.line("p4_v1::PacketOut {");
+                w.line("payload,");
+                w.line("metadata: self.to_metadata(),");
+                w.line("}");
+                w.line("}");
+            }
+            _ => {}
+        }
+        w.line("}");
+    }
+}
+
+/// Rust representation of a digest member
+enum DataKind {
+    Bitstring(String),
+    Bool,
+    Other,
+}
+
+impl DataKind {
+    fn rust_type(&self) -> String {
+        match self {
+            DataKind::Bitstring(ty) => ty.clone(),
+            DataKind::Bool => "bool".to_string(),
+            DataKind::Other => "p4_v1::P4Data".to_string(),
+        }
+    }
+
+    fn decode(&self, data: &str) -> String {
+        match self {
+            DataKind::Bitstring(_) => format!("rt::from_bitstring_data({})?", data),
+            DataKind::Bool => format!("rt::from_bool_data({})?", data),
+            DataKind::Other => format!("({}).clone()", data),
+        }
+    }
+
+    fn encode(&self, value: &str) -> String {
+        match self {
+            DataKind::Bitstring(_) => format!("rt::bitstring_data(&{})", value),
+            DataKind::Bool => format!("rt::bool_data({})", value),
+            DataKind::Other => format!("{}.clone()", value),
+        }
+    }
+}
+
+/// Source writer indenting blocks
+#[derive(Default)]
+struct Writer {
+    out: String,
+    indent: usize,
+}
+
+impl Writer {
+    fn line(&mut self, line: impl AsRef<str>) {
+        let line = line.as_ref();
+        if line.starts_with('}') {
+            self.indent = self.indent.saturating_sub(1);
+        }
+        if !line.is_empty() {
+            self.out.push_str(&"    ".repeat(self.indent));
+        }
+        self.out.push_str(line);
+        self.out.push('\n');
+        if line.ends_with('{') {
+            self.indent += 1;
+        }
+    }
+}
+
+/// Unique identifiers of a scope
+#[derive(Default)]
+struct Names(HashSet<String>);
+
+impl Names {
+    /// Scope of a generated module, which imports `p4_v1` and `rt`
+    fn module() -> Self {
+        Names(["p4_v1", "rt"].map(String::from).into())
+    }
+
+    fn unique(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut n = 1;
+        while !self.0.insert(unique.clone()) {
+            n += 1;
+            unique = format!("{}_{}", name, n);
+        }
+        unique
+    }
+}
+
+fn preamble_name(preamble: &Option<p4_cfg_v1::Preamble>) -> String {
+    preamble
+        .as_ref()
+        .map(|p| p.name.clone())
+        .unwrap_or_default()
+}
+
+/// Alias, or name, of a P4 object
+fn short_name(preamble: &Option<p4_cfg_v1::Preamble>) -> String {
+    match preamble {
+        Some(p) if !p.alias.is_empty() => p.alias.clone(),
+        Some(p) => p.name.clone(),
+        None => String::new(),
+    }
+}
+
+/// Words of a P4 name, split at non-alphanumeric characters and camel case
+/// boundaries
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut word = String::new();
+        let mut prev_lower = false;
+        for c in part.chars() {
+            if c.is_ascii_uppercase() && prev_lower && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            word.push(c);
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn escape(ident: String) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do",
+        "final", "gen", "macro", "override", "priv", "try", "typeof", "unsized", "virtual",
+        "yield",
+    ];
+
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", ident)
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else {
+        ident
+    }
+}
+
+fn snake(name: &str) -> String {
+    escape(
+        words(name)
+            .iter()
+            .map(|w| w.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+            .join("_"),
+    )
+}
+
+fn camel(name: &str) -> String {
+    escape(
+        words(name)
+            .iter()
+            .map(|w| {
+                let mut chars = w.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect(),
+    )
+}
+
+// Runtime support of the generated code
+
+/// LPM match value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lpm<T> {
+    /// Value
+    pub value: T,
+    /// Prefix length
+    pub prefix_len: i32,
+}
+
+impl<T: Bitstring> Lpm<T> {
+    /// Create an LPM match value
+    pub fn new(value: T, prefix_len: i32) -> Self {
+        Lpm { value, prefix_len }
+    }
+
+    /// Field match of the value
+    pub fn to_field_match(&self, field_id: u32) -> p4_v1::FieldMatch {
+        field_match(
+            field_id,
+            p4_v1::field_match::FieldMatchType::Lpm(p4_v1::field_match::Lpm {
+                value: self.value.to_bitstring(),
+                prefix_len: self.prefix_len,
+            }),
+        )
+    }
+}
+
+/// Ternary match value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ternary<T> {
+    /// Value
+    pub value: T,
+    /// Mask
+    pub mask: T,
+}
+
+impl<T: Bitstring> Ternary<T> {
+    /// Create a ternary match value
+    pub fn new(value: T, mask: T) -> Self {
+        Ternary { value, mask }
+    }
+
+    /// Field match of the value
+    pub fn to_field_match(&self, field_id: u32) -> p4_v1::FieldMatch {
+        field_match(
+            field_id,
+            p4_v1::field_match::FieldMatchType::Ternary(p4_v1::field_match::Ternary {
+                value: self.value.to_bitstring(),
+                mask: self.mask.to_bitstring(),
+            }),
+        )
+    }
+}
+
+/// Range match value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Range<T> {
+    /// Low bound, inclusive
+    pub low: T,
+    /// High bound, inclusive
+    pub high: T,
+}
+
+impl<T: Bitstring> Range<T> {
+    /// Create a range match value
+    pub fn new(low: T, high: T) -> Self {
+        Range { low, high }
+    }
+
+    /// Field match of the value
+    pub fn to_field_match(&self, field_id: u32) -> p4_v1::FieldMatch {
+        field_match(
+            field_id,
+            p4_v1::field_match::FieldMatchType::Range(p4_v1::field_match::Range {
+                low: self.low.to_bitstring(),
+                high: self.high.to_bitstring(),
+            }),
+        )
+    }
+}
+
+/// Action of a table implemented by an action profile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileAction {
+    /// Action profile member id
+    Member(u32),
+    /// Action profile group id
+    Group(u32),
+}
+
+impl From<ProfileAction> for p4_v1::TableAction {
+    fn from(action: ProfileAction) -> Self {
+        use p4_v1::table_action::Type;
+
+        p4_v1::TableAction {
+            r#type: Some(match action {
+                ProfileAction::Member(id) => Type::ActionProfileMemberId(id),
+                ProfileAction::Group(id) => Type::ActionProfileGroupId(id),
+            }),
+        }
+    }
+}
+
+/// Types decodable from a bitstring
+pub trait FromBitstring: Sized {
+    /// Decode a bitstring, `None` if it is too wide
+    fn from_bitstring(value: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_from_bitstring_for_uint {
+    ($($t:ty),*) => {
+        $(
+            impl FromBitstring for $t {
+                fn from_bitstring(value: &[u8]) -> Option<Self> {
+                    (bit_len(value) <= <$t>::BITS as usize)
+                        .then(|| value.iter().fold(0, |n, &b| (n << 8) | <$t>::from(b)))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_bitstring_for_uint!(u16, u32, u64, u128);
+
+impl FromBitstring for u8 {
+    fn from_bitstring(value: &[u8]) -> Option<Self> {
+        (bit_len(value) <= 8).then(|| value.last().copied().unwrap_or(0))
+    }
+}
+
+impl FromBitstring for bool {
+    fn from_bitstring(value: &[u8]) -> Option<Self> {
+        u8::from_bitstring(value)
+            .filter(|&b| b <= 1)
+            .map(|b| b == 1)
+    }
+}
+
+impl<const N: usize> FromBitstring for [u8; N] {
+    fn from_bitstring(value: &[u8]) -> Option<Self> {
+        if bit_len(value) > N * 8 {
+            return None;
+        }
+        let mut bytes = [0; N];
+        let n = value.len().min(N);
+        bytes[N - n..].copy_from_slice(&value[value.len() - n..]);
+        Some(bytes)
+    }
+}
+
+impl FromBitstring for Ipv4Addr {
+    fn from_bitstring(value: &[u8]) -> Option<Self> {
+        <[u8; 4]>::from_bitstring(value).map(Ipv4Addr::from)
+    }
+}
+
+impl FromBitstring for Ipv6Addr {
+    fn from_bitstring(value: &[u8]) -> Option<Self> {
+        <[u8; 16]>::from_bitstring(value).map(Ipv6Addr::from)
+    }
+}
+
+impl FromBitstring for Vec<u8> {
+    fn from_bitstring(value: &[u8]) -> Option<Self> {
+        Some(value.to_vec())
+    }
+}
+
+fn field_match(
+    field_id: u32,
+    field_match_type: p4_v1::field_match::FieldMatchType,
+) -> p4_v1::FieldMatch {
+    p4_v1::FieldMatch {
+        field_id,
+        field_match_type: Some(field_match_type),
+    }
+}
+
+/// Exact field match
+pub fn exact(field_id: u32, value: &impl Bitstring) -> p4_v1::FieldMatch {
+    field_match(
+        field_id,
+        p4_v1::field_match::FieldMatchType::Exact(p4_v1::field_match::Exact {
+            value: value.to_bitstring(),
+        }),
+    )
+}
+
+/// Optional field match
+pub fn optional(field_id: u32, value: &impl Bitstring) -> p4_v1::FieldMatch {
+    field_match(
+        field_id,
+        p4_v1::field_match::FieldMatchType::Optional(p4_v1::field_match::Optional {
+            value: value.to_bitstring(),
+        }),
+    )
+}
+
+/// Action parameter
+pub fn param(param_id: u32, value: &impl Bitstring) -> p4_v1::action::Param {
+    p4_v1::action::Param {
+        param_id,
+        value: value.to_bitstring(),
+    }
+}
+
+/// Packet metadata
+pub fn metadata(metadata_id: u32, value: &impl Bitstring) -> p4_v1::PacketMetadata {
+    p4_v1::PacketMetadata {
+        metadata_id,
+        value: value.to_bitstring(),
+    }
+}
+
+/// Decode the packet metadata with an id
+pub fn find_metadata<T: FromBitstring>(
+    metadata: &[p4_v1::PacketMetadata],
+    metadata_id: u32,
+) -> Option<T> {
+    metadata
+        .iter()
+        .find(|m| m.metadata_id == metadata_id)
+        .and_then(|m| T::from_bitstring(&m.value))
+}
+
+/// Members of struct data
+pub fn struct_members(data: &p4_v1::P4Data) -> Option<&[p4_v1::P4Data]> {
+    match &data.data {
+        Some(p4_v1::p4_data::Data::Struct(s)) => Some(&s.members),
+        _ => None,
+    }
+}
+
+/// Struct data
+pub fn struct_data(members: Vec<p4_v1::P4Data>) -> p4_v1::P4Data {
+    p4_v1::P4Data {
+        data: Some(p4_v1::p4_data::Data::Struct(p4_v1::P4StructLike {
+            members,
+        })),
+    }
+}
+
+/// Decode bitstring data
+pub fn from_bitstring_data<T: FromBitstring>(data: &p4_v1::P4Data) -> Option<T> {
+    match &data.data {
+        Some(p4_v1::p4_data::Data::Bitstring(value)) => T::from_bitstring(value),
+        _ => None,
+    }
+}
+
+/// Bitstring data
+pub fn bitstring_data(value: &impl Bitstring) -> p4_v1::P4Data {
+    p4_v1::P4Data {
+        data: Some(p4_v1::p4_data::Data::Bitstring(value.to_bitstring())),
+    }
+}
+
+/// Decode bool data
+pub fn from_bool_data(data: &p4_v1::P4Data) -> Option<bool> {
+    match data.data {
+        Some(p4_v1::p4_data::Data::Bool(b)) => Some(b),
+        _ => None,
+    }
+}
+
+/// Bool data
+pub fn bool_data(value: bool) -> p4_v1::P4Data {
+    p4_v1::P4Data {
+        data: Some(p4_v1::p4_data::Data::Bool(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p4_cfg_v1::match_field::MatchType;
+
+    use super::*;
+    use crate::test_utils::{match_field, param, preamble};
+
+    #[test]
+    fn generate_table() {
+        let p4info = P4Info::new(p4_cfg_v1::P4Info {
+            tables: vec![p4_cfg_v1::Table {
+                preamble: preamble(1, "MyIngress.ipv4_lpm", "ipv4_lpm"),
+                match_fields: vec![match_field(1, "hdr.ipv4.dstAddr", 32, MatchType::Lpm)],
+                action_refs: vec![p4_cfg_v1::ActionRef {
+                    id: 2,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            actions: vec![p4_cfg_v1::Action {
+                preamble: preamble(2, "MyIngress.ipv4_forward", "ipv4_forward"),
+                params: vec![param(1, "dstAddr", 48)],
+            }],
+            ..Default::default()
+        });
+
+        let code = Generator::new(&p4info)
+            .hints(DisplayHints::guess())
+            .generate()
+            .unwrap();
+        assert!(code.contains("pub mod ipv4_lpm {"));
+        assert!(code.contains("pub hdr_ipv4_dst_addr: Option<rt::Lpm<::std::net::Ipv4Addr>>,"));
+        assert!(code.contains("Ipv4Forward {"));
+        assert!(code.contains("dst_addr: [u8; 6],"));
+        assert!(code.contains("action_id: 2, params: vec![rt::param(1, dst_addr)]"));
+
+        let code = Generator::new(&p4info).generate().unwrap();
+        assert!(code.contains("pub hdr_ipv4_dst_addr: Option<rt::Lpm<u32>>,"));
+        assert!(code.contains("dst_addr: u64,"));
+
+        // Signed new types keep their two's complement bitstring
+        let mut signed: p4_cfg_v1::P4Info = p4info.as_ref().clone();
+        signed.actions[0].params[0].type_name = Some(p4_cfg_v1::P4NamedType {
+            name: "offset_t".to_string(),
+        });
+        signed.type_info = Some(p4_cfg_v1::P4TypeInfo {
+            new_types: [(
+                "offset_t".to_string(),
+                p4_cfg_v1::P4NewTypeSpec {
+                    representation: Some(
+                        p4_cfg_v1::p4_new_type_spec::Representation::OriginalType(
+                            p4_cfg_v1::P4DataTypeSpec {
+                                type_spec: Some(p4_cfg_v1::p4_data_type_spec::TypeSpec::Bitstring(
+                                    p4_cfg_v1::P4BitstringLikeTypeSpec {
+                                        type_spec: Some(
+                                            p4_cfg_v1::p4_bitstring_like_type_spec::TypeSpec::Int(
+                                                p4_cfg_v1::P4IntTypeSpec { bitwidth: 48 },
+                                            ),
+                                        ),
+                                        ..Default::default()
+                                    },
+                                )),
+                            },
+                        ),
+                    ),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        });
+        let code = Generator::new(&P4Info::new(signed)).generate().unwrap();
+        assert!(code.contains("dst_addr: Vec<u8>,"));
+
+        assert!(matches!(
+            Generator::new(&P4Info::default()).generate(),
+            Err(P4InfoFileError::P4InfoNotLoaded)
+        ));
+
+        assert_eq!(snake("hdr.ipv4.dstAddr"), "hdr_ipv4_dst_addr");
+        assert_eq!(camel("MyIngress.NoAction"), "MyIngressNoAction");
+        assert_eq!(snake("type"), "type_");
+
+        assert_eq!(u16::from_bitstring(&[0x1f, 0x90]), Some(8080));
+        assert_eq!(u8::from_bitstring(&[0x01, 0x00]), None);
+        assert_eq!(<[u8; 6]>::from_bitstring(&[1, 2]), Some([0, 0, 0, 0, 1, 2]));
+    }
+}
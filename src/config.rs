@@ -1,5 +1,12 @@
 //! Helper methods for building P4 device config
 
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+use prost::Message;
+
+use crate::error::TofinoConfigError;
+
+pub mod pipeline;
+
 /// Build a Tofino config
 ///
 /// Tofino's config is formed by concatenating the following:
@@ -52,3 +59,231 @@ pub fn build_tofino_config(
 
     config
 }
+
+/// Parts of a Tofino config
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TofinoConfig {
+    /// Name of the program
+    pub program_name: String,
+    /// Tofino binary (tofino.bin)
+    pub tofino_bin: Vec<u8>,
+    /// Context JSON (context.json)
+    pub context_json: Vec<u8>,
+}
+
+impl TofinoConfig {
+    /// Build the Tofino config, see [`build_tofino_config`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        build_tofino_config(&self.program_name, &self.tofino_bin, &self.context_json)
+    }
+}
+
+/// Parse a Tofino config into its parts
+///
+/// This is the inverse of [`build_tofino_config`]. It can be used on the
+/// `p4_device_config` returned by `get_forwarding_pipeline_config` to check
+/// which program a switch runs.
+///
+/// # Errors
+///
+/// - `Truncated` if a length prefix or a part extends past the end
+/// - `InvalidProgramName` if the program name is not valid UTF-8
+/// - `TrailingBytes` if bytes remain after the context JSON
+///
+/// # Example
+///
+/// ```rust
+/// # use p4runtime_client::config::{build_tofino_config, parse_tofino_config};
+/// let config = build_tofino_config("my_program", [0x00, 0x01], b"{}");
+///
+/// let parts = parse_tofino_config(&config).unwrap();
+/// assert_eq!(parts.program_name, "my_program");
+/// assert_eq!(parts.tofino_bin, vec![0x00, 0x01]);
+/// assert_eq!(parts.context_json, b"{}".to_vec());
+/// ```
+pub fn parse_tofino_config(config: &[u8]) -> Result<TofinoConfig, TofinoConfigError> {
+    let mut offset = 0;
+
+    let program_name = read_chunk(config, &mut offset, "program name")?;
+    let program_name = String::from_utf8(program_name.to_vec())
+        .map_err(|_| TofinoConfigError::InvalidProgramName)?;
+    let tofino_bin = read_chunk(config, &mut offset, "tofino.bin")?.to_vec();
+    let context_json = read_chunk(config, &mut offset, "context.json")?.to_vec();
+
+    if offset != config.len() {
+        return Err(TofinoConfigError::TrailingBytes {
+            n: config.len() - offset,
+        });
+    }
+
+    Ok(TofinoConfig {
+        program_name,
+        tofino_bin,
+        context_json,
+    })
+}
+
+/// Read a chunk prefixed by its 4-byte little-endian length
+fn read_chunk<'a>(
+    config: &'a [u8],
+    offset: &mut usize,
+    part: &str,
+) -> Result<&'a [u8], TofinoConfigError> {
+    let truncated = |offset: usize, needed: usize| TofinoConfigError::Truncated {
+        part: part.to_string(),
+        offset,
+        needed,
+        available: config.len().saturating_sub(offset),
+    };
+
+    let len = config
+        .get(*offset..*offset + 4)
+        .ok_or_else(|| truncated(*offset, 4))?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    *offset += 4;
+
+    let chunk = config
+        .get(*offset..)
+        .and_then(|rest| rest.get(..len))
+        .ok_or_else(|| truncated(*offset, len))?;
+    *offset += len;
+
+    Ok(chunk)
+}
+
+/// 64-bit FNV-1a hasher
+///
+/// Unlike `std::hash::DefaultHasher`, its output is stable across Rust
+/// versions, which matters for cookies stored on the device.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Fnv1a(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Write a length-prefixed chunk, so that chunk boundaries affect the hash
+    fn write_chunk(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    /// Write the entries of a protobuf map in key order
+    fn write_map<'a, M: Message + 'a>(
+        &mut self,
+        map: impl IntoIterator<Item = (&'a String, &'a M)>,
+    ) {
+        let mut entries = map.into_iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (key, value) in entries {
+            self.write_chunk(key.as_bytes());
+            self.write_chunk(&value.encode_to_vec());
+        }
+    }
+}
+
+/// Compute a pipeline config cookie
+///
+/// The cookie is the 64-bit FNV-1a hash of the encoded P4Info and the device
+/// config, so the same pipeline always yields the same cookie. It can be
+/// compared with the cookie returned by `GetForwardingPipelineConfig` to tell
+/// whether a device already runs the pipeline.
+///
+/// Protobuf map fields in `type_info` are hashed in key order, as their
+/// encoding order is unspecified.
+///
+/// # Example
+///
+/// ```rust
+/// # use p4runtime_client::config::pipeline_cookie;
+/// # use p4runtime_client::p4runtime::p4::config::v1 as p4_cfg_v1;
+/// let p4info = p4_cfg_v1::P4Info::default();
+///
+/// assert_eq!(pipeline_cookie(&p4info, b"a"), pipeline_cookie(&p4info, b"a"));
+/// assert_ne!(pipeline_cookie(&p4info, b"a"), pipeline_cookie(&p4info, b"b"));
+/// ```
+pub fn pipeline_cookie(p4info: &p4_cfg_v1::P4Info, p4_device_config: impl AsRef<[u8]>) -> u64 {
+    let mut hasher = Fnv1a::new();
+
+    let mut p4info = p4info.clone();
+    let type_info = p4info.type_info.take();
+    hasher.write_chunk(&p4info.encode_to_vec());
+
+    if let Some(type_info) = type_info {
+        hasher.write_map(&type_info.structs);
+        hasher.write_map(&type_info.headers);
+        hasher.write_map(&type_info.header_unions);
+        hasher.write_map(&type_info.enums);
+        hasher.write_map(&type_info.serializable_enums);
+        hasher.write_map(&type_info.new_types);
+        hasher.write_chunk(&type_info.error.unwrap_or_default().encode_to_vec());
+    }
+
+    hasher.write_chunk(p4_device_config.as_ref());
+
+    hasher.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_tofino_config() {
+        let parts = TofinoConfig {
+            program_name: "prog".to_string(),
+            tofino_bin: vec![1, 2, 3],
+            context_json: b"{}".to_vec(),
+        };
+
+        assert_eq!(parse_tofino_config(&parts.to_bytes()).unwrap(), parts);
+    }
+
+    #[test]
+    fn reject_malformed_tofino_config() {
+        let config = build_tofino_config("prog", [1, 2, 3], b"{}");
+
+        for len in 0..config.len() {
+            assert!(matches!(
+                parse_tofino_config(&config[..len]),
+                Err(TofinoConfigError::Truncated { .. })
+            ));
+        }
+
+        assert!(matches!(
+            parse_tofino_config(&config[..6]),
+            Err(TofinoConfigError::Truncated {
+                offset: 4,
+                needed: 4,
+                available: 2,
+                ..
+            })
+        ));
+
+        let mut trailing = config.clone();
+        trailing.push(0);
+        assert!(matches!(
+            parse_tofino_config(&trailing),
+            Err(TofinoConfigError::TrailingBytes { n: 1 })
+        ));
+
+        let invalid = build_tofino_config("", [], []);
+        let mut invalid_name = vec![1, 0, 0, 0, 0xff];
+        invalid_name.extend_from_slice(&invalid[4..]);
+        assert!(matches!(
+            parse_tofino_config(&invalid_name),
+            Err(TofinoConfigError::InvalidProgramName)
+        ));
+    }
+}
@@ -0,0 +1,465 @@
+//! Pipeline config builder for compiler output directories
+//!
+//! `p4c` (bmv2) and `bf-p4c` (Tofino) write the P4Info and the device
+//! artifacts into an output directory. [`PipelineConfigBuilder`] finds them,
+//! validates them and produces the P4Info and `p4_device_config` expected by
+//! `set_forwarding_pipeline_config`.
+//!
+//! Typical layouts:
+//!
+//! ```text
+//! build/                              build/
+//! ├── main.json                       ├── p4rt.bin
+//! └── main.p4info.txtpb               └── tna_p4rt_basic/tofino/
+//!                                         ├── tna_p4rt_basic.conf
+//!                                         └── pipe/
+//!                                             ├── context.json
+//!                                             └── tofino.bin
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+
+use super::build_tofino_config;
+use crate::{
+    error::PipelineConfigError,
+    p4info::{P4Info, P4InfoFormat},
+};
+
+/// How deep the output directory is searched
+const MAX_DEPTH: usize = 4;
+
+/// Tofino binary names, for Tofino 1, 2 and 3
+const TOFINO_BINS: &[&str] = &["tofino.bin", "tofino2.bin", "tofino3.bin"];
+
+/// Target of a pipeline config
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipelineTarget {
+    /// bmv2 `simple_switch_grpc`, configured with the bmv2 JSON
+    Bmv2,
+    /// Tofino, configured with `tofino.bin` and `context.json`
+    Tofino,
+}
+
+/// P4Info and device config of a compiled P4 program
+#[derive(Clone, Debug)]
+pub struct PipelineConfig {
+    /// Target the device config is built for
+    pub target: PipelineTarget,
+    /// P4Info of the program
+    pub p4info: p4_cfg_v1::P4Info,
+    /// Device config, to be sent along with the P4Info
+    pub p4_device_config: Vec<u8>,
+}
+
+impl PipelineConfig {
+    /// Create a builder for a compiler output directory
+    pub fn builder(dir: impl Into<PathBuf>) -> PipelineConfigBuilder {
+        PipelineConfigBuilder {
+            dir: dir.into(),
+            target: None,
+            p4info_path: None,
+            pipe: None,
+            program_name: None,
+        }
+    }
+
+    /// Split into the P4Info and the device config
+    pub fn into_parts(self) -> (p4_cfg_v1::P4Info, Vec<u8>) {
+        (self.p4info, self.p4_device_config)
+    }
+}
+
+/// Builder of [`PipelineConfig`]
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use p4runtime_client::config::pipeline::PipelineConfig;
+/// # async fn example(
+/// #     client: &mut p4runtime_client::client::Client,
+/// # ) -> Result<(), Box<dyn std::error::Error>> {
+/// let config = PipelineConfig::builder("build/").pipe("pipe").build()?;
+///
+/// client.p4info_mut().load(config.p4info);
+/// client
+///     .set_forwarding_pipeline_config(config.p4_device_config)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PipelineConfigBuilder {
+    dir: PathBuf,
+    target: Option<PipelineTarget>,
+    p4info_path: Option<PathBuf>,
+    pipe: Option<String>,
+    program_name: Option<String>,
+}
+
+impl PipelineConfigBuilder {
+    /// Set the target instead of detecting it
+    ///
+    /// The target is Tofino if a Tofino binary is found, bmv2 otherwise.
+    pub fn target(mut self, target: PipelineTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Set the P4Info file instead of searching the directory for it
+    ///
+    /// Without it, files named `*p4info*` or `p4rt.*` are considered,
+    /// preferring the shallowest one and binary over text over JSON.
+    pub fn p4info_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.p4info_path = Some(path.into());
+        self
+    }
+
+    /// Select a pipe of a multi-pipe Tofino program by its directory name
+    ///
+    /// It is required if the program has more than one pipe.
+    pub fn pipe(mut self, pipe: impl Into<String>) -> Self {
+        self.pipe = Some(pipe.into());
+        self
+    }
+
+    /// Set the Tofino program name
+    ///
+    /// Without it, the name is read from the `bf-p4c` `.conf` file, falling
+    /// back to the name of the output directory.
+    pub fn program_name(mut self, program_name: impl Into<String>) -> Self {
+        self.program_name = Some(program_name.into());
+        self
+    }
+
+    /// Find, validate and load the pipeline files
+    pub fn build(self) -> Result<PipelineConfig, PipelineConfigError> {
+        if !self.dir.is_dir() {
+            return Err(PipelineConfigError::MissingFile {
+                file: "output directory".to_string(),
+                dir: self.dir,
+            });
+        }
+
+        let mut files = Vec::new();
+        walk(&self.dir, 0, &mut files)?;
+
+        let p4info_path = match &self.p4info_path {
+            Some(path) => path.clone(),
+            None => self.find_p4info(&files)?,
+        };
+        let p4info = P4Info::from_file(&p4info_path)?.as_ref().clone();
+
+        let target = self.target.unwrap_or_else(|| {
+            if files.iter().any(|(path, _)| is_tofino_bin(path)) {
+                PipelineTarget::Tofino
+            } else {
+                PipelineTarget::Bmv2
+            }
+        });
+
+        let p4_device_config = match target {
+            PipelineTarget::Bmv2 => self.bmv2_config(&files, &p4info_path)?,
+            PipelineTarget::Tofino => self.tofino_config(&files)?,
+        };
+
+        Ok(PipelineConfig {
+            target,
+            p4info,
+            p4_device_config,
+        })
+    }
+
+    fn find_p4info(&self, files: &[(PathBuf, usize)]) -> Result<PathBuf, PipelineConfigError> {
+        let mut candidates = files
+            .iter()
+            .filter(|(path, _)| is_p4info(path))
+            .filter_map(|(path, depth)| {
+                let rank = match P4InfoFormat::from_path(path)? {
+                    P4InfoFormat::Binary => 0,
+                    P4InfoFormat::Text => 1,
+                    P4InfoFormat::Json => 2,
+                };
+                Some(((*depth, rank), path.clone()))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+
+        match candidates.as_slice() {
+            [] => Err(PipelineConfigError::MissingFile {
+                file: "P4Info".to_string(),
+                dir: self.dir.clone(),
+            }),
+            [(best, path), rest @ ..] => {
+                let tied = rest
+                    .iter()
+                    .take_while(|(rank, _)| rank == best)
+                    .map(|(_, path)| path.clone())
+                    .collect::<Vec<_>>();
+
+                if tied.is_empty() {
+                    Ok(path.clone())
+                } else {
+                    Err(PipelineConfigError::AmbiguousFile {
+                        file: "P4Info".to_string(),
+                        candidates: std::iter::once(path.clone()).chain(tied).collect(),
+                    })
+                }
+            }
+        }
+    }
+
+    fn bmv2_config(
+        &self,
+        files: &[(PathBuf, usize)],
+        p4info_path: &Path,
+    ) -> Result<Vec<u8>, PipelineConfigError> {
+        let mut configs = Vec::new();
+        for (path, _) in files {
+            if path == p4info_path || path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let content = read(path)?;
+            let is_bmv2 = serde_json::from_slice::<serde_json::Value>(&content).is_ok_and(|json| {
+                json.get("header_types").is_some() && json.get("pipelines").is_some()
+            });
+            if is_bmv2 {
+                configs.push((path.clone(), content));
+            }
+        }
+
+        match configs.len() {
+            0 => Err(PipelineConfigError::MissingFile {
+                file: "bmv2 JSON".to_string(),
+                dir: self.dir.clone(),
+            }),
+            1 => Ok(configs.pop().unwrap().1),
+            _ => Err(PipelineConfigError::AmbiguousFile {
+                file: "bmv2 JSON".to_string(),
+                candidates: configs.into_iter().map(|(path, _)| path).collect(),
+            }),
+        }
+    }
+
+    fn tofino_config(&self, files: &[(PathBuf, usize)]) -> Result<Vec<u8>, PipelineConfigError> {
+        let pipes = files
+            .iter()
+            .filter(|(path, _)| is_tofino_bin(path))
+            .map(|(path, _)| {
+                let dir = path.parent().unwrap_or(&self.dir).to_path_buf();
+                (pipe_name(&dir), dir, path.clone())
+            })
+            .collect::<Vec<_>>();
+
+        let (_, pipe_dir, tofino_bin_path) = match &self.pipe {
+            Some(pipe) => pipes
+                .iter()
+                .find(|(name, _, _)| name == pipe)
+                .ok_or_else(|| PipelineConfigError::UnknownPipe {
+                    pipe: pipe.clone(),
+                    pipes: pipes.iter().map(|(name, _, _)| name.clone()).collect(),
+                })?,
+            None => match pipes.as_slice() {
+                [] => {
+                    return Err(PipelineConfigError::MissingFile {
+                        file: "tofino.bin".to_string(),
+                        dir: self.dir.clone(),
+                    })
+                }
+                [pipe] => pipe,
+                _ => {
+                    return Err(PipelineConfigError::AmbiguousPipe {
+                        pipes: pipes.iter().map(|(name, _, _)| name.clone()).collect(),
+                    })
+                }
+            },
+        };
+
+        let tofino_bin = read(tofino_bin_path)?;
+        if tofino_bin.is_empty() {
+            return Err(PipelineConfigError::InvalidFile {
+                path: tofino_bin_path.clone(),
+                reason: "empty Tofino binary".to_string(),
+            });
+        }
+
+        let context_path = pipe_dir.join("context.json");
+        if !context_path.is_file() {
+            return Err(PipelineConfigError::MissingFile {
+                file: "context.json".to_string(),
+                dir: pipe_dir.clone(),
+            });
+        }
+        let context_json = read(&context_path)?;
+        if let Err(e) = serde_json::from_slice::<serde_json::Value>(&context_json) {
+            return Err(PipelineConfigError::InvalidFile {
+                path: context_path,
+                reason: e.to_string(),
+            });
+        }
+
+        let program_name = match &self.program_name {
+            Some(name) => name.clone(),
+            None => self.find_program_name(files)?,
+        };
+
+        Ok(build_tofino_config(&program_name, tofino_bin, context_json))
+    }
+
+    fn find_program_name(&self, files: &[(PathBuf, usize)]) -> Result<String, PipelineConfigError> {
+        let conf = files
+            .iter()
+            .find(|(path, _)| path.extension().is_some_and(|e| e == "conf"));
+
+        if let Some((path, _)) = conf {
+            let content = read(path)?;
+            let name = serde_json::from_slice::<serde_json::Value>(&content)
+                .ok()
+                .and_then(|json| {
+                    json.pointer("/p4_devices/0/p4_programs/0/program-name")
+                        .and_then(|name| name.as_str())
+                        .map(str::to_string)
+                });
+            if let Some(name) = name {
+                return Ok(name);
+            }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                return Ok(stem.to_string());
+            }
+        }
+
+        Ok(pipe_name(&self.dir))
+    }
+}
+
+/// Collect regular files with their depth, in a stable order
+fn walk(
+    dir: &Path,
+    depth: usize,
+    files: &mut Vec<(PathBuf, usize)>,
+) -> Result<(), PipelineConfigError> {
+    let mut entries = std::fs::read_dir(dir).and_then(|entries| {
+        entries
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if depth < MAX_DEPTH {
+                walk(&path, depth + 1, files)?;
+            }
+        } else if path.is_file() {
+            files.push((path, depth));
+        }
+    }
+
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, PipelineConfigError> {
+    Ok(std::fs::read(path)?)
+}
+
+fn is_p4info(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.contains("p4info") || name.starts_with("p4rt."))
+}
+
+fn is_tofino_bin(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| TOFINO_BINS.contains(&name))
+}
+
+fn pipe_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "p4runtime-client-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn write(&self, path: &str, content: &[u8]) {
+            let path = self.0.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const P4INFO: &[u8] = b"pkg_info { arch: \"tna\" }\n";
+
+    #[test]
+    fn build_bmv2_config() {
+        let dir = TempDir::new("bmv2");
+        dir.write("main.p4info.txtpb", P4INFO);
+        dir.write("main.json", br#"{"header_types": [], "pipelines": []}"#);
+
+        let config = PipelineConfig::builder(&dir.0).build().unwrap();
+        assert_eq!(config.target, PipelineTarget::Bmv2);
+        assert_eq!(config.p4info.pkg_info.unwrap().arch, "tna");
+        assert_eq!(
+            config.p4_device_config,
+            br#"{"header_types": [], "pipelines": []}"#
+        );
+    }
+
+    #[test]
+    fn build_multi_pipe_tofino_config() {
+        let dir = TempDir::new("tofino");
+        dir.write("p4rt.txt", P4INFO);
+        dir.write(
+            "prog/tofino/prog.conf",
+            br#"{"p4_devices": [{"p4_programs": [{"program-name": "prog"}]}]}"#,
+        );
+        for pipe in ["pipe0", "pipe1"] {
+            dir.write(&format!("prog/tofino/{}/tofino.bin", pipe), pipe.as_bytes());
+            dir.write(&format!("prog/tofino/{}/context.json", pipe), b"{}");
+        }
+
+        assert!(matches!(
+            PipelineConfig::builder(&dir.0).build(),
+            Err(PipelineConfigError::AmbiguousPipe { .. })
+        ));
+        assert!(matches!(
+            PipelineConfig::builder(&dir.0).pipe("pipe2").build(),
+            Err(PipelineConfigError::UnknownPipe { .. })
+        ));
+
+        let config = PipelineConfig::builder(&dir.0)
+            .pipe("pipe1")
+            .build()
+            .unwrap();
+        assert_eq!(config.target, PipelineTarget::Tofino);
+        assert_eq!(
+            config.p4_device_config,
+            build_tofino_config("prog", b"pipe1", b"{}")
+        );
+    }
+}
@@ -1,12 +1,13 @@
 //! Counter helper and operations
 
-use std::borrow::{Borrow, BorrowMut};
+use std::borrow::Borrow;
 
 use p4runtime::p4::v1 as p4_v1;
 
 use crate::{client::Client, error::ClientError};
 
 /// Wrapper for counter operations
+#[derive(Clone)]
 pub struct Counter<T>
 where
     T: Borrow<Client>,
@@ -42,19 +43,17 @@ impl<T: Borrow<Client>> Counter<T> {
             data,
         }
     }
-}
 
-impl<T: Borrow<Client> + BorrowMut<Client>> Counter<T> {
     /// Read a single counter entry
     pub async fn read_entry(
-        &mut self,
+        &self,
         counter_entry: p4_v1::CounterEntry,
     ) -> Result<p4_v1::CounterEntry, ClientError> {
         let entity = p4_v1::Entity {
             entity: Some(p4_v1::entity::Entity::CounterEntry(counter_entry)),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         let entity = client.read_entity_single(entity).await?;
 
         if let Some(p4_v1::entity::Entity::CounterEntry(counter_entry)) = entity.entity {
@@ -66,14 +65,14 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Counter<T> {
 
     /// Read multiple counter entries
     pub async fn read_entries(
-        &mut self,
+        &self,
         counter_entry: p4_v1::CounterEntry,
     ) -> Result<Vec<p4_v1::CounterEntry>, ClientError> {
         let entity = p4_v1::Entity {
             entity: Some(p4_v1::entity::Entity::CounterEntry(counter_entry)),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         let entities = client.read_entities(entity).await?;
 
         let entries = entities
@@ -92,7 +91,7 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Counter<T> {
 
     /// Read multiple counters' entries
     pub async fn read_entries_batch(
-        &mut self,
+        &self,
         counter_entries: Vec<p4_v1::CounterEntry>,
     ) -> Result<Vec<p4_v1::CounterEntry>, ClientError> {
         let entities = counter_entries
@@ -102,7 +101,7 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Counter<T> {
             })
             .collect();
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         let entities = client.read_entities_batch(entities).await?;
 
         let entries = entities
@@ -121,7 +120,7 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Counter<T> {
 
     /// Modify a counter entry
     pub async fn modify_entry(
-        &mut self,
+        &self,
         counter_entry: p4_v1::CounterEntry,
     ) -> Result<p4_v1::WriteResponse, ClientError> {
         let update = p4_v1::Update {
@@ -131,14 +130,14 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Counter<T> {
             }),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         let res = client.write_update(update).await?;
         Ok(res.into_inner())
     }
 
     /// Modify multiple counter entries
     pub async fn modify_entries(
-        &mut self,
+        &self,
         counter_entries: Vec<p4_v1::CounterEntry>,
     ) -> Result<p4_v1::WriteResponse, ClientError> {
         let updates = counter_entries
@@ -151,7 +150,7 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Counter<T> {
             })
             .collect();
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         let res = client.write_update_batch(updates).await?;
         Ok(res.into_inner())
     }
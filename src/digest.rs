@@ -1,12 +1,13 @@
 //! Digest helper and operations
 
-use std::borrow::{Borrow, BorrowMut};
+use std::borrow::Borrow;
 
 use p4runtime::p4::v1 as p4_v1;
 
-use crate::{client::Client, error::ClientError};
+use crate::{client::Client, error::ClientError, stream::correlation::StreamOutcome};
 
 /// Wrapper for digest operations
+#[derive(Clone)]
 pub struct Digest<T>
 where
     T: Borrow<Client>,
@@ -40,19 +41,17 @@ impl<T: Borrow<Client>> Digest<T> {
             }),
         }
     }
-}
 
-impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
     /// Read a DigestEntry
     pub async fn read_entry(
-        &mut self,
+        &self,
         digest_entry: p4_v1::DigestEntry,
     ) -> Result<p4_v1::DigestEntry, ClientError> {
         let entity = p4_v1::Entity {
             entity: Some(p4_v1::entity::Entity::DigestEntry(digest_entry)),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         let entity = client.read_entity_single(entity).await?;
 
         match entity.entity {
@@ -63,7 +62,7 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
 
     /// Insert a DigestEntry
     pub async fn insert_entry(
-        &mut self,
+        &self,
         digest_entry: p4_v1::DigestEntry,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let update = p4_v1::Update {
@@ -73,13 +72,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
             }),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update(update).await
     }
 
     /// Insert multiple DigestEntries
     pub async fn insert_entries(
-        &mut self,
+        &self,
         digest_entries: Vec<p4_v1::DigestEntry>,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let updates = digest_entries
@@ -92,13 +91,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
             })
             .collect();
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update_batch(updates).await
     }
 
     /// Modify a DigestEntry
     pub async fn modify_entry(
-        &mut self,
+        &self,
         digest_entry: p4_v1::DigestEntry,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let update = p4_v1::Update {
@@ -108,13 +107,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
             }),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update(update).await
     }
 
     /// Modify multiple DigestEntries
     pub async fn modify_entries(
-        &mut self,
+        &self,
         digest_entries: Vec<p4_v1::DigestEntry>,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let updates = digest_entries
@@ -127,13 +126,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
             })
             .collect();
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update_batch(updates).await
     }
 
     /// Delete a DigestEntry
     pub async fn delete_entry(
-        &mut self,
+        &self,
         digest_entry: p4_v1::DigestEntry,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let update = p4_v1::Update {
@@ -143,13 +142,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
             }),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update(update).await
     }
 
     /// Delete multiple DigestEntries
     pub async fn delete_entries(
-        &mut self,
+        &self,
         digest_entries: Vec<p4_v1::DigestEntry>,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let updates = digest_entries
@@ -162,15 +161,18 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
             })
             .collect();
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update_batch(updates).await
     }
 
     /// Acknowledge a DigestList
+    ///
+    /// The returned [`StreamOutcome`] resolves to the `StreamError` reported
+    /// by the server for this acknowledgement, if any.
     pub async fn ack_digest_list(
-        &mut self,
+        &self,
         digest_list: &p4_v1::DigestList,
-    ) -> Result<(), ClientError> {
+    ) -> Result<StreamOutcome, ClientError> {
         let req = p4_v1::StreamMessageRequest {
             update: Some(p4_v1::stream_message_request::Update::DigestAck(
                 p4_v1::DigestListAck {
@@ -180,7 +182,7 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Digest<T> {
             )),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.send_message_request(req).await
     }
 }
@@ -0,0 +1,358 @@
+//! Extern helper and operations
+//!
+//! Architecture-specific externs (e.g. TNA `Lpf`, `Wred` or `RegisterParam`)
+//! are described in the `externs` section of P4Info and accessed through
+//! `ExternEntry` entities, whose payload is a `google.protobuf.Any`.
+//! Typed payloads can be plugged in by implementing [`ExternCodec`].
+
+use std::borrow::Borrow;
+
+use p4runtime::p4::v1 as p4_v1;
+
+use crate::{
+    client::Client,
+    error::{ClientError, ExtensionError},
+};
+
+/// Codec for the payload of an architecture-specific extern
+///
+/// # Example
+///
+/// ```rust
+/// # use p4runtime_client::{error::ExtensionError, externs::ExternCodec, stream::extension};
+/// # #[derive(Clone, PartialEq, prost::Message)]
+/// # pub struct LpfSpec {
+/// #     #[prost(int64, tag = "1")]
+/// #     pub gain_time_constant_ns: i64,
+/// # }
+/// struct Lpf;
+///
+/// impl ExternCodec for Lpf {
+///     const EXTERN_TYPE_NAME: &'static str = "Lpf";
+///     type Entry = LpfSpec;
+///
+///     fn encode(entry: &LpfSpec) -> pbjson_types::Any {
+///         extension::encode_any("type.googleapis.com/tofino.LpfSpec", entry)
+///     }
+///
+///     fn decode(any: &pbjson_types::Any) -> Result<LpfSpec, ExtensionError> {
+///         extension::decode_any_checked(any, "type.googleapis.com/tofino.LpfSpec")
+///     }
+/// }
+/// ```
+pub trait ExternCodec {
+    /// Extern type name as found in P4Info
+    const EXTERN_TYPE_NAME: &'static str;
+
+    /// Typed payload of the extern entry
+    type Entry;
+
+    /// Encode a typed payload
+    fn encode(entry: &Self::Entry) -> pbjson_types::Any;
+
+    /// Decode a typed payload
+    fn decode(any: &pbjson_types::Any) -> Result<Self::Entry, ExtensionError>;
+}
+
+/// Wrapper for extern operations
+#[derive(Clone)]
+pub struct Extern<T>
+where
+    T: Borrow<Client>,
+{
+    client: T,
+}
+
+impl<T: Borrow<Client>> Extern<T> {
+    /// Create a new extern wrapper
+    pub fn new(client: T) -> Self {
+        Extern { client }
+    }
+
+    /// Create a new ExternEntry by extern type name and instance name
+    ///
+    /// # Arguments
+    ///
+    /// - `extern_type_name`: The extern type name, e.g. `Lpf`
+    ///   - It is used to find the extern type id in P4Info
+    ///   - If the name is not found, 0 is used
+    /// - `extern_name`: The name of the extern instance
+    ///   - If the name is not found, wildcard is used, i.e., id = 0
+    /// - `entry`: The architecture-specific payload
+    pub fn new_entry(
+        &self,
+        extern_type_name: &str,
+        extern_name: &str,
+        entry: Option<pbjson_types::Any>,
+    ) -> p4_v1::ExternEntry {
+        let client: &Client = self.client.borrow();
+        let extern_type_id = client.p4info().extern_type_id(extern_type_name);
+        let extern_id = client.p4info().extern_id(extern_type_id, extern_name);
+
+        p4_v1::ExternEntry {
+            extern_type_id,
+            extern_id,
+            entry,
+        }
+    }
+
+    /// Create a new ExternEntry with a typed payload
+    pub fn new_typed_entry<C: ExternCodec>(
+        &self,
+        extern_name: &str,
+        entry: Option<&C::Entry>,
+    ) -> p4_v1::ExternEntry {
+        self.new_entry(C::EXTERN_TYPE_NAME, extern_name, entry.map(C::encode))
+    }
+
+    /// Decode the typed payload of an ExternEntry
+    ///
+    /// Returns `None` if the entry has no payload.
+    pub fn decode_entry<C: ExternCodec>(
+        &self,
+        extern_entry: &p4_v1::ExternEntry,
+    ) -> Result<Option<C::Entry>, ExtensionError> {
+        extern_entry.entry.as_ref().map(C::decode).transpose()
+    }
+
+    /// Read a single ExternEntry
+    pub async fn read_entry(
+        &self,
+        extern_entry: p4_v1::ExternEntry,
+    ) -> Result<p4_v1::ExternEntry, ClientError> {
+        let entity = p4_v1::Entity {
+            entity: Some(p4_v1::entity::Entity::ExternEntry(extern_entry)),
+        };
+
+        let client: &Client = self.client.borrow();
+        let entity = client.read_entity_single(entity).await?;
+
+        match entity.entity {
+            Some(p4_v1::entity::Entity::ExternEntry(entry)) => Ok(entry),
+            _ => Err(ClientError::UnexpectedEntry),
+        }
+    }
+
+    /// Read multiple ExternEntries
+    pub async fn read_entries(
+        &self,
+        extern_entry: p4_v1::ExternEntry,
+    ) -> Result<Vec<p4_v1::ExternEntry>, ClientError> {
+        let entity = p4_v1::Entity {
+            entity: Some(p4_v1::entity::Entity::ExternEntry(extern_entry)),
+        };
+
+        let client: &Client = self.client.borrow();
+        let entities = client.read_entities(entity).await?;
+
+        let mut entries = Vec::with_capacity(entities.len());
+        for e in entities {
+            match e.entity {
+                Some(p4_v1::entity::Entity::ExternEntry(entry)) => entries.push(entry),
+                _ => return Err(ClientError::UnexpectedEntry),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Read the typed payload of an extern instance
+    pub async fn read_typed<C: ExternCodec>(
+        &self,
+        extern_name: &str,
+    ) -> Result<Option<C::Entry>, ClientError> {
+        let extern_entry = self.new_typed_entry::<C>(extern_name, None);
+        let extern_entry = self.read_entry(extern_entry).await?;
+
+        Ok(self.decode_entry::<C>(&extern_entry)?)
+    }
+
+    /// Insert an ExternEntry
+    pub async fn insert_entry(
+        &self,
+        extern_entry: p4_v1::ExternEntry,
+    ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
+        self.write_entry(p4_v1::update::Type::Insert, extern_entry)
+            .await
+    }
+
+    /// Modify an ExternEntry
+    pub async fn modify_entry(
+        &self,
+        extern_entry: p4_v1::ExternEntry,
+    ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
+        self.write_entry(p4_v1::update::Type::Modify, extern_entry)
+            .await
+    }
+
+    /// Delete an ExternEntry
+    pub async fn delete_entry(
+        &self,
+        extern_entry: p4_v1::ExternEntry,
+    ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
+        self.write_entry(p4_v1::update::Type::Delete, extern_entry)
+            .await
+    }
+
+    /// Modify the typed payload of an extern instance
+    pub async fn modify_typed<C: ExternCodec>(
+        &self,
+        extern_name: &str,
+        entry: &C::Entry,
+    ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
+        let extern_entry = self.new_typed_entry::<C>(extern_name, Some(entry));
+        self.modify_entry(extern_entry).await
+    }
+
+    async fn write_entry(
+        &self,
+        r#type: p4_v1::update::Type,
+        extern_entry: p4_v1::ExternEntry,
+    ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
+        let update = p4_v1::Update {
+            r#type: r#type as i32,
+            entity: Some(p4_v1::Entity {
+                entity: Some(p4_v1::entity::Entity::ExternEntry(extern_entry)),
+            }),
+        };
+
+        let client: &Client = self.client.borrow();
+        client.write_update(update).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p4runtime::p4::config::v1 as p4_cfg_v1;
+
+    use super::*;
+    use crate::{p4info::P4Info, stream::extension, test_utils::preamble};
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct LpfSpec {
+        #[prost(int64, tag = "1")]
+        gain_time_constant_ns: i64,
+    }
+
+    struct Lpf;
+
+    impl ExternCodec for Lpf {
+        const EXTERN_TYPE_NAME: &'static str = "Lpf";
+        type Entry = LpfSpec;
+
+        fn encode(entry: &LpfSpec) -> pbjson_types::Any {
+            extension::encode_any("type.googleapis.com/tofino.LpfSpec", entry)
+        }
+
+        fn decode(any: &pbjson_types::Any) -> Result<LpfSpec, ExtensionError> {
+            extension::decode_any_checked(any, "type.googleapis.com/tofino.LpfSpec")
+        }
+    }
+
+    fn p4info() -> p4_cfg_v1::P4Info {
+        p4_cfg_v1::P4Info {
+            externs: vec![p4_cfg_v1::Extern {
+                extern_type_id: 0x81,
+                extern_type_name: "Lpf".to_string(),
+                instances: vec![
+                    p4_cfg_v1::ExternInstance {
+                        preamble: preamble(0x8100_0001, "MyIngress.lpf_a", "lpf_a"),
+                        info: None,
+                    },
+                    p4_cfg_v1::ExternInstance {
+                        preamble: preamble(0x8100_0002, "MyIngress.lpf_b", "lpf_b"),
+                        info: None,
+                    },
+                ],
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn extern_ids() {
+        let p4info = P4Info::new(p4info());
+
+        assert_eq!(p4info.extern_type_id("Lpf"), 0x81);
+        assert_eq!(p4info.extern_type_id("Wred"), 0);
+        assert_eq!(p4info.extern_id(0x81, "MyIngress.lpf_a"), 0x8100_0001);
+        assert_eq!(p4info.extern_id(0x81, "lpf_a"), 0x8100_0001);
+        assert_eq!(p4info.extern_id(0x81, "lpf_b"), 0x8100_0002);
+        assert_eq!(p4info.extern_id(0x82, "lpf_a"), 0);
+        assert_eq!(p4info.extern_id(0x81, "lpf_c"), 0);
+
+        let mut client = Client::builder().device_id(1).build().unwrap();
+        client.p4info_mut().load(self::p4info());
+        let externs = Extern::new(&client);
+        let spec = LpfSpec {
+            gain_time_constant_ns: 10,
+        };
+
+        let entry = externs.new_typed_entry::<Lpf>("lpf_b", Some(&spec));
+        assert_eq!(entry.extern_type_id, 0x81);
+        assert_eq!(entry.extern_id, 0x8100_0002);
+        assert_eq!(externs.decode_entry::<Lpf>(&entry).unwrap(), Some(spec));
+        assert!(externs
+            .decode_entry::<Lpf>(&externs.new_entry("Lpf", "lpf_b", Some(Default::default())))
+            .is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn write_and_read_entries() {
+        use crate::testing::MockServer;
+
+        let server = MockServer::new();
+        let mut client = Client::builder()
+            .device_id(1)
+            .election_id(p4_v1::Uint128 { high: 0, low: 1 })
+            .build()
+            .unwrap();
+        client.connect_with_channel(server.channel().await.unwrap());
+        client.run().await.unwrap();
+        client.p4info_mut().load(p4info());
+        client.set_forwarding_pipeline_config(vec![]).await.unwrap();
+
+        let externs = Extern::new(&client);
+        let spec = |gain_time_constant_ns| LpfSpec {
+            gain_time_constant_ns,
+        };
+
+        let entry = externs.new_typed_entry::<Lpf>("lpf_a", Some(&spec(10)));
+        assert_eq!(entry.extern_type_id, 0x81);
+        assert_eq!(entry.extern_id, 0x8100_0001);
+        externs.insert_entry(entry).await.unwrap();
+        externs
+            .insert_entry(externs.new_typed_entry::<Lpf>("lpf_b", Some(&spec(20))))
+            .await
+            .unwrap();
+        assert_eq!(
+            externs.read_typed::<Lpf>("lpf_a").await.unwrap(),
+            Some(spec(10))
+        );
+
+        externs
+            .modify_typed::<Lpf>("lpf_a", &spec(30))
+            .await
+            .unwrap();
+        assert_eq!(
+            externs.read_typed::<Lpf>("lpf_a").await.unwrap(),
+            Some(spec(30))
+        );
+
+        // Unknown instance names are wildcards
+        let all = externs.new_entry("Lpf", "", None);
+        assert_eq!(externs.read_entries(all.clone()).await.unwrap().len(), 2);
+
+        externs
+            .delete_entry(externs.new_typed_entry::<Lpf>("lpf_b", None))
+            .await
+            .unwrap();
+        let entries = externs.read_entries(all).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            externs.decode_entry::<Lpf>(&entries[0]).unwrap(),
+            Some(spec(30))
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Management of many devices in one process
+//!
+//! A [`Fleet`] owns one [`Client`] per device, keyed by device id, name or
+//! any other ordered key. Operations run on all clients concurrently and
+//! return one result per device, so a failing device does not stop the
+//! others.
+
+use std::collections::BTreeMap;
+
+use futures_util::future::{join_all, BoxFuture};
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+use p4runtime::p4::v1 as p4_v1;
+
+use crate::{
+    client::{Client, PipelineBootstrap},
+    error::ClientError,
+};
+
+/// Per-device results of a fleet operation
+pub type FleetResults<K, T> = BTreeMap<K, Result<T, ClientError>>;
+
+/// A set of clients keyed by device
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use p4runtime_client::{client::Client, fleet::Fleet};
+/// # use p4runtime_client::p4runtime::p4::v1 as p4_v1;
+/// # async fn example(updates: Vec<p4_v1::Update>) {
+/// let mut fleet = Fleet::new();
+/// for (i, name) in ["leaf1", "leaf2", "spine1"].into_iter().enumerate() {
+///     let client = Client::builder()
+///         .device_id(i as u64)
+///         .election_id(p4_v1::Uint128 { high: 0, low: 1 })
+///         .build()
+///         .unwrap();
+///     fleet.insert(name.to_string(), client);
+/// }
+///
+/// fleet.connect(|name| format!("http://{}:9559", name)).await;
+/// fleet.run().await;
+///
+/// let results = fleet
+///     .for_each(|_, client| Box::pin(client.write_update_batch(updates.clone())))
+///     .await;
+/// for (name, result) in results {
+///     if let Err(e) = result {
+///         eprintln!("{}: {}", name, e);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Fleet<K = u64> {
+    clients: BTreeMap<K, Client>,
+}
+
+impl<K> Default for Fleet<K> {
+    fn default() -> Self {
+        Fleet {
+            clients: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: Ord + Clone> Fleet<K> {
+    /// Create an empty fleet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a client, returning the client previously stored under the key
+    pub fn insert(&mut self, key: K, client: Client) -> Option<Client> {
+        self.clients.insert(key, client)
+    }
+
+    /// Remove a client
+    pub fn remove(&mut self, key: &K) -> Option<Client> {
+        self.clients.remove(key)
+    }
+
+    /// Get a client
+    pub fn get(&self, key: &K) -> Option<&Client> {
+        self.clients.get(key)
+    }
+
+    /// Get a mutable client
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut Client> {
+        self.clients.get_mut(key)
+    }
+
+    /// Keys of all clients, in order
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.clients.keys()
+    }
+
+    /// Iterate over all clients, in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &Client)> {
+        self.clients.iter()
+    }
+
+    /// Iterate mutably over all clients, in key order
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut Client)> {
+        self.clients.iter_mut()
+    }
+
+    /// Number of clients
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Whether the fleet has no client
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Run an operation on all clients concurrently
+    ///
+    /// The operation returns a boxed future borrowing the client, e.g.
+    /// `|_, client| Box::pin(client.capabilities())`.
+    pub async fn for_each<F, T>(&mut self, f: F) -> FleetResults<K, T>
+    where
+        F: for<'a> Fn(&'a K, &'a mut Client) -> BoxFuture<'a, Result<T, ClientError>>,
+    {
+        let f = &f;
+        join_all(
+            self.clients
+                .iter_mut()
+                .map(|(key, client)| async move { (key.clone(), f(key, client).await) }),
+        )
+        .await
+        .into_iter()
+        .collect()
+    }
+
+    /// Connect all clients concurrently
+    ///
+    /// `endpoint` gives the address of each device, e.g.
+    /// `|name| format!("http://{}:9559", name)`.
+    pub async fn connect<D, F>(&mut self, endpoint: F) -> FleetResults<K, ()>
+    where
+        F: Fn(&K) -> D,
+        D: TryInto<tonic::transport::Endpoint> + Send + 'static,
+        D::Error: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    {
+        self.for_each(|key, client| {
+            let dst = endpoint(key);
+            Box::pin(async move { Ok(client.connect(dst).await?) })
+        })
+        .await
+    }
+
+    /// Open the stream channel and arbitrate on all clients concurrently
+    pub async fn run(&mut self) -> FleetResults<K, ()> {
+        self.for_each(|_, client| Box::pin(client.run())).await
+    }
+
+    /// Load a P4Info and push a pipeline to all devices concurrently
+    pub async fn set_forwarding_pipeline_config(
+        &mut self,
+        p4info: &p4_cfg_v1::P4Info,
+        p4_device_config: &[u8],
+    ) -> FleetResults<K, ()> {
+        self.for_each(|_, client| {
+            client.p4info_mut().load(p4info.clone());
+            let p4_device_config = p4_device_config.to_vec();
+            Box::pin(async move {
+                client
+                    .set_forwarding_pipeline_config(p4_device_config)
+                    .await?;
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    /// Bring up a pipeline on all devices concurrently
+    ///
+    /// See [`Client::bootstrap_pipeline`].
+    pub async fn bootstrap_pipeline(
+        &mut self,
+        p4info: &p4_cfg_v1::P4Info,
+        p4_device_config: &[u8],
+    ) -> FleetResults<K, PipelineBootstrap> {
+        self.for_each(|_, client| {
+            Box::pin(client.bootstrap_pipeline(p4info.clone(), p4_device_config.to_vec()))
+        })
+        .await
+    }
+
+    /// Write the same updates to all devices concurrently
+    pub async fn write_update_batch(
+        &mut self,
+        updates: &[p4_v1::Update],
+    ) -> FleetResults<K, tonic::Response<p4_v1::WriteResponse>> {
+        self.for_each(|_, client| Box::pin(client.write_update_batch(updates.to_vec())))
+            .await
+    }
+
+    /// Read the same entity from all devices concurrently
+    pub async fn read_entities(
+        &mut self,
+        entity: &p4_v1::Entity,
+    ) -> FleetResults<K, Vec<p4_v1::Entity>> {
+        self.for_each(|_, client| Box::pin(client.read_entities(entity.clone())))
+            .await
+    }
+
+    /// Stop all clients
+    pub async fn quit(&mut self) {
+        join_all(self.clients.values_mut().map(|client| client.quit())).await;
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::testing::MockServer;
+
+    fn insert(table_id: u32) -> p4_v1::Update {
+        p4_v1::Update {
+            r#type: p4_v1::update::Type::Insert as i32,
+            entity: Some(p4_v1::Entity {
+                entity: Some(p4_v1::entity::Entity::TableEntry(p4_v1::TableEntry {
+                    table_id,
+                    ..Default::default()
+                })),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn report_results_per_device() {
+        let servers = [MockServer::new(), MockServer::new()];
+        let mut fleet = Fleet::new();
+        for (name, server) in ["leaf1", "leaf2"].into_iter().zip(&servers) {
+            let mut client = Client::builder()
+                .device_id(1)
+                .election_id(p4_v1::Uint128 { high: 0, low: 1 })
+                .build()
+                .unwrap();
+            client.connect_with_channel(server.channel().await.unwrap());
+            fleet.insert(name, client);
+        }
+
+        assert!(fleet.run().await.values().all(Result::is_ok));
+        let results = fleet
+            .set_forwarding_pipeline_config(&p4_cfg_v1::P4Info::default(), &[])
+            .await;
+        assert!(results.values().all(Result::is_ok));
+
+        // The entry already exists on leaf2 only
+        servers[1].insert_entities(insert(1).entity);
+        let results = fleet.write_update_batch(&[insert(1)]).await;
+        assert_eq!(
+            results.keys().copied().collect::<Vec<_>>(),
+            ["leaf1", "leaf2"]
+        );
+        assert!(results["leaf1"].is_ok());
+        assert!(results["leaf2"].is_err());
+
+        let results = fleet.read_entities(&insert(0).entity.unwrap()).await;
+        assert_eq!(results["leaf1"].as_ref().unwrap().len(), 1);
+        assert_eq!(results["leaf2"].as_ref().unwrap().len(), 1);
+
+        fleet.quit().await;
+    }
+}
@@ -2,14 +2,30 @@
 
 #![deny(missing_docs)]
 
+pub mod cache;
 pub mod client;
+pub mod codegen;
 pub mod config;
 pub mod counter;
 pub mod digest;
+pub mod externs;
+pub mod fleet;
 pub mod p4info;
+pub mod record;
+pub mod retry;
+pub mod role;
+pub mod sim;
+pub mod stream;
 pub mod table;
+#[cfg(test)]
+mod test_utils;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tls")]
+pub mod tls;
 pub mod utils;
 
+pub use ipnet;
 pub use p4runtime;
 
 #[allow(missing_docs)]
@@ -24,14 +40,23 @@ pub mod error {
         ClientError = {
             #[display("Please connect to server first")]
             MissingP4rtClient,
+            #[display("Please run the client first")]
+            NotRunning,
             Timeout,
+            #[display("The stream channel is closed")]
+            Closed,
+            #[display("Lagged behind, {n} messages were dropped")]
+            Lagged {
+                n: u64,
+            },
             ArbitrationFailed,
             NoneEntity,
             MultipleEntities {
                 n: usize,
             },
-            UnexpectedEntry
-        } || TonicStatus || TokioError;
+            UnexpectedEntry,
+            Transport(tonic::transport::Error),
+        } || TonicStatus || TokioError || ExtensionError || RoleError || ReconcileError || NamedEntityError;
         MakeFieldMatchError = {
             UnexistedField {
                 field_name: String,
@@ -43,11 +68,294 @@ pub mod error {
             MissingSecondValue,
             ExpectedVec,
             ExpectedI32
+        } || MatchValueError;
+        ExtensionError = {
+            TypeUrlMismatch {
+                expected: String,
+                found: String,
+            },
+            Decode(prost::DecodeError),
+        };
+        P4InfoFileError = {
+            Io(std::io::Error),
+            Decode(prost::DecodeError),
+            Json(serde_json::Error),
+            TextFormat(crate::p4info::text_format::TextFormatError),
+            Utf8(std::str::Utf8Error),
+            #[display("P4Info is not loaded")]
+            P4InfoNotLoaded,
+        };
+        PipelineConfigError = {
+            MissingFile {
+                file: String,
+                dir: std::path::PathBuf,
+            },
+            AmbiguousFile {
+                file: String,
+                candidates: Vec<std::path::PathBuf>,
+            },
+            AmbiguousPipe {
+                pipes: Vec<String>,
+            },
+            UnknownPipe {
+                pipe: String,
+                pipes: Vec<String>,
+            },
+            InvalidFile {
+                path: std::path::PathBuf,
+                reason: String,
+            },
+        } || P4InfoFileError;
+        TofinoConfigError = {
+            #[display("Tofino config truncated in {part} at offset {offset}: needed {needed} bytes, {available} available")]
+            Truncated {
+                part: String,
+                offset: usize,
+                needed: usize,
+                available: usize,
+            },
+            InvalidProgramName,
+            TrailingBytes {
+                n: usize,
+            },
+        };
+        RoleError = {
+            #[display("Entity {id} is outside the role")]
+            OutsideRole {
+                id: u32,
+            },
+            #[display("Packet replication engine entries are outside the role")]
+            PacketReplicationEngineOutsideRole,
+            UnknownObject {
+                name: String,
+            },
+            #[display("P4Info is not loaded")]
+            P4InfoNotLoaded,
+        };
+        ReconcileError = {
+            #[display("Desired entry {index} is a default entry")]
+            DefaultEntry {
+                index: usize,
+            },
+            #[display("Desired entry {index} has the same key as a previous one")]
+            DuplicateKey {
+                index: usize,
+            },
+            #[display("Desired entry {index} belongs to table {table_id}")]
+            ForeignTable {
+                index: usize,
+                table_id: u32,
+            },
+        };
+        RecordError = {
+            Io(std::io::Error),
+            Decode(prost::DecodeError),
+        };
+        SimError = {
+            #[display("Entity already exists")]
+            AlreadyExists,
+            #[display("Entity not found")]
+            NotFound,
+            #[display("Update has no type")]
+            MissingUpdateType,
+            #[display("Update has no entity")]
+            MissingEntity,
+            #[display("{kind} entities only support MODIFY")]
+            ModifyOnly {
+                kind: String,
+            },
+            #[display("Unknown {kind} id {id}")]
+            UnknownId {
+                kind: String,
+                id: u32,
+            },
+            #[display("Table {table} is full ({size} entries)")]
+            TableFull {
+                table: String,
+                size: i64,
+            },
+            #[display("Table {table} is const")]
+            ConstTable {
+                table: String,
+            },
+            #[display("The default action of table {table} is const")]
+            ConstDefaultAction {
+                table: String,
+            },
+            #[display("Table {table} requires a priority")]
+            MissingPriority {
+                table: String,
+            },
+            #[display("Table {table} does not take a priority")]
+            UnexpectedPriority {
+                table: String,
+            },
+            #[display("Default entries of table {table} have no match fields")]
+            DefaultEntryWithMatch {
+                table: String,
+            },
+            #[display("Match field {field_id} is not in table {table}")]
+            UnknownMatchField {
+                table: String,
+                field_id: u32,
+            },
+            #[display("Match field {field_id} of table {table} is repeated")]
+            DuplicateMatchField {
+                table: String,
+                field_id: u32,
+            },
+            #[display("Exact match field {field_id} of table {table} is missing")]
+            MissingExactMatch {
+                table: String,
+                field_id: u32,
+            },
+            #[display("Match field {field_id} of table {table} has the wrong match type")]
+            MatchTypeMismatch {
+                table: String,
+                field_id: u32,
+            },
+            #[display("Table {table} requires an action")]
+            MissingAction {
+                table: String,
+            },
+            #[display("Wrong kind of action for table {table}")]
+            ActionTypeMismatch {
+                table: String,
+            },
+            #[display("Action {action} is not an action of table {table}")]
+            ActionNotAllowed {
+                table: String,
+                action: String,
+            },
+            #[display("Action {action} can only be the default action of table {table}")]
+            DefaultOnlyAction {
+                table: String,
+                action: String,
+            },
+            #[display("Action {action} cannot be the default action of table {table}")]
+            TableOnlyAction {
+                table: String,
+                action: String,
+            },
+            #[display("Action {action} has no parameter {param_id}")]
+            UnknownActionParam {
+                action: String,
+                param_id: u32,
+            },
+            #[display("Action {action} expects {expected} parameters, found {found}")]
+            ActionParamCount {
+                action: String,
+                expected: usize,
+                found: usize,
+            },
+            #[display("Index {index} is out of range for {name} of size {size}")]
+            IndexOutOfRange {
+                name: String,
+                index: i64,
+                size: i64,
+            },
         };
         MakeTableActionError = {
             UnexistedAction {
                 action_name: String,
             },
         };
+        EntryBuilderError = {
+            #[display("Unknown table {table_name}")]
+            UnknownTable {
+                table_name: String,
+            },
+            #[display("Table {table_name} has no match field {field_name}")]
+            UnknownField {
+                table_name: String,
+                field_name: String,
+            },
+            #[display("Match field {field_name} is repeated")]
+            DuplicateField {
+                field_name: String,
+            },
+            #[display("Match field {field_name} is {expected}, not {found}")]
+            MatchKindMismatch {
+                field_name: String,
+                expected: String,
+                found: String,
+            },
+        } || MakeTableActionError || MatchValueError;
+        NamedEntityError = {
+            #[display("Unknown {kind} {name}")]
+            UnknownName {
+                kind: String,
+                name: String,
+            },
+            #[display("Unknown {kind} id {id}")]
+            UnknownId {
+                kind: String,
+                id: u32,
+            },
+            #[display("Invalid value {value} of {name}: {reason}")]
+            InvalidValue {
+                name: String,
+                value: String,
+                reason: String,
+            },
+            #[display("Action {action} has no value for parameter {param}")]
+            MissingParam {
+                action: String,
+                param: String,
+            },
+            #[display("Entry of table {table} has more than one kind of action")]
+            AmbiguousAction {
+                table: String,
+            },
+            #[display("{kind} entities have no name-based form")]
+            Unsupported {
+                kind: String,
+            },
+            #[display("P4Info is not loaded")]
+            P4InfoNotLoaded,
+        } || MatchValueError;
+        EntitiesFileError = {
+            Io(std::io::Error),
+            Json(serde_json::Error),
+            Yaml(serde_norway::Error),
+        } || NamedEntityError;
+        MatchValueError = {
+            #[display("Match field {field_id} is not in the table")]
+            UnknownFieldId {
+                field_id: u32,
+            },
+            #[display("Match field {field_name} has no value")]
+            MissingValue {
+                field_name: String,
+            },
+            #[display("Value of match field {field_name} exceeds {bitwidth} bits")]
+            ValueTooWide {
+                field_name: String,
+                bitwidth: i32,
+            },
+            #[display("Prefix length {prefix_len} of match field {field_name} is not within {bitwidth} bits")]
+            PrefixTooLong {
+                field_name: String,
+                prefix_len: i32,
+                bitwidth: i32,
+            },
+            #[display("Value of match field {field_name} has bits set beyond the /{prefix_len} prefix")]
+            BitsBeyondPrefix {
+                field_name: String,
+                prefix_len: i32,
+            },
+            #[display("Value of match field {field_name} has bits set outside the mask")]
+            BitsOutsideMask {
+                field_name: String,
+            },
+            #[display("Match field {field_name} matches everything and must be omitted")]
+            DontCare {
+                field_name: String,
+            },
+            #[display("Range of match field {field_name} has low > high")]
+            InvertedRange {
+                field_name: String,
+            },
+        };
     }
 }
@@ -1,11 +1,17 @@
 //! P4Info helper
 
-use std::{collections::HashMap, ops::Deref};
+use std::{collections::HashMap, ops::Deref, path::Path};
 
 use p4runtime::p4::config::v1 as p4_cfg_v1;
 use p4runtime::p4::v1 as p4_v1;
+use prost::Message;
 
+use crate::error::P4InfoFileError;
+
+pub mod display;
+pub mod named;
 pub mod table;
+pub mod text_format;
 
 /// P4Info Helper
 #[derive(Clone, Debug, Default)]
@@ -35,6 +41,10 @@ pub struct P4Info {
     register_id_map: HashMap<String, u32>,
     /// digest_name, id mapping
     digest_id_map: HashMap<String, u32>,
+    /// extern_type_name, extern_type_id mapping
+    extern_type_id_map: HashMap<String, u32>,
+    /// (extern_type_id, extern_instance_name), id mapping
+    extern_id_map: HashMap<(u32, String), u32>,
 
     table_map: HashMap<u32, p4_cfg_v1::Table>,
     action_map: HashMap<u32, p4_cfg_v1::Action>,
@@ -55,6 +65,11 @@ impl Deref for P4Info {
 }
 
 impl P4Info {
+    /// Internal P4Info object, if loaded
+    pub(crate) fn inner(&self) -> Option<&p4_cfg_v1::P4Info> {
+        self.p4info.as_ref()
+    }
+
     /// Load P4Info to P4Info Helper
     pub fn load(&mut self, p4info: p4_cfg_v1::P4Info) {
         self.p4info = Some(p4info);
@@ -304,6 +319,43 @@ impl P4Info {
                 ]
             })
             .collect();
+
+        self.extern_type_id_map = self
+            .p4info
+            .as_ref()
+            .unwrap()
+            .externs
+            .iter()
+            .map(|ext| (ext.extern_type_name.clone(), ext.extern_type_id))
+            .collect();
+
+        self.extern_id_map = self
+            .p4info
+            .as_ref()
+            .unwrap()
+            .externs
+            .iter()
+            .flat_map(|ext| {
+                ext.instances.iter().flat_map(|instance| {
+                    [
+                        (
+                            (
+                                ext.extern_type_id,
+                                instance.preamble.as_ref().unwrap().name.clone(),
+                            ),
+                            instance.preamble.as_ref().unwrap().id,
+                        ),
+                        (
+                            (
+                                ext.extern_type_id,
+                                instance.preamble.as_ref().unwrap().alias.clone(),
+                            ),
+                            instance.preamble.as_ref().unwrap().id,
+                        ),
+                    ]
+                })
+            })
+            .collect();
     }
 
     /// Find table id by table name
@@ -314,14 +366,14 @@ impl P4Info {
     }
 
     /// Find table by table id
-    pub fn get_table_by_id(&self, table_id: u32) -> Option<table::Table> {
+    pub fn get_table_by_id(&self, table_id: u32) -> Option<table::Table<'_>> {
         let info_table = self.table_map.get(&table_id);
 
         info_table.map(|info_table| table::Table::new(info_table, &self.action_map))
     }
 
     /// Find table by table name
-    pub fn get_table(&self, table_name: &str) -> Option<table::Table> {
+    pub fn get_table(&self, table_name: &str) -> Option<table::Table<'_>> {
         let table_id = self.table_id(table_name);
 
         self.get_table_by_id(table_id)
@@ -438,4 +490,140 @@ impl P4Info {
     pub fn digest_id(&self, digest_name: &str) -> u32 {
         *self.digest_id_map.get(digest_name).unwrap_or(&0)
     }
+
+    /// Find extern type id by extern type name
+    ///
+    /// If not found, return 0
+    pub fn extern_type_id(&self, extern_type_name: &str) -> u32 {
+        *self.extern_type_id_map.get(extern_type_name).unwrap_or(&0)
+    }
+
+    /// Find extern instance id by extern type id and instance name
+    ///
+    /// If not found, return 0 (wildcard)
+    pub fn extern_id(&self, extern_type_id: u32, extern_name: &str) -> u32 {
+        *self
+            .extern_id_map
+            .get(&(extern_type_id, extern_name.to_string()))
+            .unwrap_or(&0)
+    }
+}
+
+/// Serialization format of a P4Info file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum P4InfoFormat {
+    /// Protobuf binary, e.g. `p4info.bin`
+    Binary,
+    /// Protobuf text format, e.g. `p4info.txtpb`
+    Text,
+    /// Protobuf JSON mapping, e.g. `p4info.json`
+    Json,
+}
+
+impl P4InfoFormat {
+    /// Guess the format from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "bin" | "pb" | "binpb" => Some(P4InfoFormat::Binary),
+            "txt" | "txtpb" | "pbtxt" | "textproto" | "prototxt" => Some(P4InfoFormat::Text),
+            "json" => Some(P4InfoFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the file content
+    ///
+    /// Content that is not valid UTF-8 is binary, content starting with `{`
+    /// is JSON, anything else is text format.
+    pub fn sniff(content: &[u8]) -> Self {
+        match std::str::from_utf8(content) {
+            Err(_) => P4InfoFormat::Binary,
+            Ok(s) if s.trim_start().starts_with('{') => P4InfoFormat::Json,
+            Ok(_) => P4InfoFormat::Text,
+        }
+    }
+}
+
+impl P4Info {
+    /// Create a P4Info Helper from a P4Info object
+    pub fn new(p4info: p4_cfg_v1::P4Info) -> Self {
+        let mut helper = P4Info::default();
+        helper.load(p4info);
+        helper
+    }
+
+    /// Load a P4Info file
+    ///
+    /// The format is guessed from the file extension, falling back to the
+    /// file content.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, P4InfoFileError> {
+        let content = std::fs::read(path.as_ref())?;
+        let format =
+            P4InfoFormat::from_path(path.as_ref()).unwrap_or_else(|| P4InfoFormat::sniff(&content));
+
+        Self::from_bytes(&content, format)
+    }
+
+    /// Load P4Info from file content in the given format
+    pub fn from_bytes(content: &[u8], format: P4InfoFormat) -> Result<Self, P4InfoFileError> {
+        match format {
+            P4InfoFormat::Binary => Self::from_binary(content),
+            P4InfoFormat::Text => Self::from_text(std::str::from_utf8(content)?),
+            P4InfoFormat::Json => Ok(Self::new(serde_json::from_slice(content)?)),
+        }
+    }
+
+    /// Load P4Info from protobuf binary
+    pub fn from_binary(content: &[u8]) -> Result<Self, P4InfoFileError> {
+        Ok(Self::new(p4_cfg_v1::P4Info::decode(content)?))
+    }
+
+    /// Load P4Info from protobuf text format
+    pub fn from_text(content: &str) -> Result<Self, P4InfoFileError> {
+        let json = text_format::text_to_json(content)?;
+        Ok(Self::new(serde_json::from_value(json)?))
+    }
+
+    /// Load P4Info from protobuf JSON mapping
+    pub fn from_json(content: &str) -> Result<Self, P4InfoFileError> {
+        Ok(Self::new(serde_json::from_str(content)?))
+    }
+
+    /// Serialize the loaded P4Info as protobuf binary
+    pub fn to_binary(&self) -> Result<Vec<u8>, P4InfoFileError> {
+        Ok(self
+            .inner()
+            .ok_or(P4InfoFileError::P4InfoNotLoaded)?
+            .encode_to_vec())
+    }
+
+    /// Serialize the loaded P4Info as protobuf text format
+    pub fn to_text(&self) -> Result<String, P4InfoFileError> {
+        let json = serde_json::to_value(self.inner().ok_or(P4InfoFileError::P4InfoNotLoaded)?)?;
+        Ok(text_format::json_to_text(&json))
+    }
+
+    /// Serialize the loaded P4Info as protobuf JSON mapping
+    pub fn to_json(&self) -> Result<String, P4InfoFileError> {
+        Ok(serde_json::to_string_pretty(
+            self.inner().ok_or(P4InfoFileError::P4InfoNotLoaded)?,
+        )?)
+    }
+
+    /// Write the loaded P4Info to a file in the given format
+    pub fn write_file(
+        &self,
+        path: impl AsRef<Path>,
+        format: P4InfoFormat,
+    ) -> Result<(), P4InfoFileError> {
+        let content = match format {
+            P4InfoFormat::Binary => self.to_binary()?,
+            P4InfoFormat::Text => self.to_text()?.into_bytes(),
+            P4InfoFormat::Json => self.to_json()?.into_bytes(),
+        };
+
+        Ok(std::fs::write(path, content)?)
+    }
 }
@@ -0,0 +1,1052 @@
+//! Human-readable formatting of P4Runtime messages
+//!
+//! [`P4Info::display`] wraps a message so that it is formatted with the names
+//! from the P4Info instead of numeric ids, e.g.
+//!
+//! ```text
+//! ipv4_lpm: hdr.ipv4.dstAddr=10.0.1.2/32 -> ipv4_forward(dstAddr=08:00:00:00:01:02, port=1)
+//! ```
+//!
+//! Values are rendered in decimal up to 64 bits and in hexadecimal above.
+//! [`DisplayHints`] render fields, parameters and metadata as IPv4, IPv6 or
+//! MAC addresses, by name or by guessing from the name and bitwidth.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use p4runtime_client::p4info::{display::DisplayHints, P4Info};
+//! # use p4runtime_client::p4runtime::p4::v1 as p4_v1;
+//! # fn example(p4info: &P4Info, entry: &p4_v1::TableEntry) {
+//! let hints = DisplayHints::guess();
+//! log::info!("Installing {}", p4info.display_with(entry, &hints));
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+use p4runtime::p4::v1 as p4_v1;
+
+use super::P4Info;
+
+/// How a value is rendered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueFormat {
+    /// Decimal integer
+    Decimal,
+    /// Hexadecimal integer, prefixed with `0x`
+    Hex,
+    /// Dotted IPv4 address
+    Ipv4,
+    /// IPv6 address
+    Ipv6,
+    /// Colon-separated MAC address
+    Mac,
+}
+
+/// Rendering hints of values
+#[derive(Clone, Debug, Default)]
+pub struct DisplayHints {
+    formats: HashMap<String, ValueFormat>,
+    guess: bool,
+}
+
+impl DisplayHints {
+    /// Create hints guessing addresses from names and bitwidths
+    ///
+    /// 32, 48 and 128 bits values whose name contains `addr` are rendered as
+    /// IPv4, MAC and IPv6 addresses, as are values whose name contains `ip`
+    /// or `mac` with the matching bitwidth.
+    pub fn guess() -> Self {
+        DisplayHints {
+            guess: true,
+            ..Default::default()
+        }
+    }
+
+    /// Render a value as `format`
+    ///
+    /// `name` is the full or last dot-separated name of a match field, action
+    /// parameter or packet metadata, or the name of its P4 type.
+    pub fn hint(mut self, name: impl Into<String>, format: ValueFormat) -> Self {
+        self.formats.insert(name.into(), format);
+        self
+    }
+
+    pub(crate) fn format(&self, name: &str, type_name: Option<&str>, bitwidth: i32) -> ValueFormat {
+        let short = name.rsplit('.').next().unwrap_or(name);
+        let hinted = [Some(name), Some(short), type_name]
+            .into_iter()
+            .flatten()
+            .find_map(|name| self.formats.get(name));
+        if let Some(format) = hinted {
+            return *format;
+        }
+
+        if self.guess {
+            let lower = short.to_lowercase();
+            let addr = lower.contains("addr");
+            match bitwidth {
+                32 if addr || lower.contains("ip") => return ValueFormat::Ipv4,
+                48 if addr || lower.contains("mac") => return ValueFormat::Mac,
+                128 if addr || lower.contains("ip") => return ValueFormat::Ipv6,
+                _ => {}
+            }
+        }
+
+        if bitwidth > 64 {
+            ValueFormat::Hex
+        } else {
+            ValueFormat::Decimal
+        }
+    }
+}
+
+/// Formatting context of [`P4Display`]
+pub struct Context<'a> {
+    p4info: &'a P4Info,
+    hints: Option<&'a DisplayHints>,
+}
+
+/// A message which can be formatted with P4Info names
+pub trait P4Display {
+    /// Format the message
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+/// A message formatted with P4Info names, see [`P4Info::display`]
+pub struct Displayed<'a, T: ?Sized> {
+    value: &'a T,
+    ctx: Context<'a>,
+}
+
+impl<T: P4Display + ?Sized> fmt::Display for Displayed<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_p4(&self.ctx, f)
+    }
+}
+
+impl P4Info {
+    /// Format a message with the names of this P4Info
+    ///
+    /// Ids missing from the P4Info are rendered as `<kind>#<id>`.
+    pub fn display<'a, T: P4Display + ?Sized>(&'a self, value: &'a T) -> Displayed<'a, T> {
+        Displayed {
+            value,
+            ctx: Context {
+                p4info: self,
+                hints: None,
+            },
+        }
+    }
+
+    /// Format a message with the names of this P4Info and rendering hints
+    pub fn display_with<'a, T: P4Display + ?Sized>(
+        &'a self,
+        value: &'a T,
+        hints: &'a DisplayHints,
+    ) -> Displayed<'a, T> {
+        Displayed {
+            value,
+            ctx: Context {
+                p4info: self,
+                hints: Some(hints),
+            },
+        }
+    }
+}
+
+impl Context<'_> {
+    /// Short name of a P4 object, or `<kind>#<id>` if it is unknown
+    fn name(&self, kind: &str, id: u32) -> String {
+        macro_rules! find {
+            ($objects:expr) => {
+                $objects
+                    .iter()
+                    .filter_map(|o| o.preamble.as_ref())
+                    .find(|p| p.id == id)
+            };
+        }
+
+        let preamble = match kind {
+            "table" => self.table(id).and_then(|t| t.preamble.as_ref()),
+            "action" => self.action(id).and_then(|a| a.preamble.as_ref()),
+            _ => self.p4info.inner().and_then(|p4info| match kind {
+                "action_profile" => find!(p4info.action_profiles),
+                "counter" => find!(p4info.counters),
+                "direct_counter" => find!(p4info.direct_counters),
+                "meter" => find!(p4info.meters),
+                "direct_meter" => find!(p4info.direct_meters),
+                "value_set" => find!(p4info.value_sets),
+                "register" => find!(p4info.registers),
+                "digest" => find!(p4info.digests),
+                _ => None,
+            }),
+        };
+
+        match preamble {
+            Some(p) if !p.alias.is_empty() => p.alias.clone(),
+            Some(p) => p.name.clone(),
+            None => format!("{}#{}", kind, id),
+        }
+    }
+
+    fn table(&self, id: u32) -> Option<&p4_cfg_v1::Table> {
+        self.p4info.table_map.get(&id)
+    }
+
+    fn action(&self, id: u32) -> Option<&p4_cfg_v1::Action> {
+        self.p4info.action_map.get(&id)
+    }
+
+    fn packet_metadata(&self, name: &str) -> Option<&p4_cfg_v1::ControllerPacketMetadata> {
+        self.p4info
+            .inner()?
+            .controller_packet_metadata
+            .iter()
+            .find(|m| m.preamble.as_ref().is_some_and(|p| p.name == name))
+    }
+
+    fn value(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: &str,
+        type_name: Option<&p4_cfg_v1::P4NamedType>,
+        bitwidth: i32,
+        value: &[u8],
+    ) -> fmt::Result {
+        let format = match self.hints {
+            Some(hints) => hints.format(name, type_name.map(|t| t.name.as_str()), bitwidth),
+            None => DisplayHints::default().format(name, None, bitwidth),
+        };
+        write_value(f, format, value)
+    }
+
+    fn field_match(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        table: Option<&p4_cfg_v1::Table>,
+        field_match: &p4_v1::FieldMatch,
+    ) -> fmt::Result {
+        use p4_v1::field_match::FieldMatchType;
+
+        let field =
+            table.and_then(|t| t.match_fields.iter().find(|m| m.id == field_match.field_id));
+        let name = field
+            .map(|m| m.name.clone())
+            .unwrap_or_else(|| format!("field#{}", field_match.field_id));
+        let type_name = field.and_then(|m| m.type_name.as_ref());
+        let bitwidth = field.map(|m| m.bitwidth).unwrap_or_default();
+
+        write!(f, "{}", name)?;
+        match &field_match.field_match_type {
+            Some(FieldMatchType::Exact(m)) => {
+                write!(f, "=")?;
+                self.value(f, &name, type_name, bitwidth, &m.value)
+            }
+            Some(FieldMatchType::Optional(m)) => {
+                write!(f, "=")?;
+                self.value(f, &name, type_name, bitwidth, &m.value)
+            }
+            Some(FieldMatchType::Lpm(m)) => {
+                write!(f, "=")?;
+                self.value(f, &name, type_name, bitwidth, &m.value)?;
+                write!(f, "/{}", m.prefix_len)
+            }
+            Some(FieldMatchType::Ternary(m)) => {
+                write!(f, "=")?;
+                self.value(f, &name, type_name, bitwidth, &m.value)?;
+                write!(f, "&&&")?;
+                write_value(f, ValueFormat::Hex, &m.mask)
+            }
+            Some(FieldMatchType::Range(m)) => {
+                write!(f, "=")?;
+                self.value(f, &name, type_name, bitwidth, &m.low)?;
+                write!(f, "..")?;
+                self.value(f, &name, type_name, bitwidth, &m.high)
+            }
+            Some(FieldMatchType::Other(_)) => write!(f, "=<other>"),
+            None => write!(f, "=<none>"),
+        }
+    }
+
+    fn action_call(&self, f: &mut fmt::Formatter<'_>, action: &p4_v1::Action) -> fmt::Result {
+        let info = self.action(action.action_id);
+
+        write!(f, "{}(", self.name("action", action.action_id))?;
+        for (i, param) in action.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            let info = info.and_then(|a| a.params.iter().find(|p| p.id == param.param_id));
+            let name = info
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| format!("param#{}", param.param_id));
+            write!(f, "{}=", name)?;
+            self.value(
+                f,
+                &name,
+                info.and_then(|p| p.type_name.as_ref()),
+                info.map(|p| p.bitwidth).unwrap_or_default(),
+                &param.value,
+            )?;
+        }
+        write!(f, ")")
+    }
+
+    fn metadata(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        header: &str,
+        metadata: &[p4_v1::PacketMetadata],
+    ) -> fmt::Result {
+        let info = self.packet_metadata(header);
+
+        write!(f, "{}(", header)?;
+        for (i, m) in metadata.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            let info = info.and_then(|h| h.metadata.iter().find(|i| i.id == m.metadata_id));
+            let name = info
+                .map(|i| i.name.clone())
+                .unwrap_or_else(|| format!("metadata#{}", m.metadata_id));
+            write!(f, "{}=", name)?;
+            self.value(
+                f,
+                &name,
+                info.and_then(|i| i.type_name.as_ref()),
+                info.map(|i| i.bitwidth).unwrap_or_default(),
+                &m.value,
+            )?;
+        }
+        write!(f, ")")
+    }
+
+    fn data(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: &str,
+        type_spec: Option<&p4_cfg_v1::P4DataTypeSpec>,
+        data: &p4_v1::P4Data,
+    ) -> fmt::Result {
+        use p4_cfg_v1::p4_data_type_spec::TypeSpec;
+        use p4_v1::p4_data::Data;
+
+        let members = |struct_name: &str| {
+            self.p4info
+                .inner()
+                .and_then(|p| p.type_info.as_ref())
+                .and_then(|t| t.structs.get(struct_name))
+                .map(|s| s.members.as_slice())
+                .unwrap_or_default()
+        };
+
+        match &data.data {
+            Some(Data::Bitstring(value)) => {
+                let bitwidth = match type_spec.and_then(|t| t.type_spec.as_ref()) {
+                    Some(TypeSpec::Bitstring(b)) => bitstring_width(b),
+                    _ => 0,
+                };
+                self.value(f, name, None, bitwidth, value)
+            }
+            Some(Data::Bool(b)) => write!(f, "{}", b),
+            Some(Data::Struct(s)) | Some(Data::Tuple(s)) => {
+                let members = match type_spec.and_then(|t| t.type_spec.as_ref()) {
+                    Some(TypeSpec::Struct(t)) => members(&t.name),
+                    _ => &[],
+                };
+
+                write!(f, "{{")?;
+                for (i, data) in s.members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match members.get(i) {
+                        Some(member) => {
+                            write!(f, "{}=", member.name)?;
+                            self.data(f, &member.name, member.type_spec.as_ref(), data)?;
+                        }
+                        None => self.data(f, "", None, data)?,
+                    }
+                }
+                write!(f, "}}")
+            }
+            Some(Data::Enum(s)) | Some(Data::Error(s)) => write!(f, "{}", s),
+            Some(Data::EnumValue(value)) => write_value(f, ValueFormat::Decimal, value),
+            Some(Data::Varbit(v)) => write_value(f, ValueFormat::Hex, &v.bitstring),
+            Some(_) => write!(f, "<header>"),
+            None => write!(f, "<none>"),
+        }
+    }
+}
+
+fn bitstring_width(spec: &p4_cfg_v1::P4BitstringLikeTypeSpec) -> i32 {
+    use p4_cfg_v1::p4_bitstring_like_type_spec::TypeSpec;
+
+    match &spec.type_spec {
+        Some(TypeSpec::Bit(b)) => b.bitwidth,
+        Some(TypeSpec::Int(i)) => i.bitwidth,
+        Some(TypeSpec::Varbit(v)) => v.max_bitwidth,
+        None => 0,
+    }
+}
+
+/// Render a value as a string
+pub(crate) fn value_to_string(format: ValueFormat, value: &[u8]) -> String {
+    struct Value<'a>(ValueFormat, &'a [u8]);
+
+    impl fmt::Display for Value<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_value(f, self.0, self.1)
+        }
+    }
+
+    Value(format, value).to_string()
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, format: ValueFormat, value: &[u8]) -> fmt::Result {
+    let start = value.iter().position(|&b| b != 0).unwrap_or(value.len());
+    let significant = &value[start..];
+
+    let padded = |n: usize| -> Option<Vec<u8>> {
+        (significant.len() <= n).then(|| {
+            let mut bytes = vec![0; n - significant.len()];
+            bytes.extend_from_slice(significant);
+            bytes
+        })
+    };
+
+    match format {
+        ValueFormat::Ipv4 => {
+            if let Some(b) = padded(4) {
+                return write!(f, "{}", Ipv4Addr::new(b[0], b[1], b[2], b[3]));
+            }
+        }
+        ValueFormat::Ipv6 => {
+            if let Some(b) = padded(16) {
+                let octets: [u8; 16] = b.try_into().unwrap_or_default();
+                return write!(f, "{}", Ipv6Addr::from(octets));
+            }
+        }
+        ValueFormat::Mac => {
+            if let Some(b) = padded(6) {
+                return write!(
+                    f,
+                    "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                    b[0], b[1], b[2], b[3], b[4], b[5]
+                );
+            }
+        }
+        ValueFormat::Decimal if significant.len() <= 16 => {
+            let n = significant
+                .iter()
+                .fold(0u128, |n, &b| (n << 8) | u128::from(b));
+            return write!(f, "{}", n);
+        }
+        _ => {}
+    }
+
+    // Hexadecimal, and fallback of values too wide for the format
+    write!(f, "0x")?;
+    if significant.is_empty() {
+        return write!(f, "0");
+    }
+    for b in significant {
+        write!(f, "{:02x}", b)?;
+    }
+    Ok(())
+}
+
+fn write_counter(f: &mut fmt::Formatter<'_>, data: &p4_v1::CounterData) -> fmt::Result {
+    write!(
+        f,
+        "packets={}, bytes={}",
+        data.packet_count, data.byte_count
+    )
+}
+
+fn write_meter(f: &mut fmt::Formatter<'_>, config: &p4_v1::MeterConfig) -> fmt::Result {
+    write!(
+        f,
+        "cir={}, cburst={}, pir={}, pburst={}",
+        config.cir, config.cburst, config.pir, config.pburst
+    )?;
+    if config.eburst != 0 {
+        write!(f, ", eburst={}", config.eburst)?;
+    }
+    Ok(())
+}
+
+fn write_index(f: &mut fmt::Formatter<'_>, index: &Option<p4_v1::Index>) -> fmt::Result {
+    match index {
+        Some(index) => write!(f, "[{}]", index.index),
+        None => write!(f, "[*]"),
+    }
+}
+
+fn write_replicas(f: &mut fmt::Formatter<'_>, replicas: &[p4_v1::Replica]) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, replica) in replicas.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+
+        match &replica.port_kind {
+            Some(p4_v1::replica::PortKind::Port(port)) => {
+                write_value(f, ValueFormat::Decimal, port)?
+            }
+            #[allow(deprecated)]
+            None => write!(f, "{}", replica.egress_port)?,
+        }
+        if replica.instance != 0 {
+            write!(f, "#{}", replica.instance)?;
+        }
+    }
+    write!(f, "]")
+}
+
+impl P4Display for p4_v1::TableEntry {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use p4_v1::table_action::Type;
+
+        let table = ctx.table(self.table_id);
+        write!(f, "{}: ", ctx.name("table", self.table_id))?;
+
+        if self.is_default_action {
+            write!(f, "default")?;
+        } else if self.r#match.is_empty() {
+            write!(f, "*")?;
+        }
+        for (i, field_match) in self.r#match.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            ctx.field_match(f, table, field_match)?;
+        }
+        if self.priority != 0 {
+            write!(f, " priority={}", self.priority)?;
+        }
+
+        match self.action.as_ref().and_then(|a| a.r#type.as_ref()) {
+            Some(Type::Action(action)) => {
+                write!(f, " -> ")?;
+                ctx.action_call(f, action)
+            }
+            Some(Type::ActionProfileMemberId(id)) => write!(f, " -> member {}", id),
+            Some(Type::ActionProfileGroupId(id)) => write!(f, " -> group {}", id),
+            Some(Type::ActionProfileActionSet(set)) => {
+                write!(f, " -> [")?;
+                for (i, a) in set.action_profile_actions.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if let Some(action) = &a.action {
+                        ctx.action_call(f, action)?;
+                    }
+                    write!(f, " x{}", a.weight)?;
+                }
+                write!(f, "]")
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl P4Display for p4_v1::CounterEntry {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", ctx.name("counter", self.counter_id))?;
+        write_index(f, &self.index)?;
+        if let Some(data) = &self.data {
+            write!(f, ": ")?;
+            write_counter(f, data)?;
+        }
+        Ok(())
+    }
+}
+
+impl P4Display for p4_v1::DirectCounterEntry {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "counter of ")?;
+        match &self.table_entry {
+            Some(entry) => entry.fmt_p4(ctx, f)?,
+            None => write!(f, "*")?,
+        }
+        if let Some(data) = &self.data {
+            write!(f, " => ")?;
+            write_counter(f, data)?;
+        }
+        Ok(())
+    }
+}
+
+impl P4Display for p4_v1::MeterEntry {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", ctx.name("meter", self.meter_id))?;
+        write_index(f, &self.index)?;
+        if let Some(config) = &self.config {
+            write!(f, ": ")?;
+            write_meter(f, config)?;
+        }
+        Ok(())
+    }
+}
+
+impl P4Display for p4_v1::DirectMeterEntry {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "meter of ")?;
+        match &self.table_entry {
+            Some(entry) => entry.fmt_p4(ctx, f)?,
+            None => write!(f, "*")?,
+        }
+        if let Some(config) = &self.config {
+            write!(f, " => ")?;
+            write_meter(f, config)?;
+        }
+        Ok(())
+    }
+}
+
+impl P4Display for p4_v1::PacketReplicationEngineEntry {
+    fn fmt_p4(&self, _ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use p4_v1::packet_replication_engine_entry::Type;
+
+        match &self.r#type {
+            Some(Type::MulticastGroupEntry(group)) => {
+                write!(f, "multicast group {}: ", group.multicast_group_id)?;
+                write_replicas(f, &group.replicas)
+            }
+            Some(Type::CloneSessionEntry(session)) => {
+                write!(f, "clone session {}: ", session.session_id)?;
+                write_replicas(f, &session.replicas)?;
+                if session.class_of_service != 0 {
+                    write!(f, " cos={}", session.class_of_service)?;
+                }
+                if session.packet_length_bytes != 0 {
+                    write!(f, " truncate={}", session.packet_length_bytes)?;
+                }
+                Ok(())
+            }
+            None => write!(f, "packet replication engine entry"),
+        }
+    }
+}
+
+impl P4Display for p4_v1::ActionProfileMember {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} member {}",
+            ctx.name("action_profile", self.action_profile_id),
+            self.member_id
+        )?;
+        if let Some(action) = &self.action {
+            write!(f, " -> ")?;
+            ctx.action_call(f, action)?;
+        }
+        Ok(())
+    }
+}
+
+impl P4Display for p4_v1::ActionProfileGroup {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} group {}: [",
+            ctx.name("action_profile", self.action_profile_id),
+            self.group_id
+        )?;
+        for (i, member) in self.members.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} x{}", member.member_id, member.weight)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl P4Display for p4_v1::RegisterEntry {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = ctx.name("register", self.register_id);
+        write!(f, "{}", name)?;
+        write_index(f, &self.index)?;
+
+        let type_spec = ctx.p4info.inner().and_then(|p| {
+            p.registers
+                .iter()
+                .find(|r| {
+                    r.preamble
+                        .as_ref()
+                        .is_some_and(|p| p.id == self.register_id)
+                })
+                .and_then(|r| r.type_spec.as_ref())
+        });
+        if let Some(data) = &self.data {
+            write!(f, " = ")?;
+            ctx.data(f, &name, type_spec, data)?;
+        }
+        Ok(())
+    }
+}
+
+impl P4Display for p4_v1::ValueSetEntry {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let match_fields = ctx.p4info.inner().and_then(|p| {
+            p.value_sets
+                .iter()
+                .find(|v| {
+                    v.preamble
+                        .as_ref()
+                        .is_some_and(|p| p.id == self.value_set_id)
+                })
+                .map(|v| p4_cfg_v1::Table {
+                    match_fields: v.r#match.clone(),
+                    ..Default::default()
+                })
+        });
+
+        write!(f, "{}: [", ctx.name("value_set", self.value_set_id))?;
+        for (i, member) in self.members.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            for (j, field_match) in member.r#match.iter().enumerate() {
+                if j > 0 {
+                    write!(f, ", ")?;
+                }
+                ctx.field_match(f, match_fields.as_ref(), field_match)?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+impl P4Display for p4_v1::DigestEntry {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", ctx.name("digest", self.digest_id))?;
+        if let Some(config) = &self.config {
+            write!(
+                f,
+                ": max_timeout_ns={}, max_list_size={}, ack_timeout_ns={}",
+                config.max_timeout_ns, config.max_list_size, config.ack_timeout_ns
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl P4Display for p4_v1::ExternEntry {
+    fn fmt_p4(&self, _ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "extern {}#{}", self.extern_type_id, self.extern_id)
+    }
+}
+
+impl P4Display for p4_v1::Entity {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use p4_v1::entity::Entity;
+
+        match &self.entity {
+            Some(Entity::ExternEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::TableEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::ActionProfileMember(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::ActionProfileGroup(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::MeterEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::DirectMeterEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::CounterEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::DirectCounterEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::PacketReplicationEngineEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::ValueSetEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::RegisterEntry(e)) => e.fmt_p4(ctx, f),
+            Some(Entity::DigestEntry(e)) => e.fmt_p4(ctx, f),
+            None => write!(f, "<empty entity>"),
+        }
+    }
+}
+
+impl P4Display for p4_v1::Update {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.r#type().as_str_name())?;
+        match &self.entity {
+            Some(entity) => entity.fmt_p4(ctx, f),
+            None => write!(f, "<empty entity>"),
+        }
+    }
+}
+
+impl P4Display for p4_v1::DigestList {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = ctx.name("digest", self.digest_id);
+        let type_spec = ctx.p4info.inner().and_then(|p| {
+            p.digests
+                .iter()
+                .find(|d| d.preamble.as_ref().is_some_and(|p| p.id == self.digest_id))
+                .and_then(|d| d.type_spec.as_ref())
+        });
+
+        write!(f, "{} list {}: [", name, self.list_id)?;
+        for (i, data) in self.data.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            ctx.data(f, &name, type_spec, data)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl P4Display for p4_v1::PacketIn {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        ctx.metadata(f, "packet_in", &self.metadata)?;
+        write!(f, " {} bytes", self.payload.len())
+    }
+}
+
+impl P4Display for p4_v1::PacketOut {
+    fn fmt_p4(&self, ctx: &Context<'_>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        ctx.metadata(f, "packet_out", &self.metadata)?;
+        write!(f, " {} bytes", self.payload.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{match_field, param, preamble};
+
+    #[test]
+    fn display_table_entry() {
+        use p4_cfg_v1::match_field::MatchType;
+
+        let p4info = P4Info::new(p4_cfg_v1::P4Info {
+            tables: vec![p4_cfg_v1::Table {
+                preamble: preamble(1, "MyIngress.ipv4_lpm", "ipv4_lpm"),
+                match_fields: vec![match_field(1, "hdr.ipv4.dstAddr", 32, MatchType::Lpm)],
+                ..Default::default()
+            }],
+            actions: vec![p4_cfg_v1::Action {
+                preamble: preamble(2, "MyIngress.ipv4_forward", "ipv4_forward"),
+                params: vec![param(1, "dstAddr", 48), param(2, "port", 9)],
+            }],
+            ..Default::default()
+        });
+
+        let entry = p4_v1::TableEntry {
+            table_id: 1,
+            r#match: vec![p4_v1::FieldMatch {
+                field_id: 1,
+                field_match_type: Some(p4_v1::field_match::FieldMatchType::Lpm(
+                    p4_v1::field_match::Lpm {
+                        value: vec![10, 0, 1, 2],
+                        prefix_len: 32,
+                    },
+                )),
+            }],
+            action: Some(p4_v1::TableAction {
+                r#type: Some(p4_v1::table_action::Type::Action(p4_v1::Action {
+                    action_id: 2,
+                    params: vec![
+                        p4_v1::action::Param {
+                            param_id: 1,
+                            value: vec![8, 0, 0, 0, 1, 2],
+                        },
+                        p4_v1::action::Param {
+                            param_id: 2,
+                            value: vec![1],
+                        },
+                    ],
+                })),
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            p4info
+                .display_with(&entry, &DisplayHints::guess())
+                .to_string(),
+            "ipv4_lpm: hdr.ipv4.dstAddr=10.0.1.2/32 -> ipv4_forward(dstAddr=08:00:00:00:01:02, port=1)"
+        );
+        assert_eq!(
+            p4info.display(&entry).to_string(),
+            "ipv4_lpm: hdr.ipv4.dstAddr=167772418/32 -> ipv4_forward(dstAddr=8796093022466, port=1)"
+        );
+
+        let counter = p4_v1::CounterEntry {
+            counter_id: 9,
+            index: Some(p4_v1::Index { index: 3 }),
+            data: Some(p4_v1::CounterData {
+                byte_count: 100,
+                packet_count: 1,
+            }),
+        };
+        assert_eq!(
+            p4info.display(&counter).to_string(),
+            "counter#9[3]: packets=1, bytes=100"
+        );
+    }
+
+    #[test]
+    fn display_other_entities() {
+        use p4_cfg_v1::{p4_bitstring_like_type_spec, p4_data_type_spec::TypeSpec};
+
+        let bit = |bitwidth| p4_cfg_v1::P4DataTypeSpec {
+            type_spec: Some(TypeSpec::Bitstring(p4_cfg_v1::P4BitstringLikeTypeSpec {
+                type_spec: Some(p4_bitstring_like_type_spec::TypeSpec::Bit(
+                    p4_cfg_v1::P4BitTypeSpec { bitwidth },
+                )),
+                ..Default::default()
+            })),
+        };
+        let p4info = P4Info::new(p4_cfg_v1::P4Info {
+            meters: vec![p4_cfg_v1::Meter {
+                preamble: preamble(3, "MyIngress.policer", "policer"),
+                ..Default::default()
+            }],
+            digests: vec![p4_cfg_v1::Digest {
+                preamble: preamble(4, "learn_t", ""),
+                type_spec: Some(p4_cfg_v1::P4DataTypeSpec {
+                    type_spec: Some(TypeSpec::Struct(p4_cfg_v1::P4NamedType {
+                        name: "learn_t".to_string(),
+                    })),
+                }),
+            }],
+            controller_packet_metadata: vec![p4_cfg_v1::ControllerPacketMetadata {
+                preamble: preamble(5, "packet_in", ""),
+                metadata: vec![p4_cfg_v1::controller_packet_metadata::Metadata {
+                    id: 1,
+                    name: "ingress_port".to_string(),
+                    bitwidth: 9,
+                    ..Default::default()
+                }],
+            }],
+            type_info: Some(p4_cfg_v1::P4TypeInfo {
+                structs: [(
+                    "learn_t".to_string(),
+                    p4_cfg_v1::P4StructTypeSpec {
+                        members: [("srcAddr", 48), ("port", 9)]
+                            .into_iter()
+                            .map(|(name, bitwidth)| p4_cfg_v1::p4_struct_type_spec::Member {
+                                name: name.to_string(),
+                                type_spec: Some(bit(bitwidth)),
+                            })
+                            .collect(),
+                        ..Default::default()
+                    },
+                )]
+                .into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        let meter = p4_v1::MeterEntry {
+            meter_id: 3,
+            index: Some(p4_v1::Index { index: 2 }),
+            config: Some(p4_v1::MeterConfig {
+                cir: 100,
+                cburst: 10,
+                pir: 200,
+                pburst: 20,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            p4info.display(&meter).to_string(),
+            "policer[2]: cir=100, cburst=10, pir=200, pburst=20"
+        );
+
+        // Ids are looked up by kind
+        let counter = p4_v1::CounterEntry {
+            counter_id: 3,
+            ..Default::default()
+        };
+        assert_eq!(p4info.display(&counter).to_string(), "counter#3[*]");
+
+        let replica = |port: u8, instance| p4_v1::Replica {
+            port_kind: Some(p4_v1::replica::PortKind::Port(vec![port])),
+            instance,
+            ..Default::default()
+        };
+        let group = p4_v1::PacketReplicationEngineEntry {
+            r#type: Some(
+                p4_v1::packet_replication_engine_entry::Type::MulticastGroupEntry(
+                    p4_v1::MulticastGroupEntry {
+                        multicast_group_id: 1,
+                        replicas: vec![replica(1, 0), replica(2, 1)],
+                        ..Default::default()
+                    },
+                ),
+            ),
+        };
+        assert_eq!(
+            p4info.display(&group).to_string(),
+            "multicast group 1: [1, 2#1]"
+        );
+        let session = p4_v1::PacketReplicationEngineEntry {
+            r#type: Some(
+                p4_v1::packet_replication_engine_entry::Type::CloneSessionEntry(
+                    p4_v1::CloneSessionEntry {
+                        session_id: 5,
+                        replicas: vec![replica(3, 0)],
+                        packet_length_bytes: 128,
+                        ..Default::default()
+                    },
+                ),
+            ),
+        };
+        assert_eq!(
+            p4info.display(&session).to_string(),
+            "clone session 5: [3] truncate=128"
+        );
+
+        let bitstring = |value: Vec<u8>| p4_v1::P4Data {
+            data: Some(p4_v1::p4_data::Data::Bitstring(value)),
+        };
+        let digest = p4_v1::DigestList {
+            digest_id: 4,
+            list_id: 7,
+            data: vec![p4_v1::P4Data {
+                data: Some(p4_v1::p4_data::Data::Struct(p4_v1::P4StructLike {
+                    members: vec![bitstring(vec![8, 0, 0, 0, 1, 2]), bitstring(vec![3])],
+                })),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            p4info
+                .display_with(&digest, &DisplayHints::guess())
+                .to_string(),
+            "learn_t list 7: [{srcAddr=08:00:00:00:01:02, port=3}]"
+        );
+
+        let packet_in = p4_v1::PacketIn {
+            payload: vec![0; 4],
+            metadata: vec![p4_v1::PacketMetadata {
+                metadata_id: 1,
+                value: vec![5],
+            }],
+        };
+        assert_eq!(
+            p4info.display(&packet_in).to_string(),
+            "packet_in(ingress_port=5) 4 bytes"
+        );
+        let packet_out = p4_v1::PacketOut {
+            payload: vec![],
+            metadata: vec![p4_v1::PacketMetadata {
+                metadata_id: 1,
+                value: vec![5],
+            }],
+        };
+        assert_eq!(
+            p4info.display(&packet_out).to_string(),
+            "packet_out(metadata#1=5) 0 bytes"
+        );
+    }
+}
@@ -0,0 +1,1198 @@
+//! Name-based representation of entities
+//!
+//! [`NamedEntity`] is a serde-friendly form of the installed entities, with
+//! tables, match fields, actions and parameters referred to by name instead
+//! of id, suited to golden files kept in version control:
+//!
+//! ```yaml
+//! - kind: table_entry
+//!   table: MyIngress.ipv4_lpm
+//!   match:
+//!     hdr.ipv4.dstAddr: 10.0.1.2/32
+//!   action:
+//!     name: MyIngress.ipv4_forward
+//!     params:
+//!       dstAddr: 08:00:00:00:01:02
+//!       port: 1
+//! ```
+//!
+//! Values are integers or strings. Strings may be decimal or `0x` prefixed
+//! hexadecimal integers, IPv4, IPv6 or MAC addresses. Match values take the
+//! form of their match kind: `value/prefix_len` for LPM, `value&&&mask` for
+//! ternary and `low..high` for range. A missing prefix length or mask matches
+//! all bits.
+//!
+//! Table entries, action profile members and groups, PRE entries and value
+//! set entries are supported, i.e. the entities mirrored by
+//! [`ShadowCache`](crate::cache::ShadowCache).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use p4runtime_client::{client::Client, p4info::named};
+//! # async fn example(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+//! // Dump a switch to a file
+//! named::write_file("golden.yaml", &client.dump_entities().await?)?;
+//!
+//! // Load a file onto a switch
+//! client.load_entities(&named::read_file("golden.yaml")?).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, net::IpAddr, path::Path};
+
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+use p4runtime::p4::v1 as p4_v1;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    display::{value_to_string, DisplayHints, ValueFormat},
+    table, P4Info,
+};
+use crate::{
+    error::{EntitiesFileError, NamedEntityError},
+    utils::{bit_len, canonicalize_unsigned_bitstring, prefix_mask},
+};
+
+/// A value in the name-based representation
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    /// Unsigned integer
+    Int(u64),
+    /// Integer, address or match value, see the [module](self) documentation
+    Text(String),
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Self {
+        Value::Int(n)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
+impl Value {
+    fn text(&self) -> String {
+        match self {
+            Value::Int(n) => n.to_string(),
+            Value::Text(s) => s.clone(),
+        }
+    }
+}
+
+/// An entity referring to P4 objects by name
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NamedEntity {
+    /// Table entry
+    TableEntry(NamedTableEntry),
+    /// Action profile member
+    ActionProfileMember(NamedActionProfileMember),
+    /// Action profile group
+    ActionProfileGroup(NamedActionProfileGroup),
+    /// Multicast group of the packet replication engine
+    MulticastGroup(NamedMulticastGroup),
+    /// Clone session of the packet replication engine
+    CloneSession(NamedCloneSession),
+    /// Value set entry
+    ValueSetEntry(NamedValueSetEntry),
+}
+
+/// Table entry
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedTableEntry {
+    /// Table name
+    pub table: String,
+    /// Match values by field name
+    #[serde(default, rename = "match", skip_serializing_if = "BTreeMap::is_empty")]
+    pub r#match: BTreeMap<String, Value>,
+    /// Priority, for tables with ternary, range or optional matches
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+    /// Whether this is the default entry of the table
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_default_action: bool,
+    /// Direct action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<NamedAction>,
+    /// Action profile member id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member: Option<u32>,
+    /// Action profile group id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<u32>,
+    /// One-shot action selector programming
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub action_set: Vec<NamedWeightedAction>,
+    /// Idle timeout in nanoseconds
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub idle_timeout_ns: i64,
+    /// Opaque metadata, as a `0x` prefixed hexadecimal string
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "hex_bytes")]
+    pub metadata: Vec<u8>,
+    /// Controller metadata, deprecated in favor of `metadata`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub controller_metadata: u64,
+    /// Whether the entry is const, i.e. part of the P4 program
+    ///
+    /// Const entries can't be written, they are skipped by
+    /// [`Client::load_entities`](crate::client::Client::load_entities).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_const: bool,
+}
+
+/// Action call
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedAction {
+    /// Action name
+    pub name: String,
+    /// Parameter values by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, Value>,
+}
+
+/// Weighted action of a one-shot action selector programming
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedWeightedAction {
+    /// Action call
+    pub action: NamedAction,
+    /// Weight
+    #[serde(default = "one")]
+    pub weight: i32,
+}
+
+/// Action profile member
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedActionProfileMember {
+    /// Action profile name
+    pub action_profile: String,
+    /// Member id
+    pub member_id: u32,
+    /// Action call
+    pub action: NamedAction,
+}
+
+/// Action profile group
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedActionProfileGroup {
+    /// Action profile name
+    pub action_profile: String,
+    /// Group id
+    pub group_id: u32,
+    /// Members
+    #[serde(default)]
+    pub members: Vec<NamedGroupMember>,
+    /// Maximum number of members, `0` for the action profile default
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub max_size: i32,
+}
+
+/// Member of an action profile group
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedGroupMember {
+    /// Member id
+    pub member_id: u32,
+    /// Weight
+    #[serde(default = "one")]
+    pub weight: i32,
+}
+
+/// Multicast group
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedMulticastGroup {
+    /// Multicast group id
+    pub id: u32,
+    /// Replicas
+    #[serde(default)]
+    pub replicas: Vec<NamedReplica>,
+}
+
+/// Clone session
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedCloneSession {
+    /// Session id
+    pub id: u32,
+    /// Replicas
+    #[serde(default)]
+    pub replicas: Vec<NamedReplica>,
+    /// Class of service of the clones
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub class_of_service: u32,
+    /// Truncation length of the clones, `0` for no truncation
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub packet_length_bytes: i32,
+}
+
+/// Replica of a multicast group or clone session
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedReplica {
+    /// Egress port
+    pub port: Value,
+    /// Instance
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub instance: u32,
+}
+
+/// Value set entry
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedValueSetEntry {
+    /// Value set name
+    pub value_set: String,
+    /// Members, as match values by field name
+    #[serde(default)]
+    pub members: Vec<BTreeMap<String, Value>>,
+}
+
+fn is_zero<T: Default + PartialEq>(n: &T) -> bool {
+    *n == T::default()
+}
+
+fn one() -> i32 {
+    1
+}
+
+/// Bytes as a `0x` prefixed hexadecimal string, keeping leading zeros
+mod hex_bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let hex = bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        serializer.serialize_str(&format!("0x{}", hex))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let hex = text
+            .strip_prefix("0x")
+            .ok_or_else(|| D::Error::custom("expected a 0x prefixed hexadecimal string"))?;
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(D::Error::custom("invalid hexadecimal digit"));
+        }
+        if hex.len() % 2 != 0 {
+            return Err(D::Error::custom("odd number of hexadecimal digits"));
+        }
+
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+/// Format of a file of named entities
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntitiesFormat {
+    /// JSON
+    Json,
+    /// YAML
+    Yaml,
+}
+
+impl EntitiesFormat {
+    /// Guess the format from the file extension, defaulting to YAML
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("json") => EntitiesFormat::Json,
+            _ => EntitiesFormat::Yaml,
+        }
+    }
+}
+
+/// Read named entities from a JSON or YAML file
+pub fn read_file(path: impl AsRef<Path>) -> Result<Vec<NamedEntity>, EntitiesFileError> {
+    let content = std::fs::read(path.as_ref())?;
+
+    Ok(match EntitiesFormat::from_path(path) {
+        EntitiesFormat::Json => serde_json::from_slice(&content)?,
+        EntitiesFormat::Yaml => serde_norway::from_slice(&content)?,
+    })
+}
+
+/// Write named entities to a JSON or YAML file
+pub fn write_file(
+    path: impl AsRef<Path>,
+    entities: &[NamedEntity],
+) -> Result<(), EntitiesFileError> {
+    let content = match EntitiesFormat::from_path(path.as_ref()) {
+        EntitiesFormat::Json => serde_json::to_string_pretty(entities)?,
+        EntitiesFormat::Yaml => serde_norway::to_string(entities)?,
+    };
+
+    Ok(std::fs::write(path, content)?)
+}
+
+/// Parse a value into a canonical bitstring
+fn parse_value(name: &str, text: &str) -> Result<Vec<u8>, NamedEntityError> {
+    let invalid = |reason: &str| NamedEntityError::InvalidValue {
+        name: name.to_string(),
+        value: text.to_string(),
+        reason: reason.to_string(),
+    };
+    let s = text.trim();
+
+    let bytes = if let Ok(ip) = s.parse::<IpAddr>() {
+        match ip {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        }
+    } else if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid("invalid hexadecimal"));
+        }
+        let hex = if hex.len() % 2 == 1 {
+            format!("0{}", hex)
+        } else {
+            hex.to_string()
+        };
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid("invalid hexadecimal"))?
+    } else if s.contains(':') {
+        let bytes = s
+            .split(':')
+            .map(|b| match b.len() {
+                1 | 2 => u8::from_str_radix(b, 16).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>();
+        match bytes {
+            Some(bytes) if bytes.len() == 6 => bytes,
+            _ => return Err(invalid("invalid MAC or IPv6 address")),
+        }
+    } else {
+        s.parse::<u128>()
+            .map_err(|_| invalid("not an integer or address"))?
+            .to_be_bytes()
+            .to_vec()
+    };
+
+    Ok(canonicalize_unsigned_bitstring(bytes))
+}
+
+fn preamble_name(preamble: &Option<p4_cfg_v1::Preamble>) -> String {
+    preamble
+        .as_ref()
+        .map(|p| p.name.clone())
+        .unwrap_or_default()
+}
+
+fn find_by_id<'a, T>(
+    objects: &'a [T],
+    preamble: impl Fn(&T) -> &Option<p4_cfg_v1::Preamble>,
+    kind: &str,
+    id: u32,
+) -> Result<&'a T, NamedEntityError> {
+    objects
+        .iter()
+        .find(|o| preamble(o).as_ref().is_some_and(|p| p.id == id))
+        .ok_or_else(|| NamedEntityError::UnknownId {
+            kind: kind.to_string(),
+            id,
+        })
+}
+
+impl P4Info {
+    /// Internal P4Info object, or an error if it is not loaded
+    fn loaded(&self) -> Result<&p4_cfg_v1::P4Info, NamedEntityError> {
+        self.p4info
+            .as_ref()
+            .ok_or(NamedEntityError::P4InfoNotLoaded)
+    }
+}
+
+fn unknown_name(kind: &str, name: &str) -> NamedEntityError {
+    NamedEntityError::UnknownName {
+        kind: kind.to_string(),
+        name: name.to_string(),
+    }
+}
+
+impl P4Info {
+    /// Convert entities to their name-based form
+    ///
+    /// Addresses are recognized as with [`DisplayHints::guess`].
+    pub fn to_named(
+        &self,
+        entities: &[p4_v1::Entity],
+    ) -> Result<Vec<NamedEntity>, NamedEntityError> {
+        self.to_named_with(entities, &DisplayHints::guess())
+    }
+
+    /// Convert entities to their name-based form, rendering values with hints
+    pub fn to_named_with(
+        &self,
+        entities: &[p4_v1::Entity],
+        hints: &DisplayHints,
+    ) -> Result<Vec<NamedEntity>, NamedEntityError> {
+        entities
+            .iter()
+            .map(|entity| self.entity_to_named(entity, hints))
+            .collect()
+    }
+
+    /// Convert entities from their name-based form
+    ///
+    /// Match values are checked like
+    /// [`Table::validate_entry`](table::Table::validate_entry).
+    pub fn from_named(
+        &self,
+        entities: &[NamedEntity],
+    ) -> Result<Vec<p4_v1::Entity>, NamedEntityError> {
+        self.loaded()?;
+        entities
+            .iter()
+            .map(|entity| {
+                Ok(p4_v1::Entity {
+                    entity: Some(self.entity_from_named(entity)?),
+                })
+            })
+            .collect()
+    }
+
+    fn entity_to_named(
+        &self,
+        entity: &p4_v1::Entity,
+        hints: &DisplayHints,
+    ) -> Result<NamedEntity, NamedEntityError> {
+        use p4_v1::entity::Entity;
+        use p4_v1::packet_replication_engine_entry::Type;
+
+        let unsupported = |kind: &str| NamedEntityError::Unsupported {
+            kind: kind.to_string(),
+        };
+
+        Ok(match &entity.entity {
+            Some(Entity::TableEntry(e)) => {
+                NamedEntity::TableEntry(self.table_entry_to_named(e, hints)?)
+            }
+            Some(Entity::ActionProfileMember(m)) => {
+                NamedEntity::ActionProfileMember(NamedActionProfileMember {
+                    action_profile: self.action_profile_name(m.action_profile_id)?,
+                    member_id: m.member_id,
+                    action: match &m.action {
+                        Some(action) => self.action_to_named(action, hints)?,
+                        None => Default::default(),
+                    },
+                })
+            }
+            Some(Entity::ActionProfileGroup(g)) => {
+                NamedEntity::ActionProfileGroup(NamedActionProfileGroup {
+                    action_profile: self.action_profile_name(g.action_profile_id)?,
+                    group_id: g.group_id,
+                    members: g
+                        .members
+                        .iter()
+                        .map(|m| NamedGroupMember {
+                            member_id: m.member_id,
+                            weight: m.weight,
+                        })
+                        .collect(),
+                    max_size: g.max_size,
+                })
+            }
+            Some(Entity::PacketReplicationEngineEntry(pre)) => match &pre.r#type {
+                Some(Type::MulticastGroupEntry(group)) => {
+                    NamedEntity::MulticastGroup(NamedMulticastGroup {
+                        id: group.multicast_group_id,
+                        replicas: group.replicas.iter().map(replica_to_named).collect(),
+                    })
+                }
+                Some(Type::CloneSessionEntry(session)) => {
+                    NamedEntity::CloneSession(NamedCloneSession {
+                        id: session.session_id,
+                        replicas: session.replicas.iter().map(replica_to_named).collect(),
+                        class_of_service: session.class_of_service,
+                        packet_length_bytes: session.packet_length_bytes,
+                    })
+                }
+                None => return Err(unsupported("empty packet replication engine")),
+            },
+            Some(Entity::ValueSetEntry(e)) => {
+                let value_set = find_by_id(
+                    &self.loaded()?.value_sets,
+                    |v| &v.preamble,
+                    "value set",
+                    e.value_set_id,
+                )?;
+                NamedEntity::ValueSetEntry(NamedValueSetEntry {
+                    value_set: preamble_name(&value_set.preamble),
+                    members: e
+                        .members
+                        .iter()
+                        .map(|m| match_to_named(&value_set.r#match, &m.r#match, hints))
+                        .collect::<Result<_, _>>()?,
+                })
+            }
+            Some(Entity::ExternEntry(_)) => return Err(unsupported("extern")),
+            Some(Entity::MeterEntry(_)) => return Err(unsupported("meter")),
+            Some(Entity::DirectMeterEntry(_)) => return Err(unsupported("direct meter")),
+            Some(Entity::CounterEntry(_)) => return Err(unsupported("counter")),
+            Some(Entity::DirectCounterEntry(_)) => return Err(unsupported("direct counter")),
+            Some(Entity::RegisterEntry(_)) => return Err(unsupported("register")),
+            Some(Entity::DigestEntry(_)) => return Err(unsupported("digest")),
+            None => return Err(unsupported("empty")),
+        })
+    }
+
+    fn table_entry_to_named(
+        &self,
+        entry: &p4_v1::TableEntry,
+        hints: &DisplayHints,
+    ) -> Result<NamedTableEntry, NamedEntityError> {
+        use p4_v1::table_action::Type;
+
+        let table = find_by_id(
+            &self.loaded()?.tables,
+            |t| &t.preamble,
+            "table",
+            entry.table_id,
+        )?;
+        #[allow(deprecated)]
+        let mut named = NamedTableEntry {
+            table: preamble_name(&table.preamble),
+            r#match: match_to_named(&table.match_fields, &entry.r#match, hints)?,
+            priority: entry.priority,
+            is_default_action: entry.is_default_action,
+            idle_timeout_ns: entry.idle_timeout_ns,
+            metadata: entry.metadata.clone(),
+            controller_metadata: entry.controller_metadata,
+            is_const: entry.is_const,
+            ..Default::default()
+        };
+
+        match entry.action.as_ref().and_then(|a| a.r#type.as_ref()) {
+            Some(Type::Action(action)) => {
+                named.action = Some(self.action_to_named(action, hints)?);
+            }
+            Some(Type::ActionProfileMemberId(id)) => named.member = Some(*id),
+            Some(Type::ActionProfileGroupId(id)) => named.group = Some(*id),
+            Some(Type::ActionProfileActionSet(set)) => {
+                named.action_set = set
+                    .action_profile_actions
+                    .iter()
+                    .map(|a| {
+                        Ok(NamedWeightedAction {
+                            action: match &a.action {
+                                Some(action) => self.action_to_named(action, hints)?,
+                                None => Default::default(),
+                            },
+                            weight: a.weight,
+                        })
+                    })
+                    .collect::<Result<_, NamedEntityError>>()?;
+            }
+            None => {}
+        }
+
+        Ok(named)
+    }
+
+    fn action_to_named(
+        &self,
+        action: &p4_v1::Action,
+        hints: &DisplayHints,
+    ) -> Result<NamedAction, NamedEntityError> {
+        let info = find_by_id(
+            &self.loaded()?.actions,
+            |a| &a.preamble,
+            "action",
+            action.action_id,
+        )?;
+        let name = preamble_name(&info.preamble);
+
+        let params = action
+            .params
+            .iter()
+            .map(|param| {
+                let p = info
+                    .params
+                    .iter()
+                    .find(|p| p.id == param.param_id)
+                    .ok_or_else(|| NamedEntityError::UnknownId {
+                        kind: format!("parameter of {}", name),
+                        id: param.param_id,
+                    })?;
+                let value = value_to_named(
+                    hints,
+                    &p.name,
+                    p.type_name.as_ref(),
+                    p.bitwidth,
+                    &param.value,
+                );
+                Ok((p.name.clone(), value))
+            })
+            .collect::<Result<_, NamedEntityError>>()?;
+
+        Ok(NamedAction { name, params })
+    }
+
+    fn action_profile_name(&self, id: u32) -> Result<String, NamedEntityError> {
+        find_by_id(
+            &self.loaded()?.action_profiles,
+            |a| &a.preamble,
+            "action profile",
+            id,
+        )
+        .map(|a| preamble_name(&a.preamble))
+    }
+
+    fn entity_from_named(
+        &self,
+        entity: &NamedEntity,
+    ) -> Result<p4_v1::entity::Entity, NamedEntityError> {
+        use p4_v1::entity::Entity;
+        use p4_v1::packet_replication_engine_entry::Type;
+
+        let pre = |r#type| {
+            Entity::PacketReplicationEngineEntry(p4_v1::PacketReplicationEngineEntry {
+                r#type: Some(r#type),
+            })
+        };
+
+        Ok(match entity {
+            NamedEntity::TableEntry(e) => Entity::TableEntry(self.table_entry_from_named(e)?),
+            NamedEntity::ActionProfileMember(m) => {
+                Entity::ActionProfileMember(p4_v1::ActionProfileMember {
+                    action_profile_id: self.action_profile_id_of(&m.action_profile)?,
+                    member_id: m.member_id,
+                    action: Some(self.action_from_named(&m.action)?),
+                })
+            }
+            NamedEntity::ActionProfileGroup(g) => {
+                Entity::ActionProfileGroup(p4_v1::ActionProfileGroup {
+                    action_profile_id: self.action_profile_id_of(&g.action_profile)?,
+                    group_id: g.group_id,
+                    members: g
+                        .members
+                        .iter()
+                        .map(|m| p4_v1::action_profile_group::Member {
+                            member_id: m.member_id,
+                            weight: m.weight,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    max_size: g.max_size,
+                })
+            }
+            NamedEntity::MulticastGroup(group) => {
+                pre(Type::MulticastGroupEntry(p4_v1::MulticastGroupEntry {
+                    multicast_group_id: group.id,
+                    replicas: replicas_from_named(&group.replicas)?,
+                    ..Default::default()
+                }))
+            }
+            NamedEntity::CloneSession(session) => {
+                pre(Type::CloneSessionEntry(p4_v1::CloneSessionEntry {
+                    session_id: session.id,
+                    replicas: replicas_from_named(&session.replicas)?,
+                    class_of_service: session.class_of_service,
+                    packet_length_bytes: session.packet_length_bytes,
+                }))
+            }
+            NamedEntity::ValueSetEntry(e) => {
+                let value_set = self
+                    .loaded()?
+                    .value_sets
+                    .iter()
+                    .find(|v| {
+                        v.preamble
+                            .as_ref()
+                            .is_some_and(|p| p.name == e.value_set || p.alias == e.value_set)
+                    })
+                    .ok_or_else(|| unknown_name("value set", &e.value_set))?;
+
+                // Value sets match like tables without actions
+                let info = p4_cfg_v1::Table {
+                    match_fields: value_set.r#match.clone(),
+                    ..Default::default()
+                };
+                let actions = Default::default();
+                let table = table::Table::new(&info, &actions);
+
+                let members = e
+                    .members
+                    .iter()
+                    .map(|m| {
+                        let r#match = match_from_named(&e.value_set, &value_set.r#match, m)?;
+                        r#match
+                            .iter()
+                            .try_for_each(|fm| table.validate_field_match(fm))?;
+                        Ok(p4_v1::ValueSetMember { r#match })
+                    })
+                    .collect::<Result<_, NamedEntityError>>()?;
+
+                Entity::ValueSetEntry(p4_v1::ValueSetEntry {
+                    value_set_id: value_set.preamble.as_ref().map(|p| p.id).unwrap_or(0),
+                    members,
+                })
+            }
+        })
+    }
+
+    fn table_entry_from_named(
+        &self,
+        named: &NamedTableEntry,
+    ) -> Result<p4_v1::TableEntry, NamedEntityError> {
+        use p4_v1::table_action::Type;
+
+        let table = self
+            .get_table(&named.table)
+            .ok_or_else(|| unknown_name("table", &named.table))?;
+
+        let kinds = [
+            named.action.is_some(),
+            named.member.is_some(),
+            named.group.is_some(),
+            !named.action_set.is_empty(),
+        ];
+        if kinds.iter().filter(|&&k| k).count() > 1 {
+            return Err(NamedEntityError::AmbiguousAction {
+                table: named.table.clone(),
+            });
+        }
+
+        let action = if let Some(action) = &named.action {
+            Some(Type::Action(self.action_from_named(action)?))
+        } else if let Some(id) = named.member {
+            Some(Type::ActionProfileMemberId(id))
+        } else if let Some(id) = named.group {
+            Some(Type::ActionProfileGroupId(id))
+        } else if !named.action_set.is_empty() {
+            let action_profile_actions = named
+                .action_set
+                .iter()
+                .map(|a| {
+                    Ok(p4_v1::ActionProfileAction {
+                        action: Some(self.action_from_named(&a.action)?),
+                        weight: a.weight,
+                        ..Default::default()
+                    })
+                })
+                .collect::<Result<_, NamedEntityError>>()?;
+            Some(Type::ActionProfileActionSet(
+                p4_v1::ActionProfileActionSet {
+                    action_profile_actions,
+                },
+            ))
+        } else {
+            None
+        };
+
+        #[allow(deprecated)]
+        let entry = p4_v1::TableEntry {
+            table_id: table.id(),
+            r#match: match_from_named(&named.table, &table.match_fields, &named.r#match)?,
+            action: action.map(|r#type| p4_v1::TableAction {
+                r#type: Some(r#type),
+            }),
+            priority: named.priority,
+            is_default_action: named.is_default_action,
+            idle_timeout_ns: named.idle_timeout_ns,
+            metadata: named.metadata.clone(),
+            controller_metadata: named.controller_metadata,
+            is_const: named.is_const,
+
+            ..Default::default()
+        };
+        table.validate_entry(&entry)?;
+
+        Ok(entry)
+    }
+
+    fn action_from_named(&self, named: &NamedAction) -> Result<p4_v1::Action, NamedEntityError> {
+        let action = self
+            .action_map
+            .get(&self.action_id(&named.name))
+            .ok_or_else(|| unknown_name("action", &named.name))?;
+
+        if let Some(name) = named
+            .params
+            .keys()
+            .find(|name| !action.params.iter().any(|p| &p.name == *name))
+        {
+            return Err(unknown_name(
+                "parameter",
+                &format!("{}.{}", named.name, name),
+            ));
+        }
+
+        let params =
+            action
+                .params
+                .iter()
+                .map(|p| {
+                    let value = named.params.get(&p.name).ok_or_else(|| {
+                        NamedEntityError::MissingParam {
+                            action: named.name.clone(),
+                            param: p.name.clone(),
+                        }
+                    })?;
+                    let text = value.text();
+                    let bytes = parse_value(&p.name, &text)?;
+                    if bit_len(&bytes) > p.bitwidth as usize {
+                        return Err(NamedEntityError::InvalidValue {
+                            name: p.name.clone(),
+                            value: text,
+                            reason: format!("exceeds {} bits", p.bitwidth),
+                        });
+                    }
+
+                    Ok(p4_v1::action::Param {
+                        param_id: p.id,
+                        value: bytes,
+                    })
+                })
+                .collect::<Result<_, NamedEntityError>>()?;
+
+        Ok(p4_v1::Action {
+            action_id: action.preamble.as_ref().map(|p| p.id).unwrap_or(0),
+            params,
+        })
+    }
+
+    fn action_profile_id_of(&self, name: &str) -> Result<u32, NamedEntityError> {
+        match self.action_profile_id(name) {
+            0 => Err(unknown_name("action profile", name)),
+            id => Ok(id),
+        }
+    }
+}
+
+fn value_to_named(
+    hints: &DisplayHints,
+    name: &str,
+    type_name: Option<&p4_cfg_v1::P4NamedType>,
+    bitwidth: i32,
+    value: &[u8],
+) -> Value {
+    let format = hints.format(name, type_name.map(|t| t.name.as_str()), bitwidth);
+    if format == ValueFormat::Decimal && bit_len(value) <= 64 {
+        let n = value.iter().fold(0u64, |n, &b| (n << 8) | u64::from(b));
+        return Value::Int(n);
+    }
+
+    Value::Text(value_to_string(format, value))
+}
+
+fn match_to_named(
+    fields: &[p4_cfg_v1::MatchField],
+    matches: &[p4_v1::FieldMatch],
+    hints: &DisplayHints,
+) -> Result<BTreeMap<String, Value>, NamedEntityError> {
+    use p4_v1::field_match::FieldMatchType;
+
+    matches
+        .iter()
+        .map(|fm| {
+            let field = fields.iter().find(|f| f.id == fm.field_id).ok_or_else(|| {
+                NamedEntityError::UnknownId {
+                    kind: "match field".to_string(),
+                    id: fm.field_id,
+                }
+            })?;
+            let format = hints.format(
+                &field.name,
+                field.type_name.as_ref().map(|t| t.name.as_str()),
+                field.bitwidth,
+            );
+            let text = |value: &[u8]| value_to_string(format, value);
+
+            let value = match &fm.field_match_type {
+                Some(FieldMatchType::Exact(m)) => value_to_named(
+                    hints,
+                    &field.name,
+                    field.type_name.as_ref(),
+                    field.bitwidth,
+                    &m.value,
+                ),
+                Some(FieldMatchType::Optional(m)) => value_to_named(
+                    hints,
+                    &field.name,
+                    field.type_name.as_ref(),
+                    field.bitwidth,
+                    &m.value,
+                ),
+                Some(FieldMatchType::Lpm(m)) => {
+                    Value::Text(format!("{}/{}", text(&m.value), m.prefix_len))
+                }
+                Some(FieldMatchType::Ternary(m)) => {
+                    Value::Text(format!("{}&&&{}", text(&m.value), text(&m.mask)))
+                }
+                Some(FieldMatchType::Range(m)) => {
+                    Value::Text(format!("{}..{}", text(&m.low), text(&m.high)))
+                }
+                Some(FieldMatchType::Other(_)) | None => {
+                    return Err(NamedEntityError::Unsupported {
+                        kind: format!("{} match", field.name),
+                    })
+                }
+            };
+
+            Ok((field.name.clone(), value))
+        })
+        .collect()
+}
+
+fn match_from_named(
+    owner: &str,
+    fields: &[p4_cfg_v1::MatchField],
+    named: &BTreeMap<String, Value>,
+) -> Result<Vec<p4_v1::FieldMatch>, NamedEntityError> {
+    use p4_cfg_v1::match_field::{Match, MatchType};
+    use p4_v1::field_match::{Exact, FieldMatchType, Lpm, Optional, Range, Ternary};
+
+    let mut matches = named
+        .iter()
+        .map(|(name, value)| {
+            let field = fields
+                .iter()
+                .find(|f| &f.name == name)
+                .ok_or_else(|| unknown_name("match field", &format!("{}.{}", owner, name)))?;
+            let text = value.text();
+            let parse = |s: &str| parse_value(name, s);
+            let invalid = |reason: &str| NamedEntityError::InvalidValue {
+                name: name.clone(),
+                value: text.clone(),
+                reason: reason.to_string(),
+            };
+            let full_mask = || prefix_mask(field.bitwidth as usize, field.bitwidth as usize);
+
+            let match_type = match field.r#match {
+                Some(Match::MatchType(mt)) => MatchType::try_from(mt).ok(),
+                _ => None,
+            };
+            let field_match_type = match match_type {
+                Some(MatchType::Exact) => FieldMatchType::Exact(Exact {
+                    value: parse(&text)?,
+                }),
+                Some(MatchType::Optional) => FieldMatchType::Optional(Optional {
+                    value: parse(&text)?,
+                }),
+                Some(MatchType::Lpm) => {
+                    let (value, prefix_len) = match text.split_once('/') {
+                        Some((value, len)) => (
+                            value,
+                            len.trim()
+                                .parse()
+                                .map_err(|_| invalid("invalid prefix length"))?,
+                        ),
+                        None => (text.as_str(), field.bitwidth),
+                    };
+                    FieldMatchType::Lpm(Lpm {
+                        value: parse(value)?,
+                        prefix_len,
+                    })
+                }
+                Some(MatchType::Ternary) => {
+                    let (value, mask) = match text.split_once("&&&") {
+                        Some((value, mask)) => (parse(value)?, parse(mask)?),
+                        None => (parse(&text)?, full_mask()),
+                    };
+                    FieldMatchType::Ternary(Ternary { value, mask })
+                }
+                Some(MatchType::Range) => {
+                    let (low, high) = match text.split_once("..") {
+                        Some((low, high)) => (parse(low)?, parse(high)?),
+                        None => (parse(&text)?, parse(&text)?),
+                    };
+                    FieldMatchType::Range(Range { low, high })
+                }
+                _ => return Err(invalid("unsupported match kind")),
+            };
+
+            Ok(p4_v1::FieldMatch {
+                field_id: field.id,
+                field_match_type: Some(field_match_type),
+            })
+        })
+        .collect::<Result<Vec<_>, NamedEntityError>>()?;
+    matches.sort_by_key(|fm| fm.field_id);
+
+    Ok(matches)
+}
+
+fn replica_to_named(replica: &p4_v1::Replica) -> NamedReplica {
+    let port = match &replica.port_kind {
+        Some(p4_v1::replica::PortKind::Port(port)) => {
+            value_to_named(&DisplayHints::default(), "port", None, 0, port)
+        }
+        #[allow(deprecated)]
+        None => Value::Int(replica.egress_port.into()),
+    };
+
+    NamedReplica {
+        port,
+        instance: replica.instance,
+    }
+}
+
+fn replicas_from_named(replicas: &[NamedReplica]) -> Result<Vec<p4_v1::Replica>, NamedEntityError> {
+    replicas
+        .iter()
+        .map(|r| {
+            Ok(p4_v1::Replica {
+                port_kind: Some(p4_v1::replica::PortKind::Port(parse_value(
+                    "port",
+                    &r.port.text(),
+                )?)),
+                instance: r.instance,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use p4_cfg_v1::match_field::MatchType;
+
+    use super::*;
+    use crate::test_utils::{match_field, param, preamble};
+
+    #[test]
+    fn round_trip() {
+        let p4info = P4Info::new(p4_cfg_v1::P4Info {
+            tables: vec![p4_cfg_v1::Table {
+                preamble: preamble(1, "MyIngress.acl", ""),
+                match_fields: vec![
+                    match_field(1, "hdr.ipv4.dstAddr", 32, MatchType::Lpm),
+                    match_field(2, "hdr.tcp.dstPort", 16, MatchType::Range),
+                    match_field(3, "hdr.ethernet.srcAddr", 48, MatchType::Ternary),
+                ],
+                action_refs: vec![p4_cfg_v1::ActionRef {
+                    id: 2,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            actions: vec![p4_cfg_v1::Action {
+                preamble: preamble(2, "MyIngress.forward", ""),
+                params: vec![param(1, "dstAddr", 48), param(2, "port", 9)],
+            }],
+            ..Default::default()
+        });
+
+        let yaml = r#"
+- kind: table_entry
+  table: MyIngress.acl
+  match:
+    hdr.ipv4.dstAddr: 10.0.1.0/24
+    hdr.tcp.dstPort: 80..0x1f90
+    hdr.ethernet.srcAddr: 00:00:00:00:00:01&&&ff:ff:ff:ff:ff:ff
+  priority: 10
+  action:
+    name: MyIngress.forward
+    params:
+      dstAddr: 08:00:00:00:01:02
+      port: 1
+  metadata: "0x0001"
+- kind: multicast_group
+  id: 1
+  replicas:
+    - port: 1
+    - port: 2
+      instance: 1
+"#;
+        let named: Vec<NamedEntity> = serde_norway::from_str(yaml).unwrap();
+        let entities = p4info.from_named(&named).unwrap();
+
+        let Some(p4_v1::entity::Entity::TableEntry(entry)) = &entities[0].entity else {
+            panic!("not a table entry");
+        };
+        assert_eq!(
+            entry.r#match[0].field_match_type,
+            Some(p4_v1::field_match::FieldMatchType::Lpm(
+                p4_v1::field_match::Lpm {
+                    value: vec![10, 0, 1, 0],
+                    prefix_len: 24,
+                }
+            ))
+        );
+        assert_eq!(
+            entry.r#match[1].field_match_type,
+            Some(p4_v1::field_match::FieldMatchType::Range(
+                p4_v1::field_match::Range {
+                    low: vec![80],
+                    high: vec![0x1f, 0x90],
+                }
+            ))
+        );
+        assert_eq!(entry.metadata, vec![0, 1]);
+
+        // Ranges and port numbers are rendered in decimal
+        let mut expected = named.clone();
+        if let NamedEntity::TableEntry(e) = &mut expected[0] {
+            e.r#match
+                .insert("hdr.tcp.dstPort".to_string(), "80..8080".into());
+        }
+        assert_eq!(p4info.to_named(&entities).unwrap(), expected);
+
+        // Without P4Info, conversions fail instead of panicking
+        assert!(matches!(
+            P4Info::default().to_named(&entities),
+            Err(NamedEntityError::P4InfoNotLoaded)
+        ));
+        assert!(matches!(
+            P4Info::default().from_named(&named),
+            Err(NamedEntityError::P4InfoNotLoaded)
+        ));
+
+        // Bits beyond the prefix are rejected
+        let mut invalid = named.clone();
+        if let NamedEntity::TableEntry(e) = &mut invalid[0] {
+            e.r#match
+                .insert("hdr.ipv4.dstAddr".to_string(), "10.0.1.1/24".into());
+        }
+        assert!(matches!(
+            p4info.from_named(&invalid),
+            Err(NamedEntityError::BitsBeyondPrefix { .. })
+        ));
+
+        // Non-ASCII hexadecimal values are rejected
+        assert!(
+            serde_norway::from_str::<Vec<NamedEntity>>(&yaml.replace("0x0001", "0xaé1")).is_err()
+        );
+        let mut invalid = named.clone();
+        if let NamedEntity::TableEntry(e) = &mut invalid[0] {
+            e.r#match
+                .insert("hdr.tcp.dstPort".to_string(), "0xaé1".into());
+        }
+        assert!(matches!(
+            p4info.from_named(&invalid),
+            Err(NamedEntityError::InvalidValue { .. })
+        ));
+
+        // Parameters are checked against the action
+        let mut invalid = named;
+        if let NamedEntity::TableEntry(NamedTableEntry {
+            action: Some(action),
+            ..
+        }) = &mut invalid[0]
+        {
+            action.params.insert("port".to_string(), 512.into());
+        }
+        assert!(matches!(
+            p4info.from_named(&invalid),
+            Err(NamedEntityError::InvalidValue { .. })
+        ));
+    }
+}
@@ -6,7 +6,10 @@ use std::ops::Deref;
 use p4runtime::p4::config::v1 as p4_cfg_v1;
 use p4runtime::p4::v1 as p4_v1;
 
-use crate::error::{MakeFieldMatchError, MakeTableActionError};
+use crate::{
+    error::{MakeFieldMatchError, MakeTableActionError, MatchValueError},
+    utils::{and_bitstring, bit_len, cmp_bitstring, prefix_mask},
+};
 
 /// Field match value.
 ///
@@ -193,9 +196,195 @@ impl<'a> Table<'a> {
             }
         };
 
+        self.validate_field_match(&field_match)?;
+
         Ok(field_match)
     }
 
+    /// Check a field match value against the P4Runtime rules
+    ///
+    /// Values must fit the field bitwidth. LPM values must have no bits set
+    /// beyond the prefix, ternary values no bits set outside the mask, and
+    /// range lows must not exceed highs. Don't-care matches, i.e. zero prefix
+    /// lengths, zero masks and full ranges, must be omitted.
+    pub fn validate_field_match(
+        &self,
+        field_match: &p4_v1::FieldMatch,
+    ) -> Result<(), MatchValueError> {
+        self.check_field_match(field_match, false).map(|_| ())
+    }
+
+    /// Normalize a field match value
+    ///
+    /// Value bits beyond the prefix or outside the mask are cleared, and
+    /// don't-care matches are dropped, returning `None`. Other violations of
+    /// [`validate_field_match`](Table::validate_field_match) are errors.
+    pub fn normalize_field_match(
+        &self,
+        field_match: &p4_v1::FieldMatch,
+    ) -> Result<Option<p4_v1::FieldMatch>, MatchValueError> {
+        self.check_field_match(field_match, true)
+    }
+
+    /// Check the field match values of an entry
+    pub fn validate_entry(&self, entry: &p4_v1::TableEntry) -> Result<(), MatchValueError> {
+        entry
+            .r#match
+            .iter()
+            .try_for_each(|field_match| self.validate_field_match(field_match))
+    }
+
+    /// Normalize the field match values of an entry
+    pub fn normalize_entry(
+        &self,
+        mut entry: p4_v1::TableEntry,
+    ) -> Result<p4_v1::TableEntry, MatchValueError> {
+        entry.r#match = entry
+            .r#match
+            .iter()
+            .filter_map(|field_match| self.normalize_field_match(field_match).transpose())
+            .collect::<Result<_, _>>()?;
+
+        Ok(entry)
+    }
+
+    fn check_field_match(
+        &self,
+        field_match: &p4_v1::FieldMatch,
+        normalize: bool,
+    ) -> Result<Option<p4_v1::FieldMatch>, MatchValueError> {
+        use p4_v1::field_match::{FieldMatchType, Lpm, Ternary};
+
+        let field = self
+            .table
+            .match_fields
+            .iter()
+            .find(|f| f.id == field_match.field_id)
+            .ok_or(MatchValueError::UnknownFieldId {
+                field_id: field_match.field_id,
+            })?;
+        let field_name = || field.name.clone();
+        let bitwidth = (field.bitwidth > 0).then_some(field.bitwidth as usize);
+        let check_width = |value: &[u8]| match bitwidth {
+            Some(bitwidth) if bit_len(value) > bitwidth => Err(MatchValueError::ValueTooWide {
+                field_name: field_name(),
+                bitwidth: field.bitwidth,
+            }),
+            _ => Ok(()),
+        };
+
+        let field_match_type =
+            match field_match
+                .field_match_type
+                .as_ref()
+                .ok_or(MatchValueError::MissingValue {
+                    field_name: field_name(),
+                })? {
+                FieldMatchType::Exact(m) => {
+                    check_width(&m.value)?;
+                    FieldMatchType::Exact(m.clone())
+                }
+                FieldMatchType::Optional(m) => {
+                    check_width(&m.value)?;
+                    FieldMatchType::Optional(m.clone())
+                }
+                FieldMatchType::Lpm(m) => {
+                    check_width(&m.value)?;
+                    if m.prefix_len < 0 || bitwidth.is_some_and(|b| m.prefix_len as usize > b) {
+                        return Err(MatchValueError::PrefixTooLong {
+                            field_name: field_name(),
+                            prefix_len: m.prefix_len,
+                            bitwidth: field.bitwidth,
+                        });
+                    }
+                    if m.prefix_len == 0 {
+                        return match normalize {
+                            true => Ok(None),
+                            false => Err(MatchValueError::DontCare {
+                                field_name: field_name(),
+                            }),
+                        };
+                    }
+
+                    let mut value = m.value.clone();
+                    if let Some(bitwidth) = bitwidth {
+                        let masked =
+                            and_bitstring(&value, &prefix_mask(bitwidth, m.prefix_len as usize));
+                        if cmp_bitstring(&masked, &value).is_ne() {
+                            if !normalize {
+                                return Err(MatchValueError::BitsBeyondPrefix {
+                                    field_name: field_name(),
+                                    prefix_len: m.prefix_len,
+                                });
+                            }
+                            value = masked;
+                        }
+                    }
+
+                    FieldMatchType::Lpm(Lpm {
+                        value,
+                        prefix_len: m.prefix_len,
+                    })
+                }
+                FieldMatchType::Ternary(m) => {
+                    check_width(&m.value)?;
+                    check_width(&m.mask)?;
+                    if bit_len(&m.mask) == 0 {
+                        return match normalize {
+                            true => Ok(None),
+                            false => Err(MatchValueError::DontCare {
+                                field_name: field_name(),
+                            }),
+                        };
+                    }
+
+                    let mut value = m.value.clone();
+                    let masked = and_bitstring(&value, &m.mask);
+                    if cmp_bitstring(&masked, &value).is_ne() {
+                        if !normalize {
+                            return Err(MatchValueError::BitsOutsideMask {
+                                field_name: field_name(),
+                            });
+                        }
+                        value = masked;
+                    }
+
+                    FieldMatchType::Ternary(Ternary {
+                        value,
+                        mask: m.mask.clone(),
+                    })
+                }
+                FieldMatchType::Range(m) => {
+                    check_width(&m.low)?;
+                    check_width(&m.high)?;
+                    if cmp_bitstring(&m.low, &m.high).is_gt() {
+                        return Err(MatchValueError::InvertedRange {
+                            field_name: field_name(),
+                        });
+                    }
+                    if let Some(bitwidth) = bitwidth {
+                        let max = prefix_mask(bitwidth, bitwidth);
+                        if bit_len(&m.low) == 0 && cmp_bitstring(&m.high, &max).is_eq() {
+                            return match normalize {
+                                true => Ok(None),
+                                false => Err(MatchValueError::DontCare {
+                                    field_name: field_name(),
+                                }),
+                            };
+                        }
+                    }
+
+                    FieldMatchType::Range(m.clone())
+                }
+                FieldMatchType::Other(m) => FieldMatchType::Other(m.clone()),
+            };
+
+        Ok(Some(p4_v1::FieldMatch {
+            field_id: field_match.field_id,
+            field_match_type: Some(field_match_type),
+        }))
+    }
+
     /// Make a new table action
     pub fn make_action(
         &self,
@@ -246,3 +435,97 @@ impl<'a> Table<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p4_cfg_v1::match_field::MatchType;
+    use p4_v1::field_match::{FieldMatchType, Lpm, Range, Ternary};
+
+    use super::*;
+    use crate::test_utils::match_field;
+
+    fn field_match(field_id: u32, field_match_type: FieldMatchType) -> p4_v1::FieldMatch {
+        p4_v1::FieldMatch {
+            field_id,
+            field_match_type: Some(field_match_type),
+        }
+    }
+
+    #[test]
+    fn validate_and_normalize() {
+        let info_table = p4_cfg_v1::Table {
+            match_fields: vec![
+                match_field(1, "f1", 32, MatchType::Lpm),
+                match_field(2, "f2", 16, MatchType::Ternary),
+                match_field(3, "f3", 16, MatchType::Range),
+            ],
+            ..Default::default()
+        };
+        let actions = HashMap::new();
+        let table = Table::new(&info_table, &actions);
+
+        let lpm = field_match(
+            1,
+            FieldMatchType::Lpm(Lpm {
+                value: vec![10, 0, 1, 2],
+                prefix_len: 24,
+            }),
+        );
+        assert!(matches!(
+            table.validate_field_match(&lpm),
+            Err(MatchValueError::BitsBeyondPrefix { prefix_len: 24, .. })
+        ));
+        assert_eq!(
+            table.normalize_field_match(&lpm).unwrap(),
+            Some(field_match(
+                1,
+                FieldMatchType::Lpm(Lpm {
+                    value: vec![10, 0, 1, 0],
+                    prefix_len: 24,
+                })
+            ))
+        );
+
+        let ternary = field_match(
+            2,
+            FieldMatchType::Ternary(Ternary {
+                value: vec![0x12, 0x34],
+                mask: vec![0xff, 0x00],
+            }),
+        );
+        assert!(matches!(
+            table.validate_field_match(&ternary),
+            Err(MatchValueError::BitsOutsideMask { .. })
+        ));
+
+        let full_range = field_match(
+            3,
+            FieldMatchType::Range(Range {
+                low: vec![0],
+                high: vec![0xff, 0xff],
+            }),
+        );
+        assert!(matches!(
+            table.validate_field_match(&full_range),
+            Err(MatchValueError::DontCare { .. })
+        ));
+        let entry = table
+            .normalize_entry(p4_v1::TableEntry {
+                r#match: vec![ternary, full_range],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(entry.r#match.len(), 1);
+
+        assert!(matches!(
+            table.validate_field_match(&field_match(
+                3,
+                FieldMatchType::Range(Range {
+                    low: vec![2],
+                    high: vec![1],
+                })
+            )),
+            Err(MatchValueError::InvertedRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,819 @@
+//! Protobuf text format for P4Info
+//!
+//! `p4c --p4runtime-files` emits P4Info in protobuf text format (`.txtpb`).
+//! Text format is converted from and to the protobuf JSON mapping, which the
+//! generated P4Info types support through serde. Text format does not say
+//! whether a field is repeated, a map or holds bytes, so the few such fields
+//! of P4Info are listed here.
+//!
+//! Expanded `google.protobuf.Any` messages, e.g. the `info` of extern
+//! instances written by bf-p4c, are encoded when their type is a P4Info
+//! message. Other types can't be encoded without their schema and are
+//! rejected, rather than loading a P4Info which lost them: load the binary or
+//! JSON P4Info of such programs instead.
+
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+use prost::Message;
+use serde_json::{Map, Value};
+
+/// Repeated fields of P4Info and the messages it contains
+const REPEATED_FIELDS: &[&str] = &[
+    "tables",
+    "actions",
+    "action_profiles",
+    "counters",
+    "direct_counters",
+    "meters",
+    "direct_meters",
+    "controller_packet_metadata",
+    "value_sets",
+    "registers",
+    "digests",
+    "externs",
+    "instances",
+    "annotations",
+    "annotation_locations",
+    "structured_annotations",
+    "match_fields",
+    "action_refs",
+    "direct_resource_ids",
+    "params",
+    "table_ids",
+    "metadata",
+    "match",
+    "members",
+    "kv_pairs",
+    "expressions",
+];
+
+/// Map fields of `P4TypeInfo`
+const MAP_FIELDS: &[&str] = &[
+    "structs",
+    "headers",
+    "header_unions",
+    "enums",
+    "serializable_enums",
+    "new_types",
+];
+
+/// Enum fields, written as bare identifiers
+const ENUM_FIELDS: &[&str] = &[
+    "match_type",
+    "scope",
+    "unit",
+    "type",
+    "idle_timeout_behavior",
+];
+
+/// 64-bit integer fields, which the JSON mapping writes as strings
+const INT64_FIELDS: &[&str] = &["size", "int64_value"];
+
+/// Bytes fields, which the JSON mapping writes as base64
+///
+/// `value` is also the message of map entries, only scalar values are bytes.
+const BYTES_FIELDS: &[&str] = &["value"];
+
+/// Text format error
+#[derive(Debug, thiserror::Error)]
+#[error("line {line}, column {column}: {message}")]
+pub struct TextFormatError {
+    /// Line of the error, starting from 1
+    pub line: usize,
+    /// Column of the error, starting from 1
+    pub column: usize,
+    /// What went wrong
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(Vec<u8>),
+    Number(String),
+    Punct(char),
+}
+
+struct Lexer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Lexer {
+            input: input.as_bytes(),
+            pos: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> TextFormatError {
+        TextFormatError {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+
+    fn peek_byte(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let b = self.peek_byte()?;
+        self.pos += 1;
+        if b == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(b)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b) = self.peek_byte() {
+            match b {
+                b'#' => while !matches!(self.bump(), Some(b'\n') | None) {},
+                b if b.is_ascii_whitespace() => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, TextFormatError> {
+        self.skip_whitespace();
+
+        let Some(b) = self.peek_byte() else {
+            return Ok(None);
+        };
+
+        let token = match b {
+            b'{' | b'}' | b'<' | b'>' | b':' | b'[' | b']' | b',' | b';' => {
+                self.bump();
+                Token::Punct(b as char)
+            }
+            b'"' | b'\'' => Token::Str(self.string()?),
+            b'-' | b'.' | b'0'..=b'9' => Token::Number(self.word()),
+            b if b == b'_' || b.is_ascii_alphabetic() => Token::Ident(self.word()),
+            _ => return Err(self.error(format!("unexpected character '{}'", b as char))),
+        };
+
+        Ok(Some(token))
+    }
+
+    /// Read the type URL of an expanded Any, after the opening '['
+    fn type_url(&mut self) -> Result<String, TextFormatError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        loop {
+            match self.peek_byte() {
+                Some(b']') => break,
+                None | Some(b'\n') => return Err(self.error("unterminated type URL")),
+                Some(_) => {
+                    self.bump();
+                }
+            }
+        }
+        let url = String::from_utf8_lossy(&self.input[start..self.pos])
+            .trim()
+            .to_string();
+        self.bump();
+
+        Ok(url)
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while let Some(b) = self.peek_byte() {
+            if b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-' | b'+') {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, TextFormatError> {
+        let quote = self.bump().unwrap();
+        let mut out = Vec::new();
+
+        loop {
+            match self.bump() {
+                None | Some(b'\n') => return Err(self.error("unterminated string")),
+                Some(b) if b == quote => break,
+                Some(b'\\') => {
+                    let escaped = self
+                        .bump()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    match escaped {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'a' => out.push(0x07),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'v' => out.push(0x0b),
+                        b'\\' | b'\'' | b'"' | b'?' => out.push(escaped),
+                        b'x' | b'X' => {
+                            let mut value = 0u8;
+                            let mut digits = 0;
+                            while digits < 2 {
+                                match self.peek_byte().and_then(|b| (b as char).to_digit(16)) {
+                                    Some(d) => {
+                                        value = value * 16 + d as u8;
+                                        digits += 1;
+                                        self.bump();
+                                    }
+                                    None => break,
+                                }
+                            }
+                            if digits == 0 {
+                                return Err(self.error("invalid hex escape"));
+                            }
+                            out.push(value);
+                        }
+                        b'0'..=b'7' => {
+                            let mut value = (escaped - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.peek_byte() {
+                                    Some(b @ b'0'..=b'7') => {
+                                        value = value * 8 + (b - b'0') as u32;
+                                        self.bump();
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            if value > 0xff {
+                                return Err(self.error("invalid octal escape"));
+                            }
+                            out.push(value as u8);
+                        }
+                        _ => {
+                            return Err(
+                                self.error(format!("invalid escape '\\{}'", escaped as char))
+                            )
+                        }
+                    }
+                }
+                Some(b) => out.push(b),
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Token>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            lexer: Lexer::new(input),
+            peeked: None,
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token>, TextFormatError> {
+        if self.peeked.is_none() {
+            self.peeked = self.lexer.next_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next(&mut self) -> Result<Option<Token>, TextFormatError> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    fn eat(&mut self, punct: char) -> Result<bool, TextFormatError> {
+        if self.peek()? == Some(&Token::Punct(punct)) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), TextFormatError> {
+        if self.eat(punct)? {
+            Ok(())
+        } else {
+            Err(self.lexer.error(format!("expected '{}'", punct)))
+        }
+    }
+
+    /// Parse fields until `end`, or until the input ends if `end` is `None`
+    fn message(&mut self, end: Option<char>) -> Result<Map<String, Value>, TextFormatError> {
+        let mut fields = Map::new();
+
+        loop {
+            match self.next()? {
+                None if end.is_none() => break,
+                None => return Err(self.lexer.error("unexpected end of input")),
+                Some(Token::Punct(p)) if Some(p) == end => break,
+                Some(Token::Ident(name)) => {
+                    let value = self.field_value(&name)?;
+                    insert_field(&mut fields, name, value);
+                    // Fields may be separated by ',' or ';'
+                    if !self.eat(',')? {
+                        self.eat(';')?;
+                    }
+                }
+                Some(Token::Punct('[')) => {
+                    let type_url = self.lexer.type_url()?;
+                    if !type_url.contains('/') {
+                        return Err(self.lexer.error("extensions are not supported"));
+                    }
+                    self.any(&mut fields, type_url)?;
+                }
+                Some(token) => {
+                    return Err(self.lexer.error(format!("unexpected token {:?}", token)))
+                }
+            }
+        }
+
+        Ok(fields)
+    }
+
+    /// Parse the message of an expanded Any into its `type_url` and `value`
+    fn any(
+        &mut self,
+        fields: &mut Map<String, Value>,
+        type_url: String,
+    ) -> Result<(), TextFormatError> {
+        self.eat(':')?;
+        let message = if self.eat('{')? {
+            self.message(Some('}'))?
+        } else if self.eat('<')? {
+            self.message(Some('>'))?
+        } else {
+            return Err(self
+                .lexer
+                .error(format!("expected a message for '{}'", type_url)));
+        };
+
+        match encode_any(&type_url, Value::Object(message)) {
+            Ok(Some(value)) => {
+                fields.insert("value".to_string(), Value::String(base64_encode(&value)));
+            }
+            Ok(None) => {
+                return Err(self.lexer.error(format!(
+                    "unknown Any type '{}', load the binary or JSON P4Info instead",
+                    type_url
+                )))
+            }
+            Err(e) => {
+                return Err(self
+                    .lexer
+                    .error(format!("invalid '{}' message: {}", type_url, e)))
+            }
+        }
+        fields.insert("type_url".to_string(), Value::String(type_url));
+
+        Ok(())
+    }
+
+    fn field_value(&mut self, name: &str) -> Result<FieldValue, TextFormatError> {
+        let colon = self.eat(':')?;
+
+        if self.eat('{')? {
+            return Ok(FieldValue::Single(Value::Object(self.message(Some('}'))?)));
+        }
+        if self.eat('<')? {
+            return Ok(FieldValue::Single(Value::Object(self.message(Some('>'))?)));
+        }
+        if !colon {
+            return Err(self.lexer.error(format!("expected ':' after '{}'", name)));
+        }
+
+        if self.eat('[')? {
+            let mut values = Vec::new();
+            if !self.eat(']')? {
+                loop {
+                    values.push(self.list_element(name)?);
+                    if self.eat(']')? {
+                        break;
+                    }
+                    self.expect(',')?;
+                }
+            }
+            return Ok(FieldValue::List(values));
+        }
+
+        Ok(FieldValue::Single(self.scalar(name)?))
+    }
+
+    fn list_element(&mut self, name: &str) -> Result<Value, TextFormatError> {
+        if self.eat('{')? {
+            Ok(Value::Object(self.message(Some('}'))?))
+        } else if self.eat('<')? {
+            Ok(Value::Object(self.message(Some('>'))?))
+        } else {
+            self.scalar(name)
+        }
+    }
+
+    fn scalar(&mut self, name: &str) -> Result<Value, TextFormatError> {
+        match self.next()? {
+            Some(Token::Str(mut bytes)) => {
+                // Adjacent strings are concatenated
+                while let Some(Token::Str(_)) = self.peek()? {
+                    if let Some(Token::Str(more)) = self.next()? {
+                        bytes.extend(more);
+                    }
+                }
+
+                if BYTES_FIELDS.contains(&name) {
+                    Ok(Value::String(base64_encode(&bytes)))
+                } else {
+                    String::from_utf8(bytes)
+                        .map(Value::String)
+                        .map_err(|_| self.lexer.error(format!("invalid UTF-8 in '{}'", name)))
+                }
+            }
+            Some(Token::Number(n)) => {
+                parse_number(&n).ok_or_else(|| self.lexer.error(format!("invalid number '{}'", n)))
+            }
+            Some(Token::Ident(ident)) => Ok(match ident.as_str() {
+                "true" | "True" | "t" => Value::Bool(true),
+                "false" | "False" | "f" => Value::Bool(false),
+                _ => Value::String(ident),
+            }),
+            _ => Err(self.lexer.error(format!("expected a value for '{}'", name))),
+        }
+    }
+}
+
+enum FieldValue {
+    Single(Value),
+    List(Vec<Value>),
+}
+
+fn insert_field(fields: &mut Map<String, Value>, name: String, value: FieldValue) {
+    let repeated = REPEATED_FIELDS.contains(&name.as_str()) || MAP_FIELDS.contains(&name.as_str());
+
+    let values = match value {
+        FieldValue::Single(v) => vec![v],
+        FieldValue::List(vs) => vs,
+    };
+
+    if repeated {
+        let entry = fields
+            .entry(name)
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(array) = entry {
+            array.extend(values);
+        }
+    } else if let Some(v) = values.into_iter().last() {
+        // The last value of a singular field wins
+        fields.insert(name, v);
+    }
+}
+
+fn parse_number(n: &str) -> Option<Value> {
+    let (negative, digits) = match n.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, n),
+    };
+
+    let unsigned = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else if digits.len() > 1
+        && digits.starts_with('0')
+        && digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        u64::from_str_radix(&digits[1..], 8).ok()
+    } else {
+        digits.parse::<u64>().ok()
+    };
+
+    match unsigned {
+        Some(u) if negative => 0i64.checked_sub_unsigned(u).map(Value::from),
+        Some(u) => Some(Value::from(u)),
+        None => n
+            .trim_end_matches(['f', 'F'])
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+    }
+}
+
+/// Encode the message of an expanded Any, if its type is known
+fn encode_any(type_url: &str, mut message: Value) -> Result<Option<Vec<u8>>, serde_json::Error> {
+    macro_rules! encode {
+        ($($name:literal => $ty:ty),* $(,)?) => {
+            match type_url.rsplit('/').next().unwrap_or_default() {
+                $($name => serde_json::from_value::<$ty>(message).map(|m| Some(m.encode_to_vec())),)*
+                _ => Ok(None),
+            }
+        };
+    }
+
+    convert_maps(&mut message);
+    encode! {
+        "p4.config.v1.P4Info" => p4_cfg_v1::P4Info,
+        "p4.config.v1.Table" => p4_cfg_v1::Table,
+        "p4.config.v1.Action" => p4_cfg_v1::Action,
+        "p4.config.v1.ActionProfile" => p4_cfg_v1::ActionProfile,
+        "p4.config.v1.Counter" => p4_cfg_v1::Counter,
+        "p4.config.v1.DirectCounter" => p4_cfg_v1::DirectCounter,
+        "p4.config.v1.Meter" => p4_cfg_v1::Meter,
+        "p4.config.v1.DirectMeter" => p4_cfg_v1::DirectMeter,
+        "p4.config.v1.ControllerPacketMetadata" => p4_cfg_v1::ControllerPacketMetadata,
+        "p4.config.v1.ValueSet" => p4_cfg_v1::ValueSet,
+        "p4.config.v1.Register" => p4_cfg_v1::Register,
+        "p4.config.v1.Digest" => p4_cfg_v1::Digest,
+        "p4.config.v1.P4DataTypeSpec" => p4_cfg_v1::P4DataTypeSpec,
+    }
+}
+
+/// Convert map fields from lists of `key`/`value` entries into JSON objects
+fn convert_maps(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if MAP_FIELDS.contains(&name.as_str()) {
+                    if let Value::Array(entries) = field {
+                        let mut map = Map::new();
+                        for entry in entries.drain(..) {
+                            if let Value::Object(mut entry) = entry {
+                                let key = match entry.remove("key") {
+                                    Some(Value::String(key)) => key,
+                                    _ => String::new(),
+                                };
+                                let value = entry
+                                    .remove("value")
+                                    .unwrap_or_else(|| Value::Object(Map::new()));
+                                map.insert(key, value);
+                            }
+                        }
+                        *field = Value::Object(map);
+                    }
+                }
+                convert_maps(field);
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(convert_maps),
+        _ => {}
+    }
+}
+
+/// Parse text format into the protobuf JSON mapping
+pub(crate) fn text_to_json(input: &str) -> Result<Value, TextFormatError> {
+    let mut value = Value::Object(Parser::new(input).message(None)?);
+    convert_maps(&mut value);
+    Ok(value)
+}
+
+/// Write the protobuf JSON mapping as text format
+pub(crate) fn json_to_text(value: &Value) -> String {
+    let mut out = String::new();
+    if let Value::Object(fields) = value {
+        write_fields(&mut out, fields, 0);
+    }
+    out
+}
+
+fn write_fields(out: &mut String, fields: &Map<String, Value>, indent: usize) {
+    for (name, value) in fields {
+        let name = snake_case(name);
+        match value {
+            Value::Array(values) => {
+                for v in values {
+                    write_field(out, &name, v, indent);
+                }
+            }
+            Value::Object(map) if MAP_FIELDS.contains(&name.as_str()) => {
+                let mut entries = map.iter().collect::<Vec<_>>();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                for (key, v) in entries {
+                    let mut entry = Map::new();
+                    entry.insert("key".to_string(), Value::String(key.clone()));
+                    entry.insert("value".to_string(), v.clone());
+                    write_field(out, &name, &Value::Object(entry), indent);
+                }
+            }
+            v => write_field(out, &name, v, indent),
+        }
+    }
+}
+
+fn write_field(out: &mut String, name: &str, value: &Value, indent: usize) {
+    let pad = "  ".repeat(indent);
+
+    match value {
+        Value::Object(fields) => {
+            out.push_str(&format!("{}{} {{\n", pad, name));
+            write_fields(out, fields, indent + 1);
+            out.push_str(&format!("{}}}\n", pad));
+        }
+        Value::String(s) => {
+            let text = if ENUM_FIELDS.contains(&name)
+                || (INT64_FIELDS.contains(&name) && s.parse::<i64>().is_ok())
+            {
+                s.clone()
+            } else if BYTES_FIELDS.contains(&name) {
+                quote(&base64_decode(s).unwrap_or_else(|| s.as_bytes().to_vec()))
+            } else {
+                quote(s.as_bytes())
+            };
+            out.push_str(&format!("{}{}: {}\n", pad, name, text));
+        }
+        Value::Null => {}
+        v => out.push_str(&format!("{}{}: {}\n", pad, name, v)),
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn quote(bytes: &[u8]) -> String {
+    let mut out = String::from("\"");
+    for &b in bytes {
+        match b {
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            b'"' => out.push_str("\\\""),
+            b'\'' => out.push_str("\\'"),
+            b'\\' => out.push_str("\\\\"),
+            0x20..=0x7e => out.push(b as char),
+            _ => out.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.decode(s).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P4INFO_TEXT: &str = r#"
+# proto-file: p4/config/v1/p4info.proto
+# proto-message: p4.config.v1.P4Info
+
+pkg_info {
+  arch: "v1model"
+}
+tables {
+  preamble {
+    id: 37375156
+    name: "MyIngress.ipv4_lpm"
+    alias: "ipv4_lpm"
+  }
+  match_fields {
+    id: 1
+    name: "hdr.ipv4.dstAddr"
+    bitwidth: 32
+    match_type: LPM
+  }
+  action_refs {
+    id: 28792405
+  }
+  size: 1024
+}
+type_info {
+  serializable_enums {
+    key: "Color"
+    value {
+      underlying_type {
+        bitwidth: 8
+      }
+      members {
+        name: "RED"
+        value: "\001"
+      }
+    }
+  }
+}
+"#;
+
+    #[test]
+    fn parse_text_format() {
+        let json = text_to_json(P4INFO_TEXT).unwrap();
+
+        assert_eq!(json["pkg_info"]["arch"], "v1model");
+        assert_eq!(json["tables"][0]["preamble"]["id"], 37375156);
+        assert_eq!(json["tables"][0]["match_fields"][0]["match_type"], "LPM");
+        assert_eq!(json["tables"][0]["action_refs"][0]["id"], 28792405);
+        assert_eq!(
+            json["type_info"]["serializable_enums"]["Color"]["members"][0]["value"],
+            "AQ=="
+        );
+    }
+
+    #[test]
+    fn round_trip_text_format() {
+        let json = text_to_json(P4INFO_TEXT).unwrap();
+        let text = json_to_text(&json);
+
+        assert_eq!(text_to_json(&text).unwrap(), json);
+        assert!(text.contains("match_type: LPM\n"));
+        assert!(text.contains("value: \"\\001\"\n"));
+    }
+
+    #[test]
+    fn load_and_write_p4info() {
+        use crate::{error::P4InfoFileError, p4info::P4Info};
+
+        let p4info = P4Info::from_text(P4INFO_TEXT).unwrap();
+        assert_eq!(p4info.table_id("ipv4_lpm"), 37375156);
+
+        let from_text = P4Info::from_text(&p4info.to_text().unwrap()).unwrap();
+        let from_json = P4Info::from_json(&p4info.to_json().unwrap()).unwrap();
+        let from_binary = P4Info::from_binary(&p4info.to_binary().unwrap()).unwrap();
+        assert_eq!(from_text.as_ref(), p4info.as_ref());
+        assert_eq!(from_json.as_ref(), p4info.as_ref());
+        assert_eq!(from_binary.as_ref(), p4info.as_ref());
+
+        assert!(matches!(
+            P4Info::default().to_text(),
+            Err(P4InfoFileError::P4InfoNotLoaded)
+        ));
+        assert!(P4Info::default().to_binary().is_err());
+        assert!(P4Info::default().to_json().is_err());
+    }
+
+    #[test]
+    fn parse_expanded_any() {
+        let json = text_to_json(
+            r#"
+externs {
+  extern_type_id: 129
+  instances {
+    preamble { id: 1 name: "c" }
+    info {
+      [type.googleapis.com/p4.config.v1.Counter] {
+        preamble { id: 2 name: "c" }
+        size: 16
+      }
+    }
+  }
+}
+"#,
+        )
+        .unwrap();
+
+        let info = &json["externs"][0]["instances"][0]["info"];
+        assert_eq!(info["type_url"], "type.googleapis.com/p4.config.v1.Counter");
+        let value = base64_decode(info["value"].as_str().unwrap()).unwrap();
+        let counter = p4_cfg_v1::Counter::decode(value.as_slice()).unwrap();
+        assert_eq!(counter.preamble.unwrap().id, 2);
+        assert_eq!(counter.size, 16);
+
+        // Unknown types can't be encoded, packed ones are kept as is
+        let err = text_to_json("info { [type.googleapis.com/barefoot.Register] { size: 16 } }")
+            .unwrap_err();
+        assert!(err.message.contains("barefoot.Register"));
+        let json = text_to_json(
+            "info { type_url: \"type.googleapis.com/barefoot.Register\" value: \"\\020\" }",
+        )
+        .unwrap();
+        assert_eq!(json["info"]["value"], "EA==");
+
+        assert!(text_to_json("info { [p4.ext] { } }").is_err());
+    }
+
+    #[test]
+    fn report_error_position() {
+        let err = text_to_json("pkg_info {\n  arch: \n}").unwrap_err();
+        assert_eq!(err.line, 3);
+    }
+}
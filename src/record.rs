@@ -0,0 +1,421 @@
+//! Recording and replay of P4Runtime sessions
+//!
+//! A [`Recorder`] set on the client logs every Write, Read and
+//! SetForwardingPipelineConfig request, and every stream message sent or
+//! received, with a timestamp. Events are written as length-delimited
+//! [`RecordedEvent`] protobuf messages, so a recording can also be decoded
+//! with any protobuf library.
+//!
+//! A [`Replayer`] re-drives a recording against another server, e.g. a lab
+//! switch or the `testing::MockServer`, to reproduce an issue.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use p4runtime_client::{client::Client, record::{Recorder, Replayer}};
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut client = Client::builder()
+//!     .recorder(Some(Recorder::create("session.p4rec")?))
+//!     .build()?;
+//! // ... run the controller
+//!
+//! let mut lab = Client::builder().device_id(1).build()?;
+//! lab.connect("http://lab-switch:9559").await?;
+//! lab.run().await?;
+//! let report = Replayer::from_file("session.p4rec")?.replay(&mut lab).await;
+//! for (i, e) in &report.errors {
+//!     eprintln!("event {}: {}", i, e);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use log::warn;
+use p4runtime::p4::v1 as p4_v1;
+use prost::Message;
+
+use crate::{
+    client::Client,
+    error::{ClientError, RecordError},
+    stream::StreamHandler,
+};
+
+/// An event of a recorded session
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RecordedEvent {
+    /// Microseconds since the Unix epoch
+    #[prost(uint64, tag = "1")]
+    pub timestamp_us: u64,
+
+    /// The recorded message
+    #[prost(oneof = "recorded_event::Event", tags = "2, 3, 4, 5, 6")]
+    pub event: Option<recorded_event::Event>,
+}
+
+/// Nested types of [`RecordedEvent`]
+pub mod recorded_event {
+    use p4runtime::p4::v1 as p4_v1;
+
+    /// The recorded message
+    ///
+    /// Like the generated P4Runtime oneofs, variants are not boxed.
+    #[allow(clippy::large_enum_variant)]
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Event {
+        /// A sent Write request
+        #[prost(message, tag = "2")]
+        Write(p4_v1::WriteRequest),
+
+        /// A sent Read request
+        #[prost(message, tag = "3")]
+        Read(p4_v1::ReadRequest),
+
+        /// A sent SetForwardingPipelineConfig request
+        #[prost(message, tag = "4")]
+        SetPipeline(p4_v1::SetForwardingPipelineConfigRequest),
+
+        /// A sent stream message
+        #[prost(message, tag = "5")]
+        StreamRequest(p4_v1::StreamMessageRequest),
+
+        /// A received stream message
+        #[prost(message, tag = "6")]
+        StreamResponse(p4_v1::StreamMessageResponse),
+    }
+}
+
+impl RecordedEvent {
+    /// Create an event timestamped now
+    pub fn now(event: recorded_event::Event) -> Self {
+        let timestamp_us = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        RecordedEvent {
+            timestamp_us,
+            event: Some(event),
+        }
+    }
+}
+
+/// Session recorder
+///
+/// Clones write to the same sink. Write failures are logged and do not fail
+/// the recorded operation.
+#[derive(Clone)]
+pub struct Recorder {
+    sink: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder").finish_non_exhaustive()
+    }
+}
+
+impl Recorder {
+    /// Create a recorder writing to a sink
+    ///
+    /// The sink is flushed after every event.
+    pub fn new(sink: impl Write + Send + 'static) -> Self {
+        Recorder {
+            sink: Arc::new(Mutex::new(Box::new(sink))),
+        }
+    }
+
+    /// Create a recorder writing to a new file
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Record an event timestamped now
+    pub fn record(&self, event: recorded_event::Event) {
+        let buf = RecordedEvent::now(event).encode_length_delimited_to_vec();
+
+        let mut sink = self.sink.lock().unwrap();
+        if let Err(e) = sink.write_all(&buf).and_then(|_| sink.flush()) {
+            warn!("Failed to record event: {}", e);
+        }
+    }
+}
+
+impl StreamHandler for Recorder {
+    fn on_arbitration(&self, arbitration: &p4_v1::MasterArbitrationUpdate) {
+        record_response(
+            self,
+            p4_v1::stream_message_response::Update::Arbitration(arbitration.clone()),
+        );
+    }
+
+    fn on_packet_in(&self, packet: &p4_v1::PacketIn) {
+        record_response(
+            self,
+            p4_v1::stream_message_response::Update::Packet(packet.clone()),
+        );
+    }
+
+    fn on_digest(&self, digest: &p4_v1::DigestList) {
+        record_response(
+            self,
+            p4_v1::stream_message_response::Update::Digest(digest.clone()),
+        );
+    }
+
+    fn on_idle_timeout(&self, notification: &p4_v1::IdleTimeoutNotification) {
+        record_response(
+            self,
+            p4_v1::stream_message_response::Update::IdleTimeoutNotification(notification.clone()),
+        );
+    }
+
+    fn on_error(&self, error: &p4_v1::StreamError) {
+        record_response(
+            self,
+            p4_v1::stream_message_response::Update::Error(error.clone()),
+        );
+    }
+
+    fn on_other(&self, other: &pbjson_types::Any) {
+        record_response(
+            self,
+            p4_v1::stream_message_response::Update::Other(other.clone()),
+        );
+    }
+}
+
+fn record_response(recorder: &Recorder, update: p4_v1::stream_message_response::Update) {
+    recorder.record(recorded_event::Event::StreamResponse(
+        p4_v1::StreamMessageResponse {
+            update: Some(update),
+        },
+    ));
+}
+
+/// Decode a recording
+pub fn decode_recording(mut content: &[u8]) -> Result<Vec<RecordedEvent>, RecordError> {
+    let mut events = Vec::new();
+    while !content.is_empty() {
+        events.push(RecordedEvent::decode_length_delimited(&mut content)?);
+    }
+
+    Ok(events)
+}
+
+/// Read a recording file
+pub fn read_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedEvent>, RecordError> {
+    decode_recording(&std::fs::read(path)?)
+}
+
+/// Outcome of a replay
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Number of events sent to the server
+    pub sent: usize,
+
+    /// Number of events which can't be re-driven, i.e. received stream
+    /// messages and arbitration requests
+    pub skipped: usize,
+
+    /// Failed events, by index in the recording
+    pub errors: Vec<(usize, ClientError)>,
+}
+
+/// Session replayer
+#[derive(Clone, Debug, Default)]
+pub struct Replayer {
+    events: Vec<RecordedEvent>,
+    paced: bool,
+}
+
+impl Replayer {
+    /// Create a replayer of recorded events
+    pub fn new(events: Vec<RecordedEvent>) -> Self {
+        Replayer {
+            events,
+            paced: false,
+        }
+    }
+
+    /// Create a replayer of a recording file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RecordError> {
+        Ok(Self::new(read_recording(path)?))
+    }
+
+    /// Keep the recorded delays between events
+    ///
+    /// By default, events are replayed as fast as possible.
+    pub fn paced(mut self, paced: bool) -> Self {
+        self.paced = paced;
+        self
+    }
+
+    /// Get the recorded events
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Re-drive the recorded requests through a client
+    ///
+    /// The client must be connected and running. Requests are sent with the
+    /// device id, election id and role of the client, and Write requests keep
+    /// their atomicity. A recorded pipeline is loaded into the client with its
+    /// P4Info. Failures are reported and do not stop the replay.
+    pub async fn replay(&self, client: &mut Client) -> ReplayReport {
+        use recorded_event::Event;
+
+        let mut report = ReplayReport::default();
+        let mut previous: Option<u64> = None;
+
+        for (i, recorded) in self.events.iter().enumerate() {
+            if self.paced {
+                if let Some(previous) = previous {
+                    let delay = recorded.timestamp_us.saturating_sub(previous);
+                    tokio::time::sleep(Duration::from_micros(delay)).await;
+                }
+                previous = Some(recorded.timestamp_us);
+            }
+
+            let result = match &recorded.event {
+                Some(Event::Write(req)) => client
+                    .write_update_batch_with(req.updates.clone(), req.atomicity())
+                    .await
+                    .map(|_| ()),
+                Some(Event::Read(req)) => client
+                    .read_entities_batch(req.entities.clone())
+                    .await
+                    .map(|_| ()),
+                Some(Event::SetPipeline(req)) => {
+                    let config = req.config.clone().unwrap_or_default();
+                    if let Some(p4info) = config.p4info {
+                        client.p4info_mut().load(p4info);
+                    }
+                    client
+                        .set_forwarding_pipeline_config_with(
+                            req.action(),
+                            req.config.as_ref().map(|_| config.p4_device_config),
+                            config.cookie.map(|c| c.cookie).unwrap_or_default(),
+                        )
+                        .await
+                        .map(|_| ())
+                }
+                Some(Event::StreamRequest(req)) if !is_arbitration(req) => {
+                    client.send_message_request(req.clone()).await.map(|_| ())
+                }
+                _ => {
+                    report.skipped += 1;
+                    continue;
+                }
+            };
+
+            report.sent += 1;
+            if let Err(e) = result {
+                report.errors.push((i, e));
+            }
+        }
+
+        report
+    }
+}
+
+fn is_arbitration(req: &p4_v1::StreamMessageRequest) -> bool {
+    matches!(
+        req.update,
+        Some(p4_v1::stream_message_request::Update::Arbitration(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sink shared with the test
+    #[derive(Clone, Default)]
+    struct Sink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn recording_round_trip() {
+        let sink = Sink::default();
+        let recorder = Recorder::new(sink.clone());
+
+        let write = p4_v1::WriteRequest {
+            device_id: 1,
+            ..Default::default()
+        };
+        recorder.record(recorded_event::Event::Write(write.clone()));
+        recorder.on_packet_in(&p4_v1::PacketIn {
+            payload: vec![1, 2],
+            ..Default::default()
+        });
+
+        let events = decode_recording(&sink.0.lock().unwrap()).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, Some(recorded_event::Event::Write(write)));
+        assert!(events[0].timestamp_us <= events[1].timestamp_us);
+        assert!(matches!(
+            &events[1].event,
+            Some(recorded_event::Event::StreamResponse(res))
+                if matches!(&res.update, Some(p4_v1::stream_message_response::Update::Packet(p)) if p.payload == vec![1, 2])
+        ));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn replay_keeps_atomicity() {
+        use crate::testing::MockServer;
+
+        let server = MockServer::new();
+        let mut client = Client::builder()
+            .device_id(1)
+            .election_id(p4_v1::Uint128 { high: 0, low: 1 })
+            .build()
+            .unwrap();
+        client.connect_with_channel(server.channel().await.unwrap());
+        client.run().await.unwrap();
+        client
+            .p4info_mut()
+            .load(p4runtime::p4::config::v1::P4Info::default());
+        client.set_forwarding_pipeline_config(vec![]).await.unwrap();
+
+        let insert = p4_v1::Update {
+            r#type: p4_v1::update::Type::Insert as i32,
+            entity: Some(p4_v1::Entity {
+                entity: Some(p4_v1::entity::Entity::TableEntry(p4_v1::TableEntry {
+                    table_id: 1,
+                    ..Default::default()
+                })),
+            }),
+        };
+        let write = p4_v1::WriteRequest {
+            updates: vec![insert.clone(), insert],
+            atomicity: p4_v1::write_request::Atomicity::RollbackOnError as i32,
+            ..Default::default()
+        };
+
+        let report = Replayer::new(vec![RecordedEvent::now(recorded_event::Event::Write(
+            write,
+        ))])
+        .replay(&mut client)
+        .await;
+        assert_eq!(report.errors.len(), 1);
+        assert!(server.entities().is_empty());
+    }
+}
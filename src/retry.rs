@@ -0,0 +1,128 @@
+//! Retry policy for idempotent RPCs
+//!
+//! `Read`, `Capabilities` and `GetForwardingPipelineConfig` do not change the
+//! device state, so they can safely be sent again when the server is briefly
+//! unavailable. Writes and pipeline changes are never retried.
+
+use std::{future::Future, time::Duration};
+
+use log::debug;
+
+use crate::error::ClientError;
+
+/// Retry policy for idempotent RPCs
+///
+/// Attempts are spaced by an exponential backoff, doubling from
+/// `initial_backoff` up to `max_backoff`. The default policy does not retry.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use p4runtime_client::{client::Client, retry::RetryPolicy};
+/// let client = Client::builder()
+///     .retry_policy(
+///         RetryPolicy::new(5).backoff(Duration::from_millis(50), Duration::from_secs(1)),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Backoff before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound of the backoff
+    pub max_backoff: Duration,
+    /// gRPC status codes which are retried
+    pub codes: Vec<tonic::Code>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            codes: vec![tonic::Code::Unavailable],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy retrying `Unavailable` up to `max_attempts` attempts
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    /// Set the initial and maximum backoff
+    pub fn backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the gRPC status codes which are retried
+    pub fn retry_on(mut self, codes: impl IntoIterator<Item = tonic::Code>) -> Self {
+        self.codes = codes.into_iter().collect();
+        self
+    }
+
+    /// Backoff before the given retry, starting from 1
+    pub fn backoff_for(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    fn should_retry(&self, error: &ClientError) -> bool {
+        matches!(error, ClientError::Status(status) if self.codes.contains(&status.code()))
+    }
+
+    /// Run an operation, retrying it according to the policy
+    pub(crate) async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T, ClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(e) if attempt < self.max_attempts && self.should_retry(&e) => {
+                    let backoff = self.backoff_for(attempt);
+                    debug!(
+                        "Retrying in {:?} after attempt {} failed: {}",
+                        backoff, attempt, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_backoff() {
+        let policy =
+            RetryPolicy::new(10).backoff(Duration::from_millis(100), Duration::from_secs(1));
+
+        assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_for(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff_for(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff_for(100), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,220 @@
+//! Role helpers
+//!
+//! A P4Runtime role partitions the entities of a device between controllers.
+//! Each role carries an optional, target-specific `role_config` as
+//! `google.protobuf.Any`. A [`RoleScope`] describes the P4 objects a role owns,
+//! so that writes outside of it are rejected before reaching the server.
+
+use std::collections::HashSet;
+
+use p4runtime::p4::v1 as p4_v1;
+
+use crate::{error::RoleError, p4info::P4Info, stream::extension::encode_any};
+
+/// Create a Role
+///
+/// # Example
+///
+/// ```rust
+/// # use p4runtime_client::{client::Client, role::new_role};
+/// let client = Client::builder()
+///     .role(Some(new_role("ingress-controller", None)))
+///     .build()
+///     .unwrap();
+/// assert_eq!(client.role_name().as_deref(), Some("ingress-controller"));
+/// ```
+pub fn new_role(name: impl Into<String>, config: Option<pbjson_types::Any>) -> p4_v1::Role {
+    p4_v1::Role {
+        name: name.into(),
+        config,
+
+        ..Default::default()
+    }
+}
+
+/// Create a Role with a typed `role_config`
+pub fn new_role_with_config<M: prost::Message>(
+    name: impl Into<String>,
+    type_url: impl Into<String>,
+    config: &M,
+) -> p4_v1::Role {
+    new_role(name, Some(encode_any(type_url, config)))
+}
+
+/// P4 objects owned by a role
+///
+/// Objects are identified by their P4Info id. Direct counters and meters
+/// belong to the role owning their table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RoleScope {
+    ids: HashSet<u32>,
+    packet_replication_engine: bool,
+}
+
+impl RoleScope {
+    /// Create an empty scope, owning nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a scope from P4 object names or aliases
+    ///
+    /// Tables, action profiles, counters, meters, value sets, registers,
+    /// digests and extern instances are searched.
+    pub fn from_names<'a>(
+        p4info: &P4Info,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, RoleError> {
+        let mut scope = Self::new();
+        for name in names {
+            scope.ids.insert(object_id(p4info, name)?);
+        }
+
+        Ok(scope)
+    }
+
+    /// Add a P4 object by id
+    pub fn with_id(mut self, id: u32) -> Self {
+        self.ids.insert(id);
+        self
+    }
+
+    /// Add P4 objects by id
+    pub fn with_ids(mut self, ids: impl IntoIterator<Item = u32>) -> Self {
+        self.ids.extend(ids);
+        self
+    }
+
+    /// Allow multicast groups and clone sessions, which have no P4Info id
+    pub fn with_packet_replication_engine(mut self, allow: bool) -> Self {
+        self.packet_replication_engine = allow;
+        self
+    }
+
+    /// Whether the role owns the P4 object with the given id
+    pub fn contains_id(&self, id: u32) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// Check that the role owns an entity
+    pub fn check_entity(&self, entity: &p4_v1::Entity) -> Result<(), RoleError> {
+        use p4_v1::entity::Entity;
+
+        let id = match &entity.entity {
+            Some(Entity::TableEntry(e)) => e.table_id,
+            Some(Entity::ActionProfileMember(e)) => e.action_profile_id,
+            Some(Entity::ActionProfileGroup(e)) => e.action_profile_id,
+            Some(Entity::MeterEntry(e)) => e.meter_id,
+            Some(Entity::DirectMeterEntry(e)) => {
+                e.table_entry.as_ref().map(|t| t.table_id).unwrap_or(0)
+            }
+            Some(Entity::CounterEntry(e)) => e.counter_id,
+            Some(Entity::DirectCounterEntry(e)) => {
+                e.table_entry.as_ref().map(|t| t.table_id).unwrap_or(0)
+            }
+            Some(Entity::ValueSetEntry(e)) => e.value_set_id,
+            Some(Entity::RegisterEntry(e)) => e.register_id,
+            Some(Entity::DigestEntry(e)) => e.digest_id,
+            Some(Entity::ExternEntry(e)) => e.extern_id,
+            Some(Entity::PacketReplicationEngineEntry(_)) => {
+                return if self.packet_replication_engine {
+                    Ok(())
+                } else {
+                    Err(RoleError::PacketReplicationEngineOutsideRole)
+                };
+            }
+            None => return Ok(()),
+        };
+
+        if self.contains_id(id) {
+            Ok(())
+        } else {
+            Err(RoleError::OutsideRole { id })
+        }
+    }
+
+    /// Check that the role owns the entities of all updates
+    pub fn check_updates(&self, updates: &[p4_v1::Update]) -> Result<(), RoleError> {
+        for entity in updates.iter().filter_map(|u| u.entity.as_ref()) {
+            self.check_entity(entity)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Find the id of a P4 object which can be owned by a role
+fn object_id(p4info: &P4Info, name: &str) -> Result<u32, RoleError> {
+    let Some(p4info) = p4info.inner() else {
+        return Err(RoleError::P4InfoNotLoaded);
+    };
+
+    let preambles = p4info
+        .tables
+        .iter()
+        .map(|t| &t.preamble)
+        .chain(p4info.action_profiles.iter().map(|a| &a.preamble))
+        .chain(p4info.counters.iter().map(|c| &c.preamble))
+        .chain(p4info.meters.iter().map(|m| &m.preamble))
+        .chain(p4info.value_sets.iter().map(|v| &v.preamble))
+        .chain(p4info.registers.iter().map(|r| &r.preamble))
+        .chain(p4info.digests.iter().map(|d| &d.preamble))
+        .chain(
+            p4info
+                .externs
+                .iter()
+                .flat_map(|e| e.instances.iter().map(|i| &i.preamble)),
+        );
+
+    preambles
+        .flatten()
+        .find(|p| p.name == name || p.alias == name)
+        .map(|p| p.id)
+        .ok_or_else(|| RoleError::UnknownObject {
+            name: name.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use p4runtime::p4::config::v1 as p4_cfg_v1;
+
+    use super::*;
+    use crate::test_utils::preamble;
+
+    fn table_update(table_id: u32) -> p4_v1::Update {
+        p4_v1::Update {
+            r#type: p4_v1::update::Type::Insert as i32,
+            entity: Some(p4_v1::Entity {
+                entity: Some(p4_v1::entity::Entity::TableEntry(p4_v1::TableEntry {
+                    table_id,
+                    ..Default::default()
+                })),
+            }),
+        }
+    }
+
+    #[test]
+    fn reject_updates_outside_role() {
+        let mut p4info = P4Info::default();
+        p4info.load(p4_cfg_v1::P4Info {
+            tables: vec![p4_cfg_v1::Table {
+                preamble: preamble(0x0200_0001, "MyIngress.acl", "acl"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        let scope = RoleScope::from_names(&p4info, ["acl"]).unwrap();
+        assert!(scope.check_updates(&[table_update(0x0200_0001)]).is_ok());
+        assert!(matches!(
+            scope.check_updates(&[table_update(0x0200_0001), table_update(0x0200_0002)]),
+            Err(RoleError::OutsideRole { id: 0x0200_0002 })
+        ));
+        assert!(RoleScope::from_names(&p4info, ["l2"]).is_err());
+        assert!(matches!(
+            RoleScope::from_names(&P4Info::default(), ["acl"]),
+            Err(RoleError::P4InfoNotLoaded)
+        ));
+    }
+}
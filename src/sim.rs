@@ -0,0 +1,1078 @@
+//! Reference entity store
+//!
+//! [`EntityStore`] applies P4Runtime writes the way a target does, checking
+//! them against the P4Info, so that spec violations are caught before the
+//! controller runs against hardware. It checks:
+//!
+//! - duplicate inserts (`ALREADY_EXISTS`) and missing modifies and deletes (`NOT_FOUND`)
+//! - table sizes (`RESOURCE_EXHAUSTED`)
+//! - match fields and their types, and priorities of ternary, range and optional tables
+//! - actions and their parameters, including action scopes and const default actions
+//! - const tables (`PERMISSION_DENIED`)
+//! - indexes of counters, meters and registers (`OUT_OF_RANGE`)
+//! - members of action profile groups
+//!
+//! Match values are not checked against the field bitwidths.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use p4runtime_client::{p4info::P4Info, sim::EntityStore};
+//! # use p4runtime_client::p4runtime::p4::v1 as p4_v1;
+//! # fn example(update: p4_v1::Update) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut store = EntityStore::new(P4Info::from_file("build/main.p4info.txtpb")?);
+//! if let Err(e) = store.apply(&update) {
+//!     eprintln!("{:?}: {}", e.code(), e);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+use p4runtime::p4::v1 as p4_v1;
+
+use crate::{error::SimError, p4info::P4Info};
+
+/// Entity store checking writes like a P4Runtime target
+///
+/// A store created with [`Default`] has no P4Info and only checks the
+/// existence of entities.
+#[derive(Clone, Debug, Default)]
+pub struct EntityStore {
+    p4info: Option<Arc<P4Info>>,
+    entities: BTreeMap<Vec<u8>, p4_v1::Entity>,
+}
+
+impl SimError {
+    /// gRPC status code a target returns for the error
+    pub fn code(&self) -> tonic::Code {
+        use tonic::Code;
+
+        match self {
+            SimError::AlreadyExists => Code::AlreadyExists,
+            SimError::NotFound | SimError::UnknownId { .. } => Code::NotFound,
+            SimError::TableFull { .. } => Code::ResourceExhausted,
+            SimError::ConstTable { .. } | SimError::ConstDefaultAction { .. } => {
+                Code::PermissionDenied
+            }
+            SimError::IndexOutOfRange { .. } => Code::OutOfRange,
+            _ => Code::InvalidArgument,
+        }
+    }
+}
+
+impl From<SimError> for tonic::Status {
+    fn from(error: SimError) -> Self {
+        tonic::Status::new(error.code(), error.to_string())
+    }
+}
+
+impl EntityStore {
+    /// Create an empty store checking writes against a P4Info
+    ///
+    /// The P4Info must be loaded.
+    pub fn new(p4info: impl Into<Arc<P4Info>>) -> Self {
+        EntityStore {
+            p4info: Some(p4info.into()),
+            entities: BTreeMap::new(),
+        }
+    }
+
+    /// Get the P4Info writes are checked against
+    pub fn p4info(&self) -> Option<&P4Info> {
+        self.p4info.as_deref()
+    }
+
+    /// Number of stored entities
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Whether no entity is stored
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Iterate over the stored entities
+    pub fn entities(&self) -> impl Iterator<Item = &p4_v1::Entity> {
+        self.entities.values()
+    }
+
+    /// Remove all entities
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    /// Store an entity without checks, replacing the one with the same key
+    pub fn insert(&mut self, entity: p4_v1::Entity) -> Option<p4_v1::Entity> {
+        let key = entity_key(&entity)?;
+        self.entities.insert(key, entity)
+    }
+
+    /// Apply a batch of updates
+    ///
+    /// Like the default `CONTINUE_ON_ERROR` atomicity, a failing update does
+    /// not stop the following ones. One result is returned per update.
+    pub fn write(&mut self, updates: &[p4_v1::Update]) -> Vec<Result<(), SimError>> {
+        updates.iter().map(|update| self.apply(update)).collect()
+    }
+
+    /// Apply a single update
+    pub fn apply(&mut self, update: &p4_v1::Update) -> Result<(), SimError> {
+        use p4_v1::update::Type;
+
+        let r#type = match Type::try_from(update.r#type).unwrap_or_default() {
+            Type::Unspecified => return Err(SimError::MissingUpdateType),
+            r#type => r#type,
+        };
+        let entity = update.entity.as_ref().ok_or(SimError::MissingEntity)?;
+        let key = entity_key(entity).ok_or(SimError::MissingEntity)?;
+
+        if let Some(kind) = modify_only_kind(entity) {
+            if r#type != Type::Modify {
+                return Err(SimError::ModifyOnly {
+                    kind: kind.to_string(),
+                });
+            }
+        }
+        if let Some(p4info) = &self.p4info {
+            self.check(p4info, r#type, entity)?;
+        }
+
+        let exists = self.entities.contains_key(&key);
+        match r#type {
+            Type::Insert if exists => return Err(SimError::AlreadyExists),
+            Type::Modify if !exists && modify_only_kind(entity).is_none() => {
+                return Err(SimError::NotFound)
+            }
+            Type::Delete if !exists => return Err(SimError::NotFound),
+            _ => {}
+        }
+
+        match (r#type, &entity.entity) {
+            // Resetting the default action restores the initial one
+            (Type::Modify, Some(p4_v1::entity::Entity::TableEntry(e)))
+                if e.is_default_action && e.action.is_none() =>
+            {
+                self.entities.remove(&key);
+            }
+            (Type::Delete, Some(p4_v1::entity::Entity::TableEntry(e))) => {
+                self.entities.remove(&key);
+                self.remove_direct_resources(e);
+            }
+            (Type::Delete, _) => {
+                self.entities.remove(&key);
+            }
+            _ => {
+                self.entities.insert(key, entity.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the stored entities matching a read request entity
+    ///
+    /// Zero ids and missing indexes are wildcards, as are table entries
+    /// without match fields, which match all non-default entries of a table.
+    pub fn read(&self, entity: &p4_v1::Entity) -> Vec<p4_v1::Entity> {
+        self.read_batch(std::slice::from_ref(entity))
+    }
+
+    /// Read the stored entities matching any of the read request entities
+    pub fn read_batch(&self, entities: &[p4_v1::Entity]) -> Vec<p4_v1::Entity> {
+        self.entities
+            .values()
+            .filter(|entity| entities.iter().any(|filter| entity_matches(filter, entity)))
+            .cloned()
+            .collect()
+    }
+
+    /// Remove the direct counter and meter entries of a deleted table entry
+    fn remove_direct_resources(&mut self, table_entry: &p4_v1::TableEntry) {
+        use p4_v1::entity::Entity;
+
+        let table_entry = Some(table_entry.clone());
+        for entity in [
+            Entity::DirectCounterEntry(p4_v1::DirectCounterEntry {
+                table_entry: table_entry.clone(),
+                ..Default::default()
+            }),
+            Entity::DirectMeterEntry(p4_v1::DirectMeterEntry {
+                table_entry,
+                ..Default::default()
+            }),
+        ] {
+            let entity = p4_v1::Entity {
+                entity: Some(entity),
+            };
+            if let Some(key) = entity_key(&entity) {
+                self.entities.remove(&key);
+            }
+        }
+    }
+
+    /// Check an update against the P4Info
+    fn check(
+        &self,
+        p4info: &P4Info,
+        r#type: p4_v1::update::Type,
+        entity: &p4_v1::Entity,
+    ) -> Result<(), SimError> {
+        use p4_v1::entity::Entity;
+
+        let p4info: &p4_cfg_v1::P4Info = p4info.as_ref();
+        match &entity.entity {
+            Some(Entity::TableEntry(e)) => self.check_table_entry(p4info, r#type, e),
+            Some(Entity::ActionProfileMember(e)) => {
+                find(
+                    &p4info.action_profiles,
+                    e.action_profile_id,
+                    "action profile",
+                )?;
+                if let Some(action) = &e.action {
+                    check_action_params(p4info, action)?;
+                }
+                Ok(())
+            }
+            Some(Entity::ActionProfileGroup(e)) => {
+                find(
+                    &p4info.action_profiles,
+                    e.action_profile_id,
+                    "action profile",
+                )?;
+                if r#type == p4_v1::update::Type::Delete {
+                    return Ok(());
+                }
+                for member in &e.members {
+                    let key = entity_key(&p4_v1::Entity {
+                        entity: Some(Entity::ActionProfileMember(p4_v1::ActionProfileMember {
+                            action_profile_id: e.action_profile_id,
+                            member_id: member.member_id,
+                            ..Default::default()
+                        })),
+                    });
+                    if !key.is_some_and(|key| self.entities.contains_key(&key)) {
+                        return Err(SimError::NotFound);
+                    }
+                }
+                Ok(())
+            }
+            Some(Entity::MeterEntry(e)) => {
+                let meter = find(&p4info.meters, e.meter_id, "meter")?;
+                check_index(&meter.preamble, e.index, meter.size)
+            }
+            Some(Entity::CounterEntry(e)) => {
+                let counter = find(&p4info.counters, e.counter_id, "counter")?;
+                check_index(&counter.preamble, e.index, counter.size)
+            }
+            Some(Entity::RegisterEntry(e)) => {
+                let register = find(&p4info.registers, e.register_id, "register")?;
+                check_index(&register.preamble, e.index, register.size.into())
+            }
+            Some(Entity::DirectCounterEntry(p4_v1::DirectCounterEntry {
+                table_entry: Some(table_entry),
+                ..
+            }))
+            | Some(Entity::DirectMeterEntry(p4_v1::DirectMeterEntry {
+                table_entry: Some(table_entry),
+                ..
+            })) => {
+                // The default entry of a table always exists
+                if table_entry.is_default_action {
+                    find(&p4info.tables, table_entry.table_id, "table")?;
+                    return Ok(());
+                }
+                let table_entry = p4_v1::Entity {
+                    entity: Some(Entity::TableEntry(table_entry.clone())),
+                };
+                let key = entity_key(&table_entry).ok_or(SimError::MissingEntity)?;
+                if self.entities.contains_key(&key) {
+                    Ok(())
+                } else {
+                    Err(SimError::NotFound)
+                }
+            }
+            Some(Entity::DirectCounterEntry(_)) | Some(Entity::DirectMeterEntry(_)) => {
+                Err(SimError::MissingEntity)
+            }
+            Some(Entity::ValueSetEntry(e)) => {
+                find(&p4info.value_sets, e.value_set_id, "value set")?;
+                Ok(())
+            }
+            Some(Entity::DigestEntry(e)) => {
+                find(&p4info.digests, e.digest_id, "digest")?;
+                Ok(())
+            }
+            Some(Entity::ExternEntry(_)) | Some(Entity::PacketReplicationEngineEntry(_)) => Ok(()),
+            None => Err(SimError::MissingEntity),
+        }
+    }
+
+    fn check_table_entry(
+        &self,
+        p4info: &p4_cfg_v1::P4Info,
+        r#type: p4_v1::update::Type,
+        entry: &p4_v1::TableEntry,
+    ) -> Result<(), SimError> {
+        use p4_cfg_v1::action_ref::Scope;
+        use p4_v1::table_action::Type as ActionType;
+
+        let table = find(&p4info.tables, entry.table_id, "table")?;
+        let table_name = name(&table.preamble);
+
+        if entry.is_default_action {
+            if !entry.r#match.is_empty() {
+                return Err(SimError::DefaultEntryWithMatch { table: table_name });
+            }
+            if entry.priority != 0 {
+                return Err(SimError::UnexpectedPriority { table: table_name });
+            }
+            if table.const_default_action_id != 0 {
+                return Err(SimError::ConstDefaultAction { table: table_name });
+            }
+        } else {
+            if table.is_const_table {
+                return Err(SimError::ConstTable { table: table_name });
+            }
+            check_match(table, entry)?;
+        }
+
+        if r#type == p4_v1::update::Type::Delete {
+            return Ok(());
+        }
+
+        match entry.action.as_ref().and_then(|a| a.r#type.as_ref()) {
+            // Modifying the default entry without action resets it
+            None if entry.is_default_action => {}
+            None => return Err(SimError::MissingAction { table: table_name }),
+            Some(ActionType::Action(action)) => {
+                if table.implementation_id != 0 {
+                    return Err(SimError::ActionTypeMismatch { table: table_name });
+                }
+                let action_ref = table
+                    .action_refs
+                    .iter()
+                    .find(|r| r.id == action.action_id)
+                    .ok_or_else(|| SimError::ActionNotAllowed {
+                        table: table_name.clone(),
+                        action: action_name(p4info, action.action_id),
+                    })?;
+                match (action_ref.scope(), entry.is_default_action) {
+                    (Scope::DefaultOnly, false) => {
+                        return Err(SimError::DefaultOnlyAction {
+                            table: table_name,
+                            action: action_name(p4info, action.action_id),
+                        })
+                    }
+                    (Scope::TableOnly, true) => {
+                        return Err(SimError::TableOnlyAction {
+                            table: table_name,
+                            action: action_name(p4info, action.action_id),
+                        })
+                    }
+                    _ => {}
+                }
+                check_action_params(p4info, action)?;
+            }
+            Some(_) => {
+                if table.implementation_id == 0 {
+                    return Err(SimError::ActionTypeMismatch { table: table_name });
+                }
+            }
+        }
+
+        // Duplicates are reported as such rather than as a full table
+        let key = entity_key(&p4_v1::Entity {
+            entity: Some(p4_v1::entity::Entity::TableEntry(entry.clone())),
+        });
+        let exists = key.is_some_and(|key| self.entities.contains_key(&key));
+        if r#type == p4_v1::update::Type::Insert && table.size > 0 && !exists {
+            let n = self
+                .entities
+                .values()
+                .filter(|e| match &e.entity {
+                    Some(p4_v1::entity::Entity::TableEntry(e)) => {
+                        e.table_id == entry.table_id && !e.is_default_action
+                    }
+                    _ => false,
+                })
+                .count();
+            if n as i64 >= table.size {
+                return Err(SimError::TableFull {
+                    table: table_name,
+                    size: table.size,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// P4Info objects with a preamble
+trait HasPreamble {
+    fn preamble(&self) -> &Option<p4_cfg_v1::Preamble>;
+}
+
+macro_rules! impl_has_preamble {
+    ($($t:ty),*) => {
+        $(impl HasPreamble for $t {
+            fn preamble(&self) -> &Option<p4_cfg_v1::Preamble> {
+                &self.preamble
+            }
+        })*
+    };
+}
+
+impl_has_preamble!(
+    p4_cfg_v1::Table,
+    p4_cfg_v1::Action,
+    p4_cfg_v1::ActionProfile,
+    p4_cfg_v1::Counter,
+    p4_cfg_v1::Meter,
+    p4_cfg_v1::Register,
+    p4_cfg_v1::ValueSet,
+    p4_cfg_v1::Digest
+);
+
+/// Find a P4Info object by id
+fn find<'a, T: HasPreamble>(objects: &'a [T], id: u32, kind: &str) -> Result<&'a T, SimError> {
+    objects
+        .iter()
+        .find(|o| o.preamble().as_ref().map(|p| p.id) == Some(id))
+        .ok_or_else(|| SimError::UnknownId {
+            kind: kind.to_string(),
+            id,
+        })
+}
+
+fn name(preamble: &Option<p4_cfg_v1::Preamble>) -> String {
+    preamble
+        .as_ref()
+        .map(|p| p.name.clone())
+        .unwrap_or_default()
+}
+
+fn action_name(p4info: &p4_cfg_v1::P4Info, action_id: u32) -> String {
+    find(&p4info.actions, action_id, "action")
+        .map(|a| name(&a.preamble))
+        .unwrap_or_else(|_| action_id.to_string())
+}
+
+fn check_index(
+    preamble: &Option<p4_cfg_v1::Preamble>,
+    index: Option<p4_v1::Index>,
+    size: i64,
+) -> Result<(), SimError> {
+    match index {
+        Some(p4_v1::Index { index }) if index < 0 || index >= size => {
+            Err(SimError::IndexOutOfRange {
+                name: name(preamble),
+                index,
+                size,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_match(table: &p4_cfg_v1::Table, entry: &p4_v1::TableEntry) -> Result<(), SimError> {
+    use p4_cfg_v1::match_field::{Match, MatchType};
+    use p4_v1::field_match::FieldMatchType;
+
+    let table_name = || name(&table.preamble);
+    let match_type = |field: &p4_cfg_v1::MatchField| match field.r#match {
+        Some(Match::MatchType(t)) => MatchType::try_from(t).ok(),
+        _ => None,
+    };
+
+    for (i, field_match) in entry.r#match.iter().enumerate() {
+        let field_id = field_match.field_id;
+        let field = table
+            .match_fields
+            .iter()
+            .find(|f| f.id == field_id)
+            .ok_or_else(|| SimError::UnknownMatchField {
+                table: table_name(),
+                field_id,
+            })?;
+        if entry.r#match[..i].iter().any(|m| m.field_id == field_id) {
+            return Err(SimError::DuplicateMatchField {
+                table: table_name(),
+                field_id,
+            });
+        }
+
+        let matches_type = matches!(
+            (&field_match.field_match_type, match_type(field)),
+            (Some(FieldMatchType::Exact(_)), Some(MatchType::Exact))
+                | (Some(FieldMatchType::Lpm(_)), Some(MatchType::Lpm))
+                | (Some(FieldMatchType::Ternary(_)), Some(MatchType::Ternary))
+                | (Some(FieldMatchType::Range(_)), Some(MatchType::Range))
+                | (Some(FieldMatchType::Optional(_)), Some(MatchType::Optional))
+                | (Some(FieldMatchType::Other(_)), None)
+        );
+        if !matches_type {
+            return Err(SimError::MatchTypeMismatch {
+                table: table_name(),
+                field_id,
+            });
+        }
+    }
+
+    for field in &table.match_fields {
+        if match_type(field) == Some(MatchType::Exact)
+            && !entry.r#match.iter().any(|m| m.field_id == field.id)
+        {
+            return Err(SimError::MissingExactMatch {
+                table: table_name(),
+                field_id: field.id,
+            });
+        }
+    }
+
+    let needs_priority = table.match_fields.iter().any(|f| {
+        matches!(
+            match_type(f),
+            Some(MatchType::Ternary | MatchType::Range | MatchType::Optional)
+        )
+    });
+    match (needs_priority, entry.priority) {
+        (true, p) if p <= 0 => Err(SimError::MissingPriority {
+            table: table_name(),
+        }),
+        (false, p) if p != 0 => Err(SimError::UnexpectedPriority {
+            table: table_name(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn check_action_params(p4info: &p4_cfg_v1::P4Info, action: &p4_v1::Action) -> Result<(), SimError> {
+    let info = find(&p4info.actions, action.action_id, "action")?;
+
+    for param in &action.params {
+        if !info.params.iter().any(|p| p.id == param.param_id) {
+            return Err(SimError::UnknownActionParam {
+                action: name(&info.preamble),
+                param_id: param.param_id,
+            });
+        }
+    }
+    if action.params.len() != info.params.len() {
+        return Err(SimError::ActionParamCount {
+            action: name(&info.preamble),
+            expected: info.params.len(),
+            found: action.params.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Kind of the entities which always exist on a target and can only be
+/// modified
+fn modify_only_kind(entity: &p4_v1::Entity) -> Option<&'static str> {
+    use p4_v1::entity::Entity;
+
+    match entity.entity.as_ref()? {
+        Entity::TableEntry(e) if e.is_default_action => Some("Default table"),
+        Entity::MeterEntry(_) => Some("Meter"),
+        Entity::DirectMeterEntry(_) => Some("Direct meter"),
+        Entity::CounterEntry(_) => Some("Counter"),
+        Entity::DirectCounterEntry(_) => Some("Direct counter"),
+        Entity::RegisterEntry(_) => Some("Register"),
+        Entity::ValueSetEntry(_) => Some("Value set"),
+        _ => None,
+    }
+}
+
+/// Key of a table entry, ignoring its action and data
+fn table_entry_key(entry: &p4_v1::TableEntry) -> p4_v1::TableEntry {
+    let mut r#match = entry.r#match.clone();
+    r#match.sort_by_key(|m| m.field_id);
+
+    p4_v1::TableEntry {
+        table_id: entry.table_id,
+        r#match,
+        priority: entry.priority,
+        is_default_action: entry.is_default_action,
+
+        ..Default::default()
+    }
+}
+
+/// Key identifying an entity in the store
+///
+/// Only the fields which identify the entity are kept, so that entities
+/// differing in their data have the same key.
+fn entity_key(entity: &p4_v1::Entity) -> Option<Vec<u8>> {
+    use p4_v1::entity::Entity;
+    use p4_v1::packet_replication_engine_entry::Type;
+
+    let key = match entity.entity.as_ref()? {
+        Entity::ExternEntry(e) => Entity::ExternEntry(p4_v1::ExternEntry {
+            extern_type_id: e.extern_type_id,
+            extern_id: e.extern_id,
+            ..Default::default()
+        }),
+        Entity::TableEntry(e) => Entity::TableEntry(table_entry_key(e)),
+        Entity::ActionProfileMember(e) => Entity::ActionProfileMember(p4_v1::ActionProfileMember {
+            action_profile_id: e.action_profile_id,
+            member_id: e.member_id,
+            ..Default::default()
+        }),
+        Entity::ActionProfileGroup(e) => Entity::ActionProfileGroup(p4_v1::ActionProfileGroup {
+            action_profile_id: e.action_profile_id,
+            group_id: e.group_id,
+            ..Default::default()
+        }),
+        Entity::MeterEntry(e) => Entity::MeterEntry(p4_v1::MeterEntry {
+            meter_id: e.meter_id,
+            index: e.index,
+            ..Default::default()
+        }),
+        Entity::DirectMeterEntry(e) => Entity::DirectMeterEntry(p4_v1::DirectMeterEntry {
+            table_entry: e.table_entry.as_ref().map(table_entry_key),
+            ..Default::default()
+        }),
+        Entity::CounterEntry(e) => Entity::CounterEntry(p4_v1::CounterEntry {
+            counter_id: e.counter_id,
+            index: e.index,
+            ..Default::default()
+        }),
+        Entity::DirectCounterEntry(e) => Entity::DirectCounterEntry(p4_v1::DirectCounterEntry {
+            table_entry: e.table_entry.as_ref().map(table_entry_key),
+            ..Default::default()
+        }),
+        Entity::PacketReplicationEngineEntry(e) => {
+            let r#type = match e.r#type.as_ref()? {
+                Type::MulticastGroupEntry(m) => {
+                    Type::MulticastGroupEntry(p4_v1::MulticastGroupEntry {
+                        multicast_group_id: m.multicast_group_id,
+                        ..Default::default()
+                    })
+                }
+                Type::CloneSessionEntry(c) => Type::CloneSessionEntry(p4_v1::CloneSessionEntry {
+                    session_id: c.session_id,
+                    ..Default::default()
+                }),
+            };
+            Entity::PacketReplicationEngineEntry(p4_v1::PacketReplicationEngineEntry {
+                r#type: Some(r#type),
+            })
+        }
+        Entity::ValueSetEntry(e) => Entity::ValueSetEntry(p4_v1::ValueSetEntry {
+            value_set_id: e.value_set_id,
+            ..Default::default()
+        }),
+        Entity::RegisterEntry(e) => Entity::RegisterEntry(p4_v1::RegisterEntry {
+            register_id: e.register_id,
+            index: e.index,
+            ..Default::default()
+        }),
+        Entity::DigestEntry(e) => Entity::DigestEntry(p4_v1::DigestEntry {
+            digest_id: e.digest_id,
+            ..Default::default()
+        }),
+    };
+
+    Some(prost::Message::encode_to_vec(&p4_v1::Entity {
+        entity: Some(key),
+    }))
+}
+
+/// Whether a stored entity matches a read request entity
+fn entity_matches(filter: &p4_v1::Entity, entity: &p4_v1::Entity) -> bool {
+    use p4_v1::entity::Entity;
+    use p4_v1::packet_replication_engine_entry::Type;
+
+    fn id(filter: u32, id: u32) -> bool {
+        filter == 0 || filter == id
+    }
+
+    fn index(filter: &Option<p4_v1::Index>, index: &Option<p4_v1::Index>) -> bool {
+        filter.is_none() || filter == index
+    }
+
+    fn table_entry(filter: &p4_v1::TableEntry, entry: &p4_v1::TableEntry) -> bool {
+        if !id(filter.table_id, entry.table_id) {
+            return false;
+        }
+        if filter.is_default_action {
+            return entry.is_default_action;
+        }
+        if filter.r#match.is_empty() {
+            return !entry.is_default_action;
+        }
+
+        table_entry_key(filter) == table_entry_key(entry)
+    }
+
+    fn direct(filter: &Option<p4_v1::TableEntry>, entry: &Option<p4_v1::TableEntry>) -> bool {
+        match (filter, entry) {
+            (None, _) => true,
+            (Some(filter), Some(entry)) => table_entry(filter, entry),
+            (Some(_), None) => false,
+        }
+    }
+
+    match (&filter.entity, &entity.entity) {
+        (Some(Entity::ExternEntry(f)), Some(Entity::ExternEntry(e))) => {
+            id(f.extern_type_id, e.extern_type_id) && id(f.extern_id, e.extern_id)
+        }
+        (Some(Entity::TableEntry(f)), Some(Entity::TableEntry(e))) => table_entry(f, e),
+        (Some(Entity::ActionProfileMember(f)), Some(Entity::ActionProfileMember(e))) => {
+            id(f.action_profile_id, e.action_profile_id) && id(f.member_id, e.member_id)
+        }
+        (Some(Entity::ActionProfileGroup(f)), Some(Entity::ActionProfileGroup(e))) => {
+            id(f.action_profile_id, e.action_profile_id) && id(f.group_id, e.group_id)
+        }
+        (Some(Entity::MeterEntry(f)), Some(Entity::MeterEntry(e))) => {
+            id(f.meter_id, e.meter_id) && index(&f.index, &e.index)
+        }
+        (Some(Entity::DirectMeterEntry(f)), Some(Entity::DirectMeterEntry(e))) => {
+            direct(&f.table_entry, &e.table_entry)
+        }
+        (Some(Entity::CounterEntry(f)), Some(Entity::CounterEntry(e))) => {
+            id(f.counter_id, e.counter_id) && index(&f.index, &e.index)
+        }
+        (Some(Entity::DirectCounterEntry(f)), Some(Entity::DirectCounterEntry(e))) => {
+            direct(&f.table_entry, &e.table_entry)
+        }
+        (
+            Some(Entity::PacketReplicationEngineEntry(f)),
+            Some(Entity::PacketReplicationEngineEntry(e)),
+        ) => match (&f.r#type, &e.r#type) {
+            (Some(Type::MulticastGroupEntry(f)), Some(Type::MulticastGroupEntry(e))) => {
+                id(f.multicast_group_id, e.multicast_group_id)
+            }
+            (Some(Type::CloneSessionEntry(f)), Some(Type::CloneSessionEntry(e))) => {
+                id(f.session_id, e.session_id)
+            }
+            _ => false,
+        },
+        (Some(Entity::ValueSetEntry(f)), Some(Entity::ValueSetEntry(e))) => {
+            id(f.value_set_id, e.value_set_id)
+        }
+        (Some(Entity::RegisterEntry(f)), Some(Entity::RegisterEntry(e))) => {
+            id(f.register_id, e.register_id) && index(&f.index, &e.index)
+        }
+        (Some(Entity::DigestEntry(f)), Some(Entity::DigestEntry(e))) => {
+            id(f.digest_id, e.digest_id)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p4_cfg_v1::match_field::MatchType;
+
+    use super::*;
+    use crate::test_utils::{match_field, param, preamble};
+
+    fn p4info() -> P4Info {
+        P4Info::new(p4_cfg_v1::P4Info {
+            tables: vec![p4_cfg_v1::Table {
+                preamble: preamble(1, "acl", ""),
+                match_fields: vec![
+                    match_field(1, "dst", 8, MatchType::Exact),
+                    match_field(2, "src", 8, MatchType::Ternary),
+                ],
+                action_refs: vec![
+                    p4_cfg_v1::ActionRef {
+                        id: 10,
+                        scope: p4_cfg_v1::action_ref::Scope::TableOnly as i32,
+                        ..Default::default()
+                    },
+                    p4_cfg_v1::ActionRef {
+                        id: 11,
+                        ..Default::default()
+                    },
+                ],
+                size: 1,
+                ..Default::default()
+            }],
+            actions: vec![
+                p4_cfg_v1::Action {
+                    preamble: preamble(10, "forward", ""),
+                    params: vec![param(1, "port", 9)],
+                },
+                p4_cfg_v1::Action {
+                    preamble: preamble(11, "drop", ""),
+                    params: vec![],
+                },
+            ],
+            ..Default::default()
+        })
+    }
+
+    fn entry(dst: u8, priority: i32, action_id: u32, params: usize) -> p4_v1::TableEntry {
+        p4_v1::TableEntry {
+            table_id: 1,
+            r#match: vec![p4_v1::FieldMatch {
+                field_id: 1,
+                field_match_type: Some(p4_v1::field_match::FieldMatchType::Exact(
+                    p4_v1::field_match::Exact { value: vec![dst] },
+                )),
+            }],
+            action: Some(p4_v1::TableAction {
+                r#type: Some(p4_v1::table_action::Type::Action(p4_v1::Action {
+                    action_id,
+                    params: (0..params)
+                        .map(|i| p4_v1::action::Param {
+                            param_id: i as u32 + 1,
+                            value: vec![1],
+                        })
+                        .collect(),
+                })),
+            }),
+            priority,
+            ..Default::default()
+        }
+    }
+
+    fn update(r#type: p4_v1::update::Type, entry: p4_v1::TableEntry) -> p4_v1::Update {
+        p4_v1::Update {
+            r#type: r#type as i32,
+            entity: Some(p4_v1::Entity {
+                entity: Some(p4_v1::entity::Entity::TableEntry(entry)),
+            }),
+        }
+    }
+
+    #[test]
+    fn table_entry_semantics() {
+        use p4_v1::update::Type;
+
+        let mut store = EntityStore::new(p4info());
+
+        assert!(matches!(
+            store.apply(&update(Type::Insert, entry(1, 0, 10, 1))),
+            Err(SimError::MissingPriority { .. })
+        ));
+        assert!(matches!(
+            store.apply(&update(Type::Insert, entry(1, 1, 10, 0))),
+            Err(SimError::ActionParamCount { .. })
+        ));
+        store
+            .apply(&update(Type::Insert, entry(1, 1, 10, 1)))
+            .unwrap();
+        assert_eq!(
+            store
+                .apply(&update(Type::Insert, entry(1, 1, 11, 0)))
+                .unwrap_err()
+                .code(),
+            tonic::Code::AlreadyExists
+        );
+        assert_eq!(
+            store
+                .apply(&update(Type::Insert, entry(2, 1, 11, 0)))
+                .unwrap_err()
+                .code(),
+            tonic::Code::ResourceExhausted
+        );
+        assert!(matches!(
+            store.apply(&update(Type::Modify, entry(2, 1, 11, 0))),
+            Err(SimError::NotFound)
+        ));
+
+        let default_entry = p4_v1::TableEntry {
+            r#match: vec![],
+            is_default_action: true,
+            ..entry(0, 0, 10, 1)
+        };
+        assert!(matches!(
+            store.apply(&update(Type::Modify, default_entry.clone())),
+            Err(SimError::TableOnlyAction { .. })
+        ));
+        assert!(matches!(
+            store.apply(&update(Type::Insert, default_entry)),
+            Err(SimError::ModifyOnly { .. })
+        ));
+
+        assert!(store
+            .apply(&update(Type::Delete, entry(1, 1, 0, 0)))
+            .is_ok());
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn target_resources() {
+        use p4_v1::entity::Entity;
+        use p4_v1::update::Type;
+
+        let mut p4info: p4_cfg_v1::P4Info = p4info().as_ref().clone();
+        p4info.tables.push(p4_cfg_v1::Table {
+            preamble: preamble(2, "routes", ""),
+            match_fields: vec![match_field(1, "dst", 8, MatchType::Exact)],
+            action_refs: vec![p4_cfg_v1::ActionRef {
+                id: 11,
+                ..Default::default()
+            }],
+            const_default_action_id: 11,
+            is_const_table: true,
+            ..Default::default()
+        });
+        p4info.action_profiles.push(p4_cfg_v1::ActionProfile {
+            preamble: preamble(20, "selector", ""),
+            table_ids: vec![1],
+            with_selector: true,
+            size: 8,
+            ..Default::default()
+        });
+        p4info.counters.push(p4_cfg_v1::Counter {
+            preamble: preamble(30, "counter", ""),
+            size: 4,
+            ..Default::default()
+        });
+        p4info.meters.push(p4_cfg_v1::Meter {
+            preamble: preamble(31, "meter", ""),
+            size: 4,
+            ..Default::default()
+        });
+        p4info.registers.push(p4_cfg_v1::Register {
+            preamble: preamble(32, "register", ""),
+            size: 4,
+            ..Default::default()
+        });
+        let mut store = EntityStore::new(P4Info::new(p4info));
+        let apply = |store: &mut EntityStore, r#type: Type, entity: Entity| {
+            store
+                .apply(&p4_v1::Update {
+                    r#type: r#type as i32,
+                    entity: Some(p4_v1::Entity {
+                        entity: Some(entity),
+                    }),
+                })
+                .map_err(|e| e.code())
+        };
+
+        let route = p4_v1::TableEntry {
+            table_id: 2,
+            ..entry(1, 0, 11, 0)
+        };
+        assert_eq!(
+            apply(&mut store, Type::Insert, Entity::TableEntry(route.clone())),
+            Err(tonic::Code::PermissionDenied)
+        );
+        let default_route = p4_v1::TableEntry {
+            r#match: vec![],
+            is_default_action: true,
+            ..route
+        };
+        assert_eq!(
+            apply(&mut store, Type::Modify, Entity::TableEntry(default_route)),
+            Err(tonic::Code::PermissionDenied)
+        );
+
+        let counter = |index| p4_v1::CounterEntry {
+            counter_id: 30,
+            index: Some(p4_v1::Index { index }),
+            ..Default::default()
+        };
+        assert_eq!(
+            apply(&mut store, Type::Insert, Entity::CounterEntry(counter(0))),
+            Err(tonic::Code::InvalidArgument)
+        );
+        assert_eq!(
+            apply(&mut store, Type::Modify, Entity::CounterEntry(counter(3))),
+            Ok(())
+        );
+        assert_eq!(
+            apply(&mut store, Type::Modify, Entity::CounterEntry(counter(4))),
+            Err(tonic::Code::OutOfRange)
+        );
+        let meter = |index| p4_v1::MeterEntry {
+            meter_id: 31,
+            index: Some(p4_v1::Index { index }),
+            ..Default::default()
+        };
+        assert_eq!(
+            apply(&mut store, Type::Delete, Entity::MeterEntry(meter(0))),
+            Err(tonic::Code::InvalidArgument)
+        );
+        assert_eq!(
+            apply(&mut store, Type::Modify, Entity::MeterEntry(meter(-1))),
+            Err(tonic::Code::OutOfRange)
+        );
+        let register = p4_v1::RegisterEntry {
+            register_id: 32,
+            index: Some(p4_v1::Index { index: 4 }),
+            ..Default::default()
+        };
+        assert_eq!(
+            apply(&mut store, Type::Modify, Entity::RegisterEntry(register)),
+            Err(tonic::Code::OutOfRange)
+        );
+
+        let default_acl = p4_v1::TableEntry {
+            r#match: vec![],
+            is_default_action: true,
+            ..entry(0, 0, 11, 0)
+        };
+        let direct_counter = |table_entry| p4_v1::DirectCounterEntry {
+            table_entry: Some(table_entry),
+            ..Default::default()
+        };
+        assert_eq!(
+            apply(
+                &mut store,
+                Type::Modify,
+                Entity::DirectCounterEntry(direct_counter(default_acl))
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            apply(
+                &mut store,
+                Type::Modify,
+                Entity::DirectCounterEntry(direct_counter(entry(1, 1, 11, 0)))
+            ),
+            Err(tonic::Code::NotFound)
+        );
+
+        let group = p4_v1::ActionProfileGroup {
+            action_profile_id: 20,
+            group_id: 1,
+            members: vec![p4_v1::action_profile_group::Member {
+                member_id: 1,
+                weight: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            apply(
+                &mut store,
+                Type::Insert,
+                Entity::ActionProfileGroup(group.clone())
+            ),
+            Err(tonic::Code::NotFound)
+        );
+        let member = p4_v1::ActionProfileMember {
+            action_profile_id: 20,
+            member_id: 1,
+            action: Some(p4_v1::Action {
+                action_id: 11,
+                params: vec![],
+            }),
+        };
+        assert_eq!(
+            apply(
+                &mut store,
+                Type::Insert,
+                Entity::ActionProfileMember(member)
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            apply(&mut store, Type::Insert, Entity::ActionProfileGroup(group)),
+            Ok(())
+        );
+    }
+}
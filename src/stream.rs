@@ -0,0 +1,274 @@
+//! Stream channel handlers and dispatch
+//!
+//! Messages received on the P4Runtime stream channel are dispatched to every
+//! registered [`StreamHandler`]. How a message reaches a handler is decided by
+//! its [`DispatchPolicy`]: inline on the stream task, or through a bounded
+//! queue drained by a dedicated task.
+
+use std::sync::Arc;
+
+use log::{debug, warn};
+use p4_v1::stream_message_response::Update;
+use p4runtime::p4::v1 as p4_v1;
+use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
+
+pub mod correlation;
+pub mod extension;
+
+/// Handler for messages received on the stream channel
+///
+/// All methods have empty default implementations, so a handler only needs to
+/// implement the messages it is interested in.
+pub trait StreamHandler: Send + Sync + 'static {
+    /// Called on a master arbitration update
+    fn on_arbitration(&self, _arbitration: &p4_v1::MasterArbitrationUpdate) {}
+
+    /// Called on a packet in message
+    fn on_packet_in(&self, _packet: &p4_v1::PacketIn) {}
+
+    /// Called on a digest list
+    fn on_digest(&self, _digest: &p4_v1::DigestList) {}
+
+    /// Called on an idle timeout notification
+    fn on_idle_timeout(&self, _notification: &p4_v1::IdleTimeoutNotification) {}
+
+    /// Called on a stream error
+    fn on_error(&self, _error: &p4_v1::StreamError) {}
+
+    /// Called on an architecture-specific message
+    fn on_other(&self, _other: &pbjson_types::Any) {}
+}
+
+/// How messages are delivered to a handler
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DispatchPolicy {
+    /// Call the handler directly on the stream task
+    ///
+    /// The handler must return quickly, otherwise it delays every other
+    /// handler and the stream channel itself.
+    #[default]
+    Inline,
+
+    /// Queue messages for the handler, waiting for room when the queue is full
+    ///
+    /// A slow handler back-pressures the stream channel.
+    Block {
+        /// Queue capacity
+        capacity: usize,
+    },
+
+    /// Queue messages for the handler, dropping the incoming message when the
+    /// queue is full
+    DropNewest {
+        /// Queue capacity
+        capacity: usize,
+    },
+
+    /// Queue messages for the handler, dropping the oldest queued message when
+    /// the queue is full
+    DropOldest {
+        /// Queue capacity
+        capacity: usize,
+    },
+}
+
+/// Registered stream handlers and their dispatch policies
+#[derive(Clone, Default)]
+pub struct StreamHandlers {
+    entries: Vec<(Arc<dyn StreamHandler>, DispatchPolicy)>,
+}
+
+impl std::fmt::Debug for StreamHandlers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.entries.iter().map(|(_, policy)| policy))
+            .finish()
+    }
+}
+
+impl StreamHandlers {
+    /// Register a handler with the given dispatch policy
+    pub fn register(&mut self, handler: Arc<dyn StreamHandler>, policy: DispatchPolicy) {
+        self.entries.push((handler, policy));
+    }
+
+    /// Iterate over registered handlers and their policies
+    pub fn iter(&self) -> impl Iterator<Item = &(Arc<dyn StreamHandler>, DispatchPolicy)> {
+        self.entries.iter()
+    }
+
+    /// Number of registered handlers
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no handler is registered
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Deliver a stream update to a handler
+fn deliver(handler: &dyn StreamHandler, update: &Update) {
+    match update {
+        Update::Arbitration(arb) => handler.on_arbitration(arb),
+        Update::Packet(packet) => handler.on_packet_in(packet),
+        Update::Digest(digest) => handler.on_digest(digest),
+        Update::IdleTimeoutNotification(idle_timeout) => handler.on_idle_timeout(idle_timeout),
+        Update::Error(error) => handler.on_error(error),
+        Update::Other(other) => handler.on_other(other),
+    }
+}
+
+enum Target {
+    Inline(Arc<dyn StreamHandler>),
+    Block(mpsc::Sender<Arc<Update>>),
+    DropNewest(mpsc::Sender<Arc<Update>>),
+    DropOldest(broadcast::Sender<Arc<Update>>),
+}
+
+/// Dispatcher owned by the stream task
+///
+/// Queued handlers are drained by their own tasks, which stop when the
+/// cancel token is cancelled or the dispatcher is dropped.
+pub(crate) struct Dispatcher {
+    targets: Vec<Target>,
+}
+
+impl Dispatcher {
+    /// Create a dispatcher and spawn the tasks of queued handlers
+    pub(crate) fn new(handlers: &StreamHandlers, cancel_token: &CancellationToken) -> Self {
+        let targets = handlers
+            .entries
+            .iter()
+            .map(|(handler, policy)| {
+                let handler = handler.clone();
+                match *policy {
+                    DispatchPolicy::Inline => Target::Inline(handler),
+                    DispatchPolicy::Block { capacity } => {
+                        let (tx, rx) = mpsc::channel(capacity.max(1));
+                        spawn_queue(handler, rx, cancel_token.clone());
+                        Target::Block(tx)
+                    }
+                    DispatchPolicy::DropNewest { capacity } => {
+                        let (tx, rx) = mpsc::channel(capacity.max(1));
+                        spawn_queue(handler, rx, cancel_token.clone());
+                        Target::DropNewest(tx)
+                    }
+                    DispatchPolicy::DropOldest { capacity } => {
+                        let (tx, rx) = broadcast::channel(capacity.max(1));
+                        spawn_ring(handler, rx, cancel_token.clone());
+                        Target::DropOldest(tx)
+                    }
+                }
+            })
+            .collect();
+
+        Dispatcher { targets }
+    }
+
+    /// Dispatch an update to every handler
+    pub(crate) async fn dispatch(&self, update: Update) {
+        let update = Arc::new(update);
+
+        for target in self.targets.iter() {
+            match target {
+                Target::Inline(handler) => deliver(handler.as_ref(), &update),
+                Target::Block(tx) => {
+                    if tx.send(update.clone()).await.is_err() {
+                        debug!("Stream handler queue closed");
+                    }
+                }
+                Target::DropNewest(tx) => match tx.try_send(update.clone()) {
+                    Ok(()) => {}
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        warn!("Stream handler queue full, dropping message");
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => {
+                        debug!("Stream handler queue closed");
+                    }
+                },
+                Target::DropOldest(tx) => {
+                    // Only fails when the handler task has exited
+                    let _ = tx.send(update.clone());
+                }
+            }
+        }
+    }
+}
+
+fn spawn_queue(
+    handler: Arc<dyn StreamHandler>,
+    mut rx: mpsc::Receiver<Arc<Update>>,
+    cancel_token: CancellationToken,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+
+                update = rx.recv() => match update {
+                    Some(update) => deliver(handler.as_ref(), &update),
+                    None => break,
+                }
+            }
+        }
+    });
+}
+
+fn spawn_ring(
+    handler: Arc<dyn StreamHandler>,
+    mut rx: broadcast::Receiver<Arc<Update>>,
+    cancel_token: CancellationToken,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+
+                update = rx.recv() => match update {
+                    Ok(update) => deliver(handler.as_ref(), &update),
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("Stream handler lagged, dropped {} oldest messages", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    });
+}
+
+/// Built-in handler feeding the client's broadcast channels
+///
+/// Sending never fails loudly: a message without subscribers is dropped, and
+/// lagging subscribers observe the loss when they next receive.
+pub(crate) struct BroadcastHandler {
+    pub(crate) arbitration_tx: broadcast::Sender<p4_v1::MasterArbitrationUpdate>,
+    pub(crate) packet_tx: broadcast::Sender<p4_v1::PacketIn>,
+    pub(crate) digest_tx: broadcast::Sender<p4_v1::DigestList>,
+    pub(crate) idle_timeout_tx: broadcast::Sender<p4_v1::IdleTimeoutNotification>,
+    pub(crate) error_tx: broadcast::Sender<p4_v1::StreamError>,
+}
+
+impl StreamHandler for BroadcastHandler {
+    fn on_arbitration(&self, arbitration: &p4_v1::MasterArbitrationUpdate) {
+        let _ = self.arbitration_tx.send(arbitration.clone());
+    }
+
+    fn on_packet_in(&self, packet: &p4_v1::PacketIn) {
+        let _ = self.packet_tx.send(packet.clone());
+    }
+
+    fn on_digest(&self, digest: &p4_v1::DigestList) {
+        let _ = self.digest_tx.send(digest.clone());
+    }
+
+    fn on_idle_timeout(&self, notification: &p4_v1::IdleTimeoutNotification) {
+        let _ = self.idle_timeout_tx.send(notification.clone());
+    }
+
+    fn on_error(&self, error: &p4_v1::StreamError) {
+        let _ = self.error_tx.send(error.clone());
+    }
+}
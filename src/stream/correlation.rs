@@ -0,0 +1,276 @@
+//! Correlation of stream errors with the requests that caused them
+//!
+//! P4Runtime reports failures of `PacketOut`, `DigestListAck` and extension
+//! messages asynchronously as a `StreamError` carrying a copy of the offending message.
+//! The client remembers recently sent requests in a bounded ring and matches
+//! incoming errors against it.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use p4runtime::p4::v1 as p4_v1;
+use tokio::sync::oneshot;
+
+use super::StreamHandler;
+
+/// A stream request which may be reported back in a `StreamError`
+#[derive(Debug)]
+enum TrackedRequest {
+    PacketOut(p4_v1::PacketOut),
+    DigestAck(p4_v1::DigestListAck),
+    Other(pbjson_types::Any),
+}
+
+#[derive(Debug)]
+struct Pending {
+    request: TrackedRequest,
+    sent_at: Instant,
+    tx: oneshot::Sender<p4_v1::StreamError>,
+}
+
+/// Bounded ring of recently sent stream requests
+#[derive(Debug)]
+pub(crate) struct ErrorCorrelator {
+    pending: Mutex<VecDeque<Pending>>,
+    capacity: usize,
+    window: Duration,
+}
+
+impl ErrorCorrelator {
+    /// Create a correlator remembering at most `capacity` requests for `window`
+    pub(crate) fn new(capacity: usize, window: Duration) -> Self {
+        ErrorCorrelator {
+            pending: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            window,
+        }
+    }
+
+    /// Remember a request about to be sent
+    ///
+    /// Requests which can't be reported in a `StreamError` are not tracked and
+    /// resolve immediately.
+    pub(crate) fn track(&self, request: &p4_v1::StreamMessageRequest) -> StreamOutcome {
+        use p4_v1::stream_message_request::Update;
+
+        let request = match &request.update {
+            Some(Update::Packet(packet)) => TrackedRequest::PacketOut(packet.clone()),
+            Some(Update::DigestAck(ack)) => TrackedRequest::DigestAck(*ack),
+            Some(Update::Other(other)) => TrackedRequest::Other(other.clone()),
+            _ => return StreamOutcome::ready(),
+        };
+
+        if self.capacity == 0 {
+            return StreamOutcome::ready();
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let now = Instant::now();
+
+        let mut pending = self.pending.lock().unwrap();
+        while pending
+            .front()
+            .is_some_and(|p| now.duration_since(p.sent_at) > self.window)
+        {
+            pending.pop_front();
+        }
+        if pending.len() >= self.capacity {
+            pending.pop_front();
+        }
+        pending.push_back(Pending {
+            request,
+            sent_at: now,
+            tx,
+        });
+
+        StreamOutcome {
+            rx: Some(rx),
+            deadline: tokio::time::Instant::from_std(now + self.window),
+            sleep: None,
+        }
+    }
+
+    /// Forget requests whose [`StreamOutcome`] was dropped
+    pub(crate) fn prune(&self) {
+        self.pending.lock().unwrap().retain(|p| !p.tx.is_closed());
+    }
+
+    /// Resolve the oldest pending request matching the error
+    ///
+    /// Requests whose outcome was dropped or whose window elapsed are
+    /// forgotten first, so that they don't take the error of a newer identical
+    /// request. Returns `true` if a request was found.
+    pub(crate) fn resolve(&self, error: &p4_v1::StreamError) -> bool {
+        use p4_v1::stream_error::Details;
+
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|p| !p.tx.is_closed() && now.duration_since(p.sent_at) <= self.window);
+        let position = pending
+            .iter()
+            .position(|p| match (&error.details, &p.request) {
+                (Some(Details::PacketOut(e)), TrackedRequest::PacketOut(packet)) => {
+                    e.packet_out.as_ref() == Some(packet)
+                }
+                (Some(Details::DigestListAck(e)), TrackedRequest::DigestAck(ack)) => {
+                    e.digest_list_ack.as_ref() == Some(ack)
+                }
+                (Some(Details::Other(e)), TrackedRequest::Other(other)) => {
+                    e.other.as_ref() == Some(other)
+                }
+                _ => false,
+            });
+
+        match position.and_then(|i| pending.remove(i)) {
+            Some(p) => {
+                // The caller may have dropped the outcome already
+                let _ = p.tx.send(error.clone());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl StreamHandler for ErrorCorrelator {
+    fn on_error(&self, error: &p4_v1::StreamError) {
+        self.resolve(error);
+    }
+}
+
+/// Outcome of a stream request
+///
+/// Resolves to `Err` with the `StreamError` reported by the server for this
+/// request, or to `Ok(())` if none is reported within the correlation window.
+/// The error is delivered to the error subscribers either way.
+///
+/// Requests evicted from the correlation ring before an error arrives also
+/// resolve to `Ok(())`.
+#[derive(Debug)]
+pub struct StreamOutcome {
+    rx: Option<oneshot::Receiver<p4_v1::StreamError>>,
+    deadline: tokio::time::Instant,
+    sleep: Option<Pin<Box<tokio::time::Sleep>>>,
+}
+
+impl StreamOutcome {
+    pub(crate) fn ready() -> Self {
+        StreamOutcome {
+            rx: None,
+            deadline: tokio::time::Instant::now(),
+            sleep: None,
+        }
+    }
+}
+
+impl Future for StreamOutcome {
+    type Output = Result<(), p4_v1::StreamError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Some(rx) = self.rx.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+
+        match Pin::new(rx).poll(cx) {
+            Poll::Ready(Ok(error)) => return Poll::Ready(Err(error)),
+            Poll::Ready(Err(_)) => return Poll::Ready(Ok(())),
+            Poll::Pending => {}
+        }
+
+        let deadline = self.deadline;
+        let sleep = self
+            .sleep
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+        match sleep.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Ok(())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet_out(payload: &[u8]) -> p4_v1::StreamMessageRequest {
+        p4_v1::StreamMessageRequest {
+            update: Some(p4_v1::stream_message_request::Update::Packet(
+                p4_v1::PacketOut {
+                    payload: payload.to_vec(),
+                    metadata: vec![],
+                },
+            )),
+        }
+    }
+
+    fn packet_out_error(payload: &[u8]) -> p4_v1::StreamError {
+        p4_v1::StreamError {
+            canonical_code: 3,
+            details: Some(p4_v1::stream_error::Details::PacketOut(
+                p4_v1::PacketOutError {
+                    packet_out: Some(p4_v1::PacketOut {
+                        payload: payload.to_vec(),
+                        metadata: vec![],
+                    }),
+                },
+            )),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolve_matching_packet_out() {
+        let correlator = ErrorCorrelator::new(4, Duration::from_secs(60));
+        let _a = correlator.track(&packet_out(&[1]));
+        let _b = correlator.track(&packet_out(&[2]));
+
+        assert!(correlator.resolve(&packet_out_error(&[2])));
+        assert!(!correlator.resolve(&packet_out_error(&[2])));
+        assert!(!correlator.resolve(&packet_out_error(&[3])));
+        assert!(correlator.resolve(&packet_out_error(&[1])));
+    }
+
+    #[test]
+    fn evict_oldest_when_full() {
+        let correlator = ErrorCorrelator::new(1, Duration::from_secs(60));
+        let _a = correlator.track(&packet_out(&[1]));
+        let _b = correlator.track(&packet_out(&[2]));
+
+        assert!(!correlator.resolve(&packet_out_error(&[1])));
+        assert!(correlator.resolve(&packet_out_error(&[2])));
+    }
+
+    #[test]
+    fn prune_dropped_outcomes() {
+        let correlator = ErrorCorrelator::new(4, Duration::from_secs(60));
+        let a = correlator.track(&packet_out(&[1]));
+        let _b = correlator.track(&packet_out(&[2]));
+        drop(a);
+        correlator.prune();
+
+        assert!(!correlator.resolve(&packet_out_error(&[1])));
+        assert!(correlator.resolve(&packet_out_error(&[2])));
+    }
+
+    #[tokio::test]
+    async fn skip_dropped_and_expired_identical_requests() {
+        let correlator = ErrorCorrelator::new(4, Duration::from_secs(60));
+        drop(correlator.track(&packet_out(&[1])));
+        let awaited = correlator.track(&packet_out(&[1]));
+
+        assert!(correlator.resolve(&packet_out_error(&[1])));
+        assert_eq!(awaited.await, Err(packet_out_error(&[1])));
+
+        let correlator = ErrorCorrelator::new(4, Duration::ZERO);
+        let _expired = correlator.track(&packet_out(&[1]));
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert!(!correlator.resolve(&packet_out_error(&[1])));
+    }
+}
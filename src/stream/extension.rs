@@ -0,0 +1,160 @@
+//! Architecture-specific stream messages
+//!
+//! P4Runtime carries vendor-specific stream messages as `google.protobuf.Any`
+//! in the `other` field of `StreamMessageRequest` and `StreamMessageResponse`.
+//! The [`ExtensionRegistry`] dispatches received messages by their type URL.
+
+use std::{collections::HashMap, sync::Arc};
+
+use log::warn;
+
+use super::StreamHandler;
+use crate::error::ExtensionError;
+
+type RawHandler = Arc<dyn Fn(&pbjson_types::Any) + Send + Sync>;
+
+/// Registry of extension message handlers keyed by `Any` type URL
+#[derive(Clone, Default)]
+pub struct ExtensionRegistry {
+    handlers: HashMap<String, RawHandler>,
+}
+
+impl std::fmt::Debug for ExtensionRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.handlers.keys()).finish()
+    }
+}
+
+impl ExtensionRegistry {
+    /// Register a handler receiving messages decoded as `M`
+    ///
+    /// Messages which fail to decode are logged and dropped. Registering a
+    /// type URL again replaces the previous handler.
+    pub fn register<M, F>(&mut self, type_url: impl Into<String>, handler: F)
+    where
+        M: prost::Message + Default,
+        F: Fn(M) + Send + Sync + 'static,
+    {
+        self.register_raw(type_url, move |any| match decode_any::<M>(any) {
+            Ok(message) => handler(message),
+            Err(e) => warn!("Failed to decode extension message {}: {}", any.type_url, e),
+        });
+    }
+
+    /// Register a handler receiving the raw `Any` message
+    pub fn register_raw<F>(&mut self, type_url: impl Into<String>, handler: F)
+    where
+        F: Fn(&pbjson_types::Any) + Send + Sync + 'static,
+    {
+        self.handlers.insert(type_url.into(), Arc::new(handler));
+    }
+
+    /// Remove the handler of a type URL
+    pub fn unregister(&mut self, type_url: &str) -> bool {
+        self.handlers.remove(type_url).is_some()
+    }
+
+    /// Whether a handler is registered for a type URL
+    pub fn contains(&self, type_url: &str) -> bool {
+        self.handlers.contains_key(type_url)
+    }
+
+    /// Dispatch a message to the handler of its type URL
+    ///
+    /// Returns `false` if no handler is registered.
+    pub fn dispatch(&self, any: &pbjson_types::Any) -> bool {
+        match self.handlers.get(&any.type_url) {
+            Some(handler) => {
+                handler(any);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl StreamHandler for ExtensionRegistry {
+    fn on_other(&self, other: &pbjson_types::Any) {
+        if !self.dispatch(other) {
+            warn!("Received unsupported stream message update: {:?}", other);
+        }
+    }
+}
+
+/// Pack a message into an `Any` with the given type URL
+pub fn encode_any<M: prost::Message>(
+    type_url: impl Into<String>,
+    message: &M,
+) -> pbjson_types::Any {
+    pbjson_types::Any {
+        type_url: type_url.into(),
+        value: message.encode_to_vec().into(),
+    }
+}
+
+/// Unpack a message from an `Any`, ignoring its type URL
+pub fn decode_any<M: prost::Message + Default>(
+    any: &pbjson_types::Any,
+) -> Result<M, ExtensionError> {
+    Ok(M::decode(&any.value[..])?)
+}
+
+/// Unpack a message from an `Any`, checking its type URL
+pub fn decode_any_checked<M: prost::Message + Default>(
+    any: &pbjson_types::Any,
+    type_url: &str,
+) -> Result<M, ExtensionError> {
+    if any.type_url != type_url {
+        return Err(ExtensionError::TypeUrlMismatch {
+            expected: type_url.to_string(),
+            found: any.type_url.clone(),
+        });
+    }
+
+    decode_any(any)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    const TYPE_URL: &str = "type.googleapis.com/p4.v1.DigestListAck";
+
+    #[test]
+    fn dispatch_by_type_url() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let mut registry = ExtensionRegistry::default();
+        let sink = received.clone();
+        registry.register(TYPE_URL, move |ack: p4runtime::p4::v1::DigestListAck| {
+            sink.lock().unwrap().push(ack.list_id);
+        });
+
+        let ack = p4runtime::p4::v1::DigestListAck {
+            digest_id: 1,
+            list_id: 42,
+        };
+        assert!(registry.dispatch(&encode_any(TYPE_URL, &ack)));
+        assert!(!registry.dispatch(&encode_any("type.googleapis.com/unknown", &ack)));
+        assert_eq!(*received.lock().unwrap(), vec![42]);
+    }
+
+    #[test]
+    fn decode_checks_type_url() {
+        let ack = p4runtime::p4::v1::DigestListAck {
+            digest_id: 1,
+            list_id: 42,
+        };
+        let any = encode_any(TYPE_URL, &ack);
+
+        let decoded: p4runtime::p4::v1::DigestListAck = decode_any_checked(&any, TYPE_URL).unwrap();
+        assert_eq!(decoded, ack);
+        assert!(decode_any_checked::<p4runtime::p4::v1::DigestListAck>(
+            &any,
+            "type.googleapis.com/x"
+        )
+        .is_err());
+    }
+}
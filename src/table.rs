@@ -1,12 +1,22 @@
 //! Table helper and operations
 
-use std::borrow::{Borrow, BorrowMut};
+use std::borrow::Borrow;
 
 use p4runtime::p4::v1 as p4_v1;
 
-use crate::{client::Client, error::ClientError};
+use crate::{
+    client::Client,
+    error::{ClientError, ReconcileError},
+};
+
+pub mod builder;
+pub mod reconcile;
 
 /// Wrapper for table operations
+///
+/// The wrapper borrows or owns a client, so `Table::new(client.clone())` can
+/// be moved into a task.
+#[derive(Clone)]
 pub struct Table<T>
 where
     T: Borrow<Client>,
@@ -49,6 +59,14 @@ impl<T: Borrow<Client>> Table<T> {
         }
     }
 
+    /// Start building a table entry with typed match values
+    ///
+    /// See [`builder::EntryBuilder`].
+    pub fn entry(&self, table_name: &str) -> builder::EntryBuilder<'_> {
+        let client: &Client = self.client.borrow();
+        builder::EntryBuilder::new(client.p4info(), table_name)
+    }
+
     /// Create a new table entry by table name, match fields, action, and priority
     pub fn new_entry(
         &self,
@@ -78,19 +96,17 @@ impl<T: Borrow<Client>> Table<T> {
             ..Default::default()
         }
     }
-}
 
-impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
     /// Read a table entry
     pub async fn read_entry(
-        &mut self,
+        &self,
         table_entry: p4_v1::TableEntry,
     ) -> Result<p4_v1::TableEntry, ClientError> {
         let entity = p4_v1::Entity {
             entity: Some(p4_v1::entity::Entity::TableEntry(table_entry)),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         let entity = client.read_entity_single(entity).await?;
 
         if let Some(p4_v1::entity::Entity::TableEntry(table_entry)) = entity.entity {
@@ -102,14 +118,14 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
 
     /// Read table entries
     pub async fn read_entries(
-        &mut self,
+        &self,
         table_entry: p4_v1::TableEntry,
     ) -> Result<Vec<p4_v1::TableEntry>, ClientError> {
         let entity = p4_v1::Entity {
             entity: Some(p4_v1::entity::Entity::TableEntry(table_entry)),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         let entities = client.read_entities(entity).await?;
 
         let entries = entities
@@ -128,7 +144,7 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
 
     /// Insert a table entry
     pub async fn insert_entry(
-        &mut self,
+        &self,
         table_entry: p4_v1::TableEntry,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let update = p4_v1::Update {
@@ -138,13 +154,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
             }),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update(update).await
     }
 
     /// Insert table entries
     pub async fn insert_entries(
-        &mut self,
+        &self,
         table_entries: Vec<p4_v1::TableEntry>,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let updates = table_entries
@@ -157,13 +173,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
             })
             .collect();
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update_batch(updates).await
     }
 
     /// Modify a table entry
     pub async fn modify_entry(
-        &mut self,
+        &self,
         table_entry: p4_v1::TableEntry,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let update = p4_v1::Update {
@@ -173,13 +189,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
             }),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update(update).await
     }
 
     /// Modify table entries
     pub async fn modify_entries(
-        &mut self,
+        &self,
         table_entries: Vec<p4_v1::TableEntry>,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let updates = table_entries
@@ -192,13 +208,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
             })
             .collect();
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update_batch(updates).await
     }
 
     /// Delete a table entry
     pub async fn delete_entry(
-        &mut self,
+        &self,
         table_entry: p4_v1::TableEntry,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let update = p4_v1::Update {
@@ -208,13 +224,13 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
             }),
         };
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update(update).await
     }
 
     /// Delete table entries
     pub async fn delete_entries(
-        &mut self,
+        &self,
         table_entries: Vec<p4_v1::TableEntry>,
     ) -> Result<tonic::Response<p4_v1::WriteResponse>, ClientError> {
         let updates = table_entries
@@ -227,7 +243,58 @@ impl<T: Borrow<Client> + BorrowMut<Client>> Table<T> {
             })
             .collect();
 
-        let client: &mut Client = self.client.borrow_mut();
+        let client: &Client = self.client.borrow();
         client.write_update_batch(updates).await
     }
+
+    /// Compute the changes turning the installed entries of a table into the
+    /// desired ones, without writing them
+    ///
+    /// See [`reconcile::diff_entries`].
+    pub async fn plan_reconcile(
+        &self,
+        table_id: u32,
+        desired: Vec<p4_v1::TableEntry>,
+    ) -> Result<reconcile::TableDiff, ClientError> {
+        if let Some((index, entry)) = desired
+            .iter()
+            .enumerate()
+            .find(|(_, e)| e.table_id != table_id)
+        {
+            return Err(ReconcileError::ForeignTable {
+                index,
+                table_id: entry.table_id,
+            }
+            .into());
+        }
+
+        let installed = self
+            .read_entries(p4_v1::TableEntry {
+                table_id,
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(reconcile::diff_entries(installed, desired)?)
+    }
+
+    /// Make the entries of a table the desired ones
+    ///
+    /// The installed entries are read, and the missing, changed and
+    /// unwanted ones are inserted, modified and deleted in one batch. Returns
+    /// the applied changes.
+    pub async fn reconcile(
+        &self,
+        table_id: u32,
+        desired: Vec<p4_v1::TableEntry>,
+    ) -> Result<reconcile::TableDiff, ClientError> {
+        let diff = self.plan_reconcile(table_id, desired).await?;
+
+        if !diff.is_empty() {
+            let client: &Client = self.client.borrow();
+            client.write_update_batch(diff.to_updates()).await?;
+        }
+
+        Ok(diff)
+    }
 }
@@ -0,0 +1,426 @@
+//! Typed builder of table entries
+//!
+//! [`EntryBuilder`] resolves the table, match fields and action by name
+//! through the P4Info, and encodes common Rust types as canonical bitstrings:
+//! unsigned integers, `bool`, [`Ipv4Addr`], [`Ipv6Addr`], byte arrays such as
+//! MAC addresses, and CIDR prefixes from [`ipnet`]. Using a match kind other
+//! than the one of the field is an error, and values are checked by
+//! [`Table::validate_entry`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use p4runtime_client::{client::Client, ipnet::Ipv4Net, table::builder::Bitstring};
+//! # fn example(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+//! let entry = client
+//!     .table()
+//!     .entry("MyIngress.ipv4_lpm")
+//!     .lpm("hdr.ipv4.dstAddr", "10.0.1.0/24".parse::<Ipv4Net>()?)
+//!     .action(
+//!         "MyIngress.ipv4_forward",
+//!         vec![[0x08, 0, 0, 0, 0x01, 0x11].to_bitstring(), 1u16.to_bitstring()],
+//!     )
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+use p4runtime::p4::v1 as p4_v1;
+
+use crate::{
+    error::EntryBuilderError,
+    p4info::{table::Table, P4Info},
+    utils::canonicalize_unsigned_bitstring,
+};
+
+/// A value encodable as a P4Runtime bitstring
+pub trait Bitstring {
+    /// Encode the value as a canonical bitstring
+    fn to_bitstring(&self) -> Vec<u8>;
+}
+
+macro_rules! impl_bitstring_for_uint {
+    ($($t:ty),*) => {
+        $(
+            impl Bitstring for $t {
+                fn to_bitstring(&self) -> Vec<u8> {
+                    canonicalize_unsigned_bitstring(self.to_be_bytes().to_vec())
+                }
+            }
+        )*
+    };
+}
+
+impl_bitstring_for_uint!(u8, u16, u32, u64, u128);
+
+impl Bitstring for bool {
+    fn to_bitstring(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+}
+
+impl Bitstring for Ipv4Addr {
+    fn to_bitstring(&self) -> Vec<u8> {
+        canonicalize_unsigned_bitstring(self.octets().to_vec())
+    }
+}
+
+impl Bitstring for Ipv6Addr {
+    fn to_bitstring(&self) -> Vec<u8> {
+        canonicalize_unsigned_bitstring(self.octets().to_vec())
+    }
+}
+
+impl Bitstring for IpAddr {
+    fn to_bitstring(&self) -> Vec<u8> {
+        match self {
+            IpAddr::V4(addr) => addr.to_bitstring(),
+            IpAddr::V6(addr) => addr.to_bitstring(),
+        }
+    }
+}
+
+impl Bitstring for [u8] {
+    fn to_bitstring(&self) -> Vec<u8> {
+        canonicalize_unsigned_bitstring(self.to_vec())
+    }
+}
+
+impl<const N: usize> Bitstring for [u8; N] {
+    fn to_bitstring(&self) -> Vec<u8> {
+        self[..].to_bitstring()
+    }
+}
+
+impl Bitstring for Vec<u8> {
+    fn to_bitstring(&self) -> Vec<u8> {
+        self[..].to_bitstring()
+    }
+}
+
+impl<T: Bitstring + ?Sized> Bitstring for &T {
+    fn to_bitstring(&self) -> Vec<u8> {
+        (**self).to_bitstring()
+    }
+}
+
+/// A value and prefix length of an LPM match
+pub trait Prefix {
+    /// Get the value as a canonical bitstring and the prefix length
+    fn to_prefix(&self) -> (Vec<u8>, u32);
+}
+
+impl Prefix for Ipv4Net {
+    fn to_prefix(&self) -> (Vec<u8>, u32) {
+        (self.addr().to_bitstring(), self.prefix_len() as u32)
+    }
+}
+
+impl Prefix for Ipv6Net {
+    fn to_prefix(&self) -> (Vec<u8>, u32) {
+        (self.addr().to_bitstring(), self.prefix_len() as u32)
+    }
+}
+
+impl Prefix for IpNet {
+    fn to_prefix(&self) -> (Vec<u8>, u32) {
+        (self.addr().to_bitstring(), self.prefix_len() as u32)
+    }
+}
+
+impl<T: Bitstring> Prefix for (T, u32) {
+    fn to_prefix(&self) -> (Vec<u8>, u32) {
+        (self.0.to_bitstring(), self.1)
+    }
+}
+
+/// Builder of a table entry
+///
+/// Errors are kept until [`build`](EntryBuilder::build), so calls can be
+/// chained. The first error is returned.
+#[derive(Debug)]
+pub struct EntryBuilder<'a> {
+    table: Option<Table<'a>>,
+    entry: p4_v1::TableEntry,
+    normalize: bool,
+    error: Option<EntryBuilderError>,
+}
+
+impl<'a> EntryBuilder<'a> {
+    /// Start building an entry of a table
+    pub fn new(p4info: &'a P4Info, table_name: &str) -> Self {
+        let table = p4info.get_table(table_name);
+        let error = table.is_none().then(|| EntryBuilderError::UnknownTable {
+            table_name: table_name.to_string(),
+        });
+
+        EntryBuilder {
+            entry: p4_v1::TableEntry {
+                table_id: table.as_ref().map(|t| t.id()).unwrap_or_default(),
+                ..Default::default()
+            },
+            table,
+            normalize: false,
+            error,
+        }
+    }
+
+    /// Add an exact match
+    pub fn exact(self, field_name: &str, value: impl Bitstring) -> Self {
+        use p4_cfg_v1::match_field::MatchType;
+        use p4_v1::field_match::{Exact, FieldMatchType};
+
+        self.field_match(field_name, MatchType::Exact, || {
+            FieldMatchType::Exact(Exact {
+                value: value.to_bitstring(),
+            })
+        })
+    }
+
+    /// Add an LPM match
+    pub fn lpm(self, field_name: &str, prefix: impl Prefix) -> Self {
+        use p4_cfg_v1::match_field::MatchType;
+        use p4_v1::field_match::{FieldMatchType, Lpm};
+
+        self.field_match(field_name, MatchType::Lpm, || {
+            let (value, prefix_len) = prefix.to_prefix();
+            FieldMatchType::Lpm(Lpm {
+                value,
+                prefix_len: prefix_len.try_into().unwrap_or(-1),
+            })
+        })
+    }
+
+    /// Add a ternary match
+    pub fn ternary(self, field_name: &str, value: impl Bitstring, mask: impl Bitstring) -> Self {
+        use p4_cfg_v1::match_field::MatchType;
+        use p4_v1::field_match::{FieldMatchType, Ternary};
+
+        self.field_match(field_name, MatchType::Ternary, || {
+            FieldMatchType::Ternary(Ternary {
+                value: value.to_bitstring(),
+                mask: mask.to_bitstring(),
+            })
+        })
+    }
+
+    /// Add a range match
+    pub fn range(self, field_name: &str, low: impl Bitstring, high: impl Bitstring) -> Self {
+        use p4_cfg_v1::match_field::MatchType;
+        use p4_v1::field_match::{FieldMatchType, Range};
+
+        self.field_match(field_name, MatchType::Range, || {
+            FieldMatchType::Range(Range {
+                low: low.to_bitstring(),
+                high: high.to_bitstring(),
+            })
+        })
+    }
+
+    /// Add an optional match
+    pub fn optional(self, field_name: &str, value: impl Bitstring) -> Self {
+        use p4_cfg_v1::match_field::MatchType;
+        use p4_v1::field_match::{FieldMatchType, Optional};
+
+        self.field_match(field_name, MatchType::Optional, || {
+            FieldMatchType::Optional(Optional {
+                value: value.to_bitstring(),
+            })
+        })
+    }
+
+    /// Set the action by name and parameters in P4Info order
+    pub fn action(mut self, action_name: &str, params: Vec<Vec<u8>>) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+
+        if let Some(table) = &self.table {
+            match table.make_action(action_name, params) {
+                Ok(action) => self.entry.action = Some(action),
+                Err(e) => self.error = Some(e.into()),
+            }
+        }
+        self
+    }
+
+    /// Set a prebuilt action, e.g. an action profile member
+    pub fn table_action(mut self, action: p4_v1::TableAction) -> Self {
+        self.entry.action = Some(action);
+        self
+    }
+
+    /// Set the priority
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.entry.priority = priority;
+        self
+    }
+
+    /// Normalize match values instead of rejecting them
+    ///
+    /// See [`Table::normalize_entry`].
+    pub fn normalize(mut self) -> Self {
+        self.normalize = true;
+        self
+    }
+
+    /// Build the entry
+    pub fn build(self) -> Result<p4_v1::TableEntry, EntryBuilderError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let Some(table) = &self.table else {
+            return Ok(self.entry);
+        };
+
+        if self.normalize {
+            Ok(table.normalize_entry(self.entry)?)
+        } else {
+            table.validate_entry(&self.entry)?;
+            Ok(self.entry)
+        }
+    }
+
+    fn field_match(
+        mut self,
+        field_name: &str,
+        kind: p4_cfg_v1::match_field::MatchType,
+        make: impl FnOnce() -> p4_v1::field_match::FieldMatchType,
+    ) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        let Some(table) = &self.table else {
+            return self;
+        };
+
+        let result = find_field(table, field_name).and_then(|field| {
+            if self.entry.r#match.iter().any(|m| m.field_id == field.id) {
+                return Err(EntryBuilderError::DuplicateField {
+                    field_name: field_name.to_string(),
+                });
+            }
+
+            let expected = match field.r#match.as_ref() {
+                Some(p4_cfg_v1::match_field::Match::MatchType(mt)) => {
+                    p4_cfg_v1::match_field::MatchType::try_from(*mt)
+                        .unwrap_or_default()
+                        .as_str_name()
+                        .to_string()
+                }
+                Some(p4_cfg_v1::match_field::Match::OtherMatchType(other)) => other.clone(),
+                None => String::new(),
+            };
+            if expected != kind.as_str_name() {
+                return Err(EntryBuilderError::MatchKindMismatch {
+                    field_name: field_name.to_string(),
+                    expected,
+                    found: kind.as_str_name().to_string(),
+                });
+            }
+
+            Ok(p4_v1::FieldMatch {
+                field_id: field.id,
+                field_match_type: Some(make()),
+            })
+        });
+
+        match result {
+            Ok(field_match) => self.entry.r#match.push(field_match),
+            Err(e) => self.error = Some(e),
+        }
+        self
+    }
+}
+
+fn find_field<'t>(
+    table: &'t Table<'_>,
+    field_name: &str,
+) -> Result<&'t p4_cfg_v1::MatchField, EntryBuilderError> {
+    table
+        .match_fields
+        .iter()
+        .find(|f| f.name == field_name)
+        .ok_or_else(|| EntryBuilderError::UnknownField {
+            table_name: table
+                .preamble
+                .as_ref()
+                .map(|p| p.name.clone())
+                .unwrap_or_default(),
+            field_name: field_name.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use p4_cfg_v1::match_field::MatchType;
+
+    use super::*;
+    use crate::test_utils::{match_field, preamble};
+
+    fn p4info() -> P4Info {
+        P4Info::new(p4_cfg_v1::P4Info {
+            tables: vec![p4_cfg_v1::Table {
+                preamble: preamble(1, "acl", ""),
+                match_fields: vec![
+                    match_field(1, "dst", 32, MatchType::Lpm),
+                    match_field(2, "port", 9, MatchType::Exact),
+                    match_field(3, "mac", 48, MatchType::Ternary),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn build_entry() {
+        let p4info = p4info();
+
+        let entry = EntryBuilder::new(&p4info, "acl")
+            .lpm("dst", "10.0.1.0/24".parse::<Ipv4Net>().unwrap())
+            .exact("port", 3u16)
+            .ternary("mac", [0, 0, 0, 0, 0, 1], [0xff; 6])
+            .priority(10)
+            .build()
+            .unwrap();
+        assert_eq!(entry.table_id, 1);
+        assert_eq!(entry.priority, 10);
+        assert_eq!(
+            entry.r#match[0].field_match_type,
+            Some(p4_v1::field_match::FieldMatchType::Lpm(
+                p4_v1::field_match::Lpm {
+                    value: vec![10, 0, 1, 0],
+                    prefix_len: 24,
+                }
+            ))
+        );
+        assert_eq!(
+            entry.r#match[1].field_match_type,
+            Some(p4_v1::field_match::FieldMatchType::Exact(
+                p4_v1::field_match::Exact { value: vec![3] }
+            ))
+        );
+
+        assert!(matches!(
+            EntryBuilder::new(&p4info, "acl").exact("dst", 1u8).build(),
+            Err(EntryBuilderError::MatchKindMismatch { .. })
+        ));
+        assert!(matches!(
+            EntryBuilder::new(&p4info, "acl")
+                .exact("port", 512u16)
+                .build(),
+            Err(EntryBuilderError::ValueTooWide { bitwidth: 9, .. })
+        ));
+        assert!(matches!(
+            EntryBuilder::new(&p4info, "acl")
+                .lpm("dst", "2001:db8::/32".parse::<Ipv6Net>().unwrap())
+                .build(),
+            Err(EntryBuilderError::ValueTooWide { .. })
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+//! Desired-state reconciliation of tables
+//!
+//! Given the full desired set of entries of a table, [`diff_entries`]
+//! computes the inserts, modifies and deletes turning the installed entries
+//! into the desired ones. Entries are identified by an [`EntryKey`] built from
+//! their match fields and priority, with bitstrings canonicalized by
+//! [`canonicalize_unsigned_bitstring`], so equivalent encodings compare equal.
+
+use std::collections::BTreeMap;
+
+use p4runtime::p4::v1 as p4_v1;
+use prost::Message;
+
+use crate::{error::ReconcileError, utils::canonicalize_unsigned_bitstring};
+
+/// Key identifying a table entry
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntryKey(Vec<u8>);
+
+/// Compute the key of a table entry
+///
+/// The key covers the table id, the match fields in field id order and the
+/// priority.
+pub fn entry_key(entry: &p4_v1::TableEntry) -> EntryKey {
+    let mut r#match = entry
+        .r#match
+        .iter()
+        .map(canonicalize_field_match)
+        .collect::<Vec<_>>();
+    r#match.sort_by_key(|m| m.field_id);
+
+    let key = p4_v1::TableEntry {
+        table_id: entry.table_id,
+        r#match,
+        priority: entry.priority,
+
+        ..Default::default()
+    };
+
+    EntryKey(key.encode_to_vec())
+}
+
+fn canonicalize_field_match(field_match: &p4_v1::FieldMatch) -> p4_v1::FieldMatch {
+    use p4_v1::field_match::{Exact, FieldMatchType, Lpm, Optional, Range, Ternary};
+
+    let field_match_type = field_match.field_match_type.as_ref().map(|m| match m {
+        FieldMatchType::Exact(m) => FieldMatchType::Exact(Exact {
+            value: canonicalize_unsigned_bitstring(m.value.clone()),
+        }),
+        FieldMatchType::Ternary(m) => FieldMatchType::Ternary(Ternary {
+            value: canonicalize_unsigned_bitstring(m.value.clone()),
+            mask: canonicalize_unsigned_bitstring(m.mask.clone()),
+        }),
+        FieldMatchType::Lpm(m) => FieldMatchType::Lpm(Lpm {
+            value: canonicalize_unsigned_bitstring(m.value.clone()),
+            prefix_len: m.prefix_len,
+        }),
+        FieldMatchType::Range(m) => FieldMatchType::Range(Range {
+            low: canonicalize_unsigned_bitstring(m.low.clone()),
+            high: canonicalize_unsigned_bitstring(m.high.clone()),
+        }),
+        FieldMatchType::Optional(m) => FieldMatchType::Optional(Optional {
+            value: canonicalize_unsigned_bitstring(m.value.clone()),
+        }),
+        FieldMatchType::Other(m) => FieldMatchType::Other(m.clone()),
+    });
+
+    p4_v1::FieldMatch {
+        field_id: field_match.field_id,
+        field_match_type,
+    }
+}
+
+fn canonicalize_action(action: &p4_v1::Action) -> p4_v1::Action {
+    let mut params = action
+        .params
+        .iter()
+        .map(|p| p4_v1::action::Param {
+            param_id: p.param_id,
+            value: canonicalize_unsigned_bitstring(p.value.clone()),
+        })
+        .collect::<Vec<_>>();
+    params.sort_by_key(|p| p.param_id);
+
+    p4_v1::Action {
+        action_id: action.action_id,
+        params,
+    }
+}
+
+/// The part of an entry a MODIFY can change, canonicalized
+#[allow(deprecated)]
+fn entry_value(entry: &p4_v1::TableEntry) -> p4_v1::TableEntry {
+    use p4_v1::table_action::Type;
+
+    let action = entry.action.as_ref().map(|a| p4_v1::TableAction {
+        r#type: a.r#type.as_ref().map(|t| match t {
+            Type::Action(action) => Type::Action(canonicalize_action(action)),
+            Type::ActionProfileActionSet(set) => {
+                Type::ActionProfileActionSet(p4_v1::ActionProfileActionSet {
+                    action_profile_actions: set
+                        .action_profile_actions
+                        .iter()
+                        .map(|a| p4_v1::ActionProfileAction {
+                            action: a.action.as_ref().map(canonicalize_action),
+                            ..a.clone()
+                        })
+                        .collect(),
+                })
+            }
+            t => t.clone(),
+        }),
+    });
+
+    p4_v1::TableEntry {
+        action,
+        controller_metadata: entry.controller_metadata,
+        idle_timeout_ns: entry.idle_timeout_ns,
+        metadata: entry.metadata.clone(),
+
+        ..Default::default()
+    }
+}
+
+/// Changes turning the installed entries of a table into the desired ones
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableDiff {
+    /// Desired entries which are not installed
+    pub insert: Vec<p4_v1::TableEntry>,
+    /// Desired entries installed with another action or metadata
+    pub modify: Vec<p4_v1::TableEntry>,
+    /// Installed entries which are not desired
+    pub delete: Vec<p4_v1::TableEntry>,
+}
+
+impl TableDiff {
+    /// Whether the installed entries already are the desired ones
+    pub fn is_empty(&self) -> bool {
+        self.insert.is_empty() && self.modify.is_empty() && self.delete.is_empty()
+    }
+
+    /// Number of updates
+    pub fn len(&self) -> usize {
+        self.insert.len() + self.modify.len() + self.delete.len()
+    }
+
+    /// Build the updates, deletes first so that they free room in the table
+    pub fn to_updates(&self) -> Vec<p4_v1::Update> {
+        use p4_v1::update::Type;
+
+        let update = |r#type: Type, entry: &p4_v1::TableEntry| p4_v1::Update {
+            r#type: r#type as i32,
+            entity: Some(p4_v1::Entity {
+                entity: Some(p4_v1::entity::Entity::TableEntry(entry.clone())),
+            }),
+        };
+
+        self.delete
+            .iter()
+            .map(|e| update(Type::Delete, e))
+            .chain(self.modify.iter().map(|e| update(Type::Modify, e)))
+            .chain(self.insert.iter().map(|e| update(Type::Insert, e)))
+            .collect()
+    }
+}
+
+/// Compute the changes turning the installed entries into the desired ones
+///
+/// Default entries can't be reconciled and desired entries must have unique
+/// keys. Installed const entries are left untouched.
+pub fn diff_entries(
+    installed: Vec<p4_v1::TableEntry>,
+    desired: Vec<p4_v1::TableEntry>,
+) -> Result<TableDiff, ReconcileError> {
+    let mut desired_by_key = BTreeMap::new();
+    for (index, entry) in desired.into_iter().enumerate() {
+        if entry.is_default_action {
+            return Err(ReconcileError::DefaultEntry { index });
+        }
+        if desired_by_key.insert(entry_key(&entry), entry).is_some() {
+            return Err(ReconcileError::DuplicateKey { index });
+        }
+    }
+
+    let mut diff = TableDiff::default();
+    for entry in installed {
+        if entry.is_default_action || entry.is_const {
+            continue;
+        }
+
+        match desired_by_key.remove(&entry_key(&entry)) {
+            Some(desired) if entry_value(&desired) != entry_value(&entry) => {
+                diff.modify.push(desired)
+            }
+            Some(_) => {}
+            None => diff.delete.push(entry),
+        }
+    }
+    diff.insert.extend(desired_by_key.into_values());
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(dst: Vec<u8>, port: Vec<u8>) -> p4_v1::TableEntry {
+        p4_v1::TableEntry {
+            table_id: 1,
+            r#match: vec![p4_v1::FieldMatch {
+                field_id: 1,
+                field_match_type: Some(p4_v1::field_match::FieldMatchType::Exact(
+                    p4_v1::field_match::Exact { value: dst },
+                )),
+            }],
+            action: Some(p4_v1::TableAction {
+                r#type: Some(p4_v1::table_action::Type::Action(p4_v1::Action {
+                    action_id: 10,
+                    params: vec![p4_v1::action::Param {
+                        param_id: 1,
+                        value: port,
+                    }],
+                })),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn minimal_diff() {
+        let installed = vec![
+            entry(vec![0x00, 0x01], vec![0x00, 0x01]),
+            entry(vec![0x02], vec![0x01]),
+            entry(vec![0x03], vec![0x01]),
+        ];
+        let desired = vec![
+            // Same entry, other encoding
+            entry(vec![0x01], vec![0x01]),
+            entry(vec![0x00, 0x02], vec![0x02]),
+            entry(vec![0x04], vec![0x01]),
+        ];
+
+        let diff = diff_entries(installed, desired).unwrap();
+        assert_eq!(diff.modify, vec![entry(vec![0x00, 0x02], vec![0x02])]);
+        assert_eq!(diff.delete, vec![entry(vec![0x03], vec![0x01])]);
+        assert_eq!(diff.insert, vec![entry(vec![0x04], vec![0x01])]);
+        assert_eq!(diff.to_updates().len(), 3);
+
+        // Leading 0xFF bytes are significant
+        assert_ne!(
+            entry_key(&entry(vec![0xff, 0x01], vec![])),
+            entry_key(&entry(vec![0x01], vec![]))
+        );
+
+        assert!(matches!(
+            diff_entries(
+                vec![],
+                vec![entry(vec![1], vec![1]), entry(vec![0, 1], vec![2])]
+            ),
+            Err(ReconcileError::DuplicateKey { index: 1 })
+        ));
+    }
+}
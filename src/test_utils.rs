@@ -0,0 +1,41 @@
+//! P4Info fixtures of the unit tests
+
+use p4runtime::p4::config::v1 as p4_cfg_v1;
+
+use p4_cfg_v1::match_field::{Match, MatchType};
+
+/// Preamble of a P4 object, an empty alias meaning none
+pub(crate) fn preamble(id: u32, name: &str, alias: &str) -> Option<p4_cfg_v1::Preamble> {
+    Some(p4_cfg_v1::Preamble {
+        id,
+        name: name.to_string(),
+        alias: alias.to_string(),
+        ..Default::default()
+    })
+}
+
+/// Match field of a table
+pub(crate) fn match_field(
+    id: u32,
+    name: &str,
+    bitwidth: i32,
+    match_type: MatchType,
+) -> p4_cfg_v1::MatchField {
+    p4_cfg_v1::MatchField {
+        id,
+        name: name.to_string(),
+        bitwidth,
+        r#match: Some(Match::MatchType(match_type as i32)),
+        ..Default::default()
+    }
+}
+
+/// Parameter of an action
+pub(crate) fn param(id: u32, name: &str, bitwidth: i32) -> p4_cfg_v1::action::Param {
+    p4_cfg_v1::action::Param {
+        id,
+        name: name.to_string(),
+        bitwidth,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,892 @@
+//! In-process P4Runtime server for tests
+//!
+//! [`MockServer`] implements the P4Runtime service in memory, so controller
+//! logic can be tested against a [`Client`](crate::client::Client) without a
+//! switch. It is served over an in-memory duplex pipe, no socket is opened.
+//!
+//! The server implements:
+//!
+//! - arbitration, one primary controller per role with the highest election id
+//!   among the open stream channels, re-elected when the primary disconnects
+//! - `SetForwardingPipelineConfig` and `GetForwardingPipelineConfig`
+//! - `Write` and `Read` against an entity store
+//! - scripted stream messages, e.g. [`send_packet_in`](MockServer::send_packet_in)
+//!
+//! Writes follow the atomicity of the request, like a switch would: with the
+//! default `CONTINUE_ON_ERROR`, failing updates don't stop the following ones,
+//! and with `ROLLBACK_ON_ERROR` or `DATAPLANE_ATOMIC` a failing update rejects
+//! the whole batch. A failed batch returns `UNKNOWN` with one `p4.v1.Error` per
+//! update in the status details. Entities are only validated against the
+//! P4Info with [`with_validation`](MockServer::with_validation).
+//!
+//! # Example
+//!
+//! ```rust
+//! # use p4runtime_client::{client::Client, testing::MockServer};
+//! # use p4runtime_client::p4runtime::p4::v1 as p4_v1;
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = MockServer::new();
+//!
+//! let mut client = Client::builder()
+//!     .device_id(1)
+//!     .election_id(p4_v1::Uint128 { high: 0, low: 1 })
+//!     .build()?;
+//! client.connect_with_channel(server.channel().await?);
+//! client.run().await?;
+//!
+//! server.send_packet_in(p4_v1::PacketIn {
+//!     payload: vec![0xde, 0xad],
+//!     ..Default::default()
+//! });
+//! let packet = client.get_packet_in(std::time::Duration::from_secs(1)).await?;
+//! assert_eq!(packet.payload, vec![0xde, 0xad]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use log::{debug, warn};
+use p4runtime::p4::v1::{
+    self as p4_v1,
+    p4_runtime_server::{P4Runtime, P4RuntimeServer},
+};
+use tokio::sync::{broadcast, mpsc};
+use tonic::{
+    transport::{Channel, Endpoint, Server, Uri},
+    Code, Request, Response, Status,
+};
+
+use crate::{error::SimError, p4info::P4Info, sim::EntityStore};
+
+/// P4Runtime API version reported by `Capabilities`
+const API_VERSION: &str = "1.4.0";
+
+/// Buffer size of the in-memory pipe between client and server
+const DUPLEX_BUFFER_SIZE: usize = 1 << 20;
+
+/// Buffer size of the stream channels
+const STREAM_BUFFER_SIZE: usize = 1024;
+
+/// In-memory P4Runtime server
+///
+/// Clones share the same state, so a test keeps one clone to script stream
+/// messages and inspect the device state while clients talk to another.
+#[derive(Clone, Debug)]
+pub struct MockServer {
+    state: Arc<Mutex<MockState>>,
+    stream_tx: broadcast::Sender<p4_v1::StreamMessageResponse>,
+}
+
+/// Sender of the responses of a stream channel
+type StreamSender = mpsc::Sender<Result<p4_v1::StreamMessageResponse, Status>>;
+
+#[derive(Debug, Default)]
+struct MockState {
+    /// Arbitrated controllers, by stream channel
+    controllers: HashMap<u64, Controller>,
+    next_stream_id: u64,
+    pipeline: Option<p4_v1::ForwardingPipelineConfig>,
+    saved_pipeline: Option<p4_v1::ForwardingPipelineConfig>,
+    store: EntityStore,
+    validate: bool,
+    packet_outs: Vec<p4_v1::PacketOut>,
+    digest_acks: Vec<p4_v1::DigestListAck>,
+}
+
+/// A controller which sent an arbitration update on its stream channel
+#[derive(Debug)]
+struct Controller {
+    arbitration: p4_v1::MasterArbitrationUpdate,
+    tx: StreamSender,
+}
+
+impl Controller {
+    fn role(&self) -> &str {
+        role_name(&self.arbitration)
+    }
+
+    fn election_id(&self) -> p4_v1::Uint128 {
+        self.arbitration.election_id.unwrap_or_default()
+    }
+}
+
+/// A request rejected by the mock server, with the per-update errors of a
+/// failed Write request
+#[derive(Debug)]
+struct Rejection(Code, String, Vec<p4_v1::Error>);
+
+impl Rejection {
+    fn new(code: Code, message: impl Into<String>) -> Self {
+        Rejection(code, message.into(), vec![])
+    }
+
+    fn write_failed(errors: Vec<p4_v1::Error>) -> Self {
+        Rejection(Code::Unknown, "write failed".to_string(), errors)
+    }
+}
+
+impl From<SimError> for Rejection {
+    fn from(error: SimError) -> Self {
+        Rejection::new(error.code(), error.to_string())
+    }
+}
+
+impl From<Rejection> for Status {
+    fn from(rejection: Rejection) -> Self {
+        use prost::Message;
+
+        let Rejection(code, message, errors) = rejection;
+        if errors.is_empty() {
+            return Status::new(code, message);
+        }
+
+        let details = p4runtime::google::rpc::Status {
+            code: code as i32,
+            message: message.clone(),
+            details: errors
+                .iter()
+                .map(|error| pbjson_types::Any {
+                    type_url: "type.googleapis.com/p4.v1.Error".to_string(),
+                    value: error.encode_to_vec().into(),
+                })
+                .collect(),
+        };
+        Status::with_details(code, message, details.encode_to_vec().into())
+    }
+}
+
+impl Default for MockServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockServer {
+    /// Create a server without pipeline and entities
+    pub fn new() -> Self {
+        let (stream_tx, _) = broadcast::channel(STREAM_BUFFER_SIZE);
+
+        MockServer {
+            state: Arc::new(Mutex::new(MockState::default())),
+            stream_tx,
+        }
+    }
+
+    /// Open a channel to the server
+    ///
+    /// Pass it to [`Client::connect_with_channel`](crate::client::Client::connect_with_channel).
+    pub async fn channel(&self) -> Result<Channel, tonic::transport::Error> {
+        let server = self.clone();
+
+        Endpoint::from_static("http://mock.p4runtime")
+            .connect_with_connector(tower::service_fn(move |_: Uri| {
+                let server = server.clone();
+                async move {
+                    let (client_io, server_io) = tokio::io::duplex(DUPLEX_BUFFER_SIZE);
+                    tokio::spawn(async move {
+                        let incoming = tokio_stream::once(Ok::<_, std::io::Error>(server_io));
+                        if let Err(e) = Server::builder()
+                            .add_service(P4RuntimeServer::new(server))
+                            .serve_with_incoming(incoming)
+                            .await
+                        {
+                            warn!("Mock server stopped: {}", e);
+                        }
+                    });
+
+                    Ok::<_, std::io::Error>(hyper_util::rt::TokioIo::new(client_io))
+                }
+            }))
+            .await
+    }
+
+    /// Check writes against the P4Info of the committed pipeline
+    ///
+    /// See [`EntityStore`] for the checks. Without validation, only the
+    /// existence of entities is checked. Stored entities are kept.
+    pub fn with_validation(self) -> Self {
+        let mut state = self.lock();
+        state.validate = true;
+        state.reload_store();
+        drop(state);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Install a forwarding pipeline config, as if it was committed by a
+    /// controller
+    ///
+    /// Stored entities are removed.
+    pub fn install_pipeline(&self, config: p4_v1::ForwardingPipelineConfig) {
+        let mut state = self.lock();
+        state.pipeline = Some(config);
+        state.reset_store();
+    }
+
+    /// Get the installed forwarding pipeline config
+    pub fn installed_pipeline(&self) -> Option<p4_v1::ForwardingPipelineConfig> {
+        self.lock().pipeline.clone()
+    }
+
+    /// Get the election id of the primary controller of a role
+    ///
+    /// The default role has an empty name.
+    pub fn primary_election_id(&self, role: &str) -> Option<p4_v1::Uint128> {
+        self.lock().primary(role)
+    }
+
+    /// Store entities, replacing existing ones with the same key
+    pub fn insert_entities(&self, entities: impl IntoIterator<Item = p4_v1::Entity>) {
+        let mut state = self.lock();
+        for entity in entities {
+            state.store.insert(entity);
+        }
+    }
+
+    /// Get all stored entities
+    pub fn entities(&self) -> Vec<p4_v1::Entity> {
+        self.lock().store.entities().cloned().collect()
+    }
+
+    /// Get the packets sent by controllers
+    pub fn packet_outs(&self) -> Vec<p4_v1::PacketOut> {
+        self.lock().packet_outs.clone()
+    }
+
+    /// Get the digest acknowledgements sent by controllers
+    pub fn digest_acks(&self) -> Vec<p4_v1::DigestListAck> {
+        self.lock().digest_acks.clone()
+    }
+
+    /// Send a message on all open stream channels
+    ///
+    /// Returns the number of stream channels the message was sent on. Messages
+    /// sent while no stream channel is open are dropped.
+    pub fn send_stream_message(&self, update: p4_v1::stream_message_response::Update) -> usize {
+        let res = p4_v1::StreamMessageResponse {
+            update: Some(update),
+        };
+
+        self.stream_tx.send(res).unwrap_or(0)
+    }
+
+    /// Send a packet in message on all open stream channels
+    pub fn send_packet_in(&self, packet: p4_v1::PacketIn) -> usize {
+        self.send_stream_message(p4_v1::stream_message_response::Update::Packet(packet))
+    }
+
+    /// Send a digest list on all open stream channels
+    pub fn send_digest(&self, digest: p4_v1::DigestList) -> usize {
+        self.send_stream_message(p4_v1::stream_message_response::Update::Digest(digest))
+    }
+
+    /// Send an idle timeout notification on all open stream channels
+    pub fn send_idle_timeout(&self, notification: p4_v1::IdleTimeoutNotification) -> usize {
+        self.send_stream_message(
+            p4_v1::stream_message_response::Update::IdleTimeoutNotification(notification),
+        )
+    }
+
+    /// Send a stream error on all open stream channels
+    pub fn send_stream_error(&self, error: p4_v1::StreamError) -> usize {
+        self.send_stream_message(p4_v1::stream_message_response::Update::Error(error))
+    }
+
+    /// Handle a request of a stream channel
+    ///
+    /// Arbitration updates are answered through the senders of the
+    /// controllers. An error terminates the stream channel.
+    fn handle_stream_request(
+        &self,
+        stream_id: u64,
+        tx: &StreamSender,
+        req: p4_v1::StreamMessageRequest,
+    ) -> Result<(), Rejection> {
+        use p4_v1::stream_message_request::Update;
+
+        let mut state = self.lock();
+        match req.update {
+            Some(Update::Arbitration(arbitration)) => {
+                state.arbitrate(stream_id, tx.clone(), arbitration)?
+            }
+            Some(Update::Packet(packet)) => state.packet_outs.push(packet),
+            Some(Update::DigestAck(ack)) => state.digest_acks.push(ack),
+            Some(Update::Other(any)) => {
+                debug!("Ignoring stream extension message {}", any.type_url)
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl MockState {
+    /// Election id of the primary controller of a role
+    fn primary(&self, role: &str) -> Option<p4_v1::Uint128> {
+        self.controllers
+            .values()
+            .filter(|c| c.role() == role)
+            .map(Controller::election_id)
+            .max_by_key(election_key)
+    }
+
+    /// Register the arbitration update of a stream channel
+    ///
+    /// Election ids are unique within a role, the controller with the highest
+    /// one is the primary. When the primary changes, all controllers of the
+    /// role are notified, otherwise only the sender.
+    fn arbitrate(
+        &mut self,
+        stream_id: u64,
+        tx: StreamSender,
+        arbitration: p4_v1::MasterArbitrationUpdate,
+    ) -> Result<(), Rejection> {
+        let role = role_name(&arbitration).to_string();
+        let election_id = arbitration.election_id.unwrap_or_default();
+
+        if self
+            .controllers
+            .iter()
+            .any(|(&id, c)| id != stream_id && c.role() == role && c.election_id() == election_id)
+        {
+            return Err(Rejection::new(
+                Code::InvalidArgument,
+                "election id is used by another controller of the role",
+            ));
+        }
+        if self
+            .controllers
+            .get(&stream_id)
+            .is_some_and(|c| c.role() != role)
+        {
+            self.disconnect(stream_id);
+        }
+
+        let primary = self.primary(&role);
+        self.controllers
+            .insert(stream_id, Controller { arbitration, tx });
+        if self.primary(&role) == primary {
+            self.notify(&role, Some(stream_id));
+        } else {
+            self.notify(&role, None);
+        }
+
+        Ok(())
+    }
+
+    /// Forget the controller of a closed stream channel, electing the next
+    /// primary if it was the primary
+    fn disconnect(&mut self, stream_id: u64) {
+        let Some(controller) = self.controllers.remove(&stream_id) else {
+            return;
+        };
+        let role = controller.role();
+        if self
+            .primary(role)
+            .is_none_or(|primary| election_key(&primary) < election_key(&controller.election_id()))
+        {
+            self.notify(role, None);
+        }
+    }
+
+    /// Send the arbitration result to the controllers of a role, or only to
+    /// one stream channel
+    fn notify(&self, role: &str, only: Option<u64>) {
+        let Some(primary) = self.primary(role) else {
+            return;
+        };
+
+        for (id, controller) in &self.controllers {
+            if controller.role() != role || only.is_some_and(|only| only != *id) {
+                continue;
+            }
+            let code = if controller.election_id() == primary {
+                Code::Ok
+            } else {
+                Code::AlreadyExists
+            };
+            let mut update = controller.arbitration.clone();
+            update.election_id = Some(primary);
+            update.status = Some(p4runtime::google::rpc::Status {
+                code: code as i32,
+                ..Default::default()
+            });
+
+            let res = p4_v1::StreamMessageResponse {
+                update: Some(p4_v1::stream_message_response::Update::Arbitration(update)),
+            };
+            if controller.tx.try_send(Ok(res)).is_err() {
+                warn!("Dropping an arbitration update of stream channel {}", id);
+            }
+        }
+    }
+
+    /// Check that a request comes from the primary controller of its role
+    fn check_primary(
+        &self,
+        role: &str,
+        election_id: Option<p4_v1::Uint128>,
+    ) -> Result<(), Rejection> {
+        match (self.primary(role), election_id) {
+            (Some(primary), Some(election_id)) if primary == election_id => Ok(()),
+            _ => Err(Rejection::new(
+                Code::PermissionDenied,
+                "not the primary controller",
+            )),
+        }
+    }
+
+    fn set_pipeline(
+        &mut self,
+        action: p4_v1::set_forwarding_pipeline_config_request::Action,
+        config: Option<p4_v1::ForwardingPipelineConfig>,
+    ) -> Result<(), Rejection> {
+        use p4_v1::set_forwarding_pipeline_config_request::Action;
+
+        let missing_config = || Rejection::new(Code::InvalidArgument, "missing config");
+        match action {
+            Action::Unspecified => {
+                return Err(Rejection::new(Code::InvalidArgument, "missing action"));
+            }
+            Action::Verify => {
+                config.ok_or_else(missing_config)?;
+            }
+            Action::VerifyAndSave => {
+                self.saved_pipeline = Some(config.ok_or_else(missing_config)?);
+            }
+            Action::VerifyAndCommit => {
+                self.pipeline = Some(config.ok_or_else(missing_config)?);
+                self.reset_store();
+            }
+            Action::Commit => {
+                let saved = self.saved_pipeline.take().ok_or_else(|| {
+                    Rejection::new(Code::FailedPrecondition, "no saved config to commit")
+                })?;
+                self.pipeline = Some(saved);
+                self.reset_store();
+            }
+            Action::ReconcileAndCommit => {
+                self.pipeline = Some(config.ok_or_else(missing_config)?);
+                self.reload_store();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply a batch of updates with the atomicity of the request
+    fn write(&mut self, req: &p4_v1::WriteRequest) -> Result<(), Rejection> {
+        use p4_v1::write_request::Atomicity;
+
+        if self.pipeline.is_none() {
+            return Err(Rejection::new(
+                Code::FailedPrecondition,
+                "no forwarding pipeline config set",
+            ));
+        }
+
+        let atomic = req.atomicity() != Atomicity::ContinueOnError;
+        let mut store = self.store.clone();
+        let results = store.write(&req.updates);
+        if results.iter().all(Result::is_ok) {
+            self.store = store;
+            return Ok(());
+        }
+        if !atomic {
+            self.store = store;
+        }
+
+        let errors = results
+            .into_iter()
+            .map(|result| match result {
+                Ok(()) if atomic => p4_v1::Error {
+                    canonical_code: Code::Aborted as i32,
+                    message: "batch rolled back".to_string(),
+                    ..Default::default()
+                },
+                Ok(()) => p4_v1::Error::default(),
+                Err(e) => p4_v1::Error {
+                    canonical_code: e.code() as i32,
+                    message: e.to_string(),
+                    ..Default::default()
+                },
+            })
+            .collect::<Vec<_>>();
+        Err(Rejection::write_failed(errors))
+    }
+
+    /// Replace the store, keeping its entities
+    fn reload_store(&mut self) {
+        let entities = std::mem::take(&mut self.store);
+        self.reset_store();
+        for entity in entities.entities() {
+            self.store.insert(entity.clone());
+        }
+    }
+
+    /// Replace the store after a pipeline change
+    fn reset_store(&mut self) {
+        let p4info = self.pipeline.as_ref().and_then(|p| p.p4info.clone());
+        self.store = match p4info {
+            Some(p4info) if self.validate => EntityStore::new(P4Info::new(p4info)),
+            _ => EntityStore::default(),
+        };
+    }
+}
+
+#[tonic::async_trait]
+impl P4Runtime for MockServer {
+    async fn write(
+        &self,
+        request: Request<p4_v1::WriteRequest>,
+    ) -> Result<Response<p4_v1::WriteResponse>, Status> {
+        let req = request.into_inner();
+
+        let mut state = self.lock();
+        state.check_primary(&req.role, req.election_id)?;
+        state.write(&req)?;
+
+        Ok(Response::new(p4_v1::WriteResponse {}))
+    }
+
+    type ReadStream = tokio_stream::Once<Result<p4_v1::ReadResponse, Status>>;
+
+    async fn read(
+        &self,
+        request: Request<p4_v1::ReadRequest>,
+    ) -> Result<Response<Self::ReadStream>, Status> {
+        let req = request.into_inner();
+        let entities = self.lock().store.read_batch(&req.entities);
+
+        Ok(Response::new(tokio_stream::once(Ok(p4_v1::ReadResponse {
+            entities,
+        }))))
+    }
+
+    async fn set_forwarding_pipeline_config(
+        &self,
+        request: Request<p4_v1::SetForwardingPipelineConfigRequest>,
+    ) -> Result<Response<p4_v1::SetForwardingPipelineConfigResponse>, Status> {
+        let req = request.into_inner();
+        let action = req.action();
+
+        let mut state = self.lock();
+        state.check_primary(&req.role, req.election_id)?;
+        state.set_pipeline(action, req.config)?;
+
+        Ok(Response::new(p4_v1::SetForwardingPipelineConfigResponse {}))
+    }
+
+    async fn get_forwarding_pipeline_config(
+        &self,
+        request: Request<p4_v1::GetForwardingPipelineConfigRequest>,
+    ) -> Result<Response<p4_v1::GetForwardingPipelineConfigResponse>, Status> {
+        use p4_v1::get_forwarding_pipeline_config_request::ResponseType;
+
+        let response_type = request.into_inner().response_type();
+        let mut config = self
+            .lock()
+            .pipeline
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("no forwarding pipeline config set"))?;
+
+        match response_type {
+            ResponseType::All => {}
+            ResponseType::CookieOnly => {
+                config.p4info = None;
+                config.p4_device_config.clear();
+            }
+            ResponseType::P4infoAndCookie => config.p4_device_config.clear(),
+            ResponseType::DeviceConfigAndCookie => config.p4info = None,
+        }
+
+        Ok(Response::new(p4_v1::GetForwardingPipelineConfigResponse {
+            config: Some(config),
+        }))
+    }
+
+    type StreamChannelStream =
+        tokio_stream::wrappers::ReceiverStream<Result<p4_v1::StreamMessageResponse, Status>>;
+
+    async fn stream_channel(
+        &self,
+        request: Request<tonic::Streaming<p4_v1::StreamMessageRequest>>,
+    ) -> Result<Response<Self::StreamChannelStream>, Status> {
+        let mut requests = request.into_inner();
+        let mut scripted = self.stream_tx.subscribe();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        let server = self.clone();
+        let stream_id = {
+            let mut state = self.lock();
+            state.next_stream_id += 1;
+            state.next_stream_id
+        };
+
+        tokio::spawn(async move {
+            loop {
+                let res = tokio::select! {
+                    req = requests.message() => match req {
+                        Ok(Some(req)) => match server.handle_stream_request(stream_id, &tx, req) {
+                            Ok(()) => continue,
+                            Err(rejection) => {
+                                let _ = tx.send(Err(rejection.into())).await;
+                                break;
+                            }
+                        },
+                        Ok(None) | Err(_) => break,
+                    },
+
+                    res = scripted.recv() => match res {
+                        Ok(res) => res,
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("Mock stream channel dropped {} scripted messages", n);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+
+                if tx.send(Ok(res)).await.is_err() {
+                    break;
+                }
+            }
+
+            server.lock().disconnect(stream_id);
+        });
+
+        Ok(Response::new(tokio_stream::wrappers::ReceiverStream::new(
+            rx,
+        )))
+    }
+
+    async fn capabilities(
+        &self,
+        _request: Request<p4_v1::CapabilitiesRequest>,
+    ) -> Result<Response<p4_v1::CapabilitiesResponse>, Status> {
+        Ok(Response::new(p4_v1::CapabilitiesResponse {
+            p4runtime_api_version: API_VERSION.to_string(),
+        }))
+    }
+}
+
+fn election_key(election_id: &p4_v1::Uint128) -> (u64, u64) {
+    (election_id.high, election_id.low)
+}
+
+fn role_name(arbitration: &p4_v1::MasterArbitrationUpdate) -> &str {
+    arbitration.role.as_ref().map_or("", |r| r.name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use p4_v1::p4_runtime_client::P4RuntimeClient;
+
+    use super::*;
+    use crate::{client::Client, error::ClientError};
+
+    fn table_entry(table_id: u32, value: u8) -> p4_v1::Entity {
+        p4_v1::Entity {
+            entity: Some(p4_v1::entity::Entity::TableEntry(p4_v1::TableEntry {
+                table_id,
+                r#match: vec![p4_v1::FieldMatch {
+                    field_id: 1,
+                    field_match_type: Some(p4_v1::field_match::FieldMatchType::Exact(
+                        p4_v1::field_match::Exact { value: vec![value] },
+                    )),
+                }],
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn insert(entity: p4_v1::Entity) -> p4_v1::Update {
+        p4_v1::Update {
+            r#type: p4_v1::update::Type::Insert as i32,
+            entity: Some(entity),
+        }
+    }
+
+    fn write_error_codes<T>(result: Result<T, ClientError>) -> Vec<i32> {
+        use prost::Message;
+
+        let Err(ClientError::Status(status)) = result else {
+            panic!("expected a failed write");
+        };
+        assert_eq!(status.code(), Code::Unknown);
+        p4runtime::google::rpc::Status::decode(status.details())
+            .unwrap()
+            .details
+            .iter()
+            .map(|any| p4_v1::Error::decode(&any.value[..]).unwrap().canonical_code)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn client_against_mock_server() {
+        let server = MockServer::new();
+        let mut client = Client::builder()
+            .device_id(1)
+            .election_id(p4_v1::Uint128 { high: 0, low: 1 })
+            .build()
+            .unwrap();
+        client.connect_with_channel(server.channel().await.unwrap());
+        client.run().await.unwrap();
+
+        // Writes need a pipeline
+        let res = client.write_update(insert(table_entry(1, 1))).await;
+        assert!(matches!(res, Err(ClientError::Status(s)) if s.code() == Code::FailedPrecondition));
+
+        client
+            .p4info_mut()
+            .load(p4runtime::p4::config::v1::P4Info::default());
+        client.set_forwarding_pipeline_config(vec![]).await.unwrap();
+        client
+            .write_update_batch(vec![insert(table_entry(1, 1)), insert(table_entry(2, 1))])
+            .await
+            .unwrap();
+        // Failing updates don't stop the following ones
+        let res = client
+            .write_update_batch(vec![insert(table_entry(1, 1)), insert(table_entry(2, 2))])
+            .await;
+        assert_eq!(write_error_codes(res), vec![Code::AlreadyExists as i32, 0]);
+        assert_eq!(server.entities().len(), 3);
+
+        // Unless the batch is atomic
+        let res = P4Runtime::write(
+            &server,
+            Request::new(p4_v1::WriteRequest {
+                election_id: Some(p4_v1::Uint128 { high: 0, low: 1 }),
+                updates: vec![insert(table_entry(2, 3)), insert(table_entry(1, 1))],
+                atomicity: p4_v1::write_request::Atomicity::RollbackOnError as i32,
+                ..Default::default()
+            }),
+        )
+        .await
+        .map_err(ClientError::from);
+        assert_eq!(
+            write_error_codes(res),
+            vec![Code::Aborted as i32, Code::AlreadyExists as i32]
+        );
+        assert_eq!(server.entities().len(), 3);
+
+        let entities = client.read_entities(table_entry(1, 0)).await.unwrap();
+        assert!(entities.is_empty());
+        let mut wildcard = table_entry(1, 0);
+        if let Some(p4_v1::entity::Entity::TableEntry(e)) = &mut wildcard.entity {
+            e.r#match.clear();
+        }
+        let entities = client.read_entities(wildcard).await.unwrap();
+        assert_eq!(entities, vec![table_entry(1, 1)]);
+
+        assert_eq!(server.send_packet_in(p4_v1::PacketIn::default()), 1);
+        client.get_packet_in(Duration::from_secs(1)).await.unwrap();
+
+        client
+            .send_packet_out(p4_v1::PacketOut {
+                payload: vec![1, 2, 3],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        // The stream channel is processed asynchronously
+        for _ in 0..100 {
+            if !server.packet_outs().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(server.packet_outs()[0].payload, vec![1, 2, 3]);
+        assert_eq!(server.entities().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn elect_next_primary_on_disconnect() {
+        let server = MockServer::new();
+        let election_id = |low| p4_v1::Uint128 { high: 0, low };
+        let client = |low| {
+            let server = server.clone();
+            async move {
+                let mut client = Client::builder()
+                    .device_id(1)
+                    .election_id(election_id(low))
+                    .build()
+                    .unwrap();
+                client.connect_with_channel(server.channel().await.unwrap());
+                client
+            }
+        };
+
+        let mut primary = client(2).await;
+        primary.run().await.unwrap();
+        let mut backup = client(1).await;
+        assert!(matches!(
+            backup.run().await,
+            Err(ClientError::ArbitrationFailed)
+        ));
+        assert_eq!(server.primary_election_id(""), Some(election_id(2)));
+
+        // Election ids are unique within a role
+        let mut duplicate = P4RuntimeClient::new(server.channel().await.unwrap());
+        let arbitration = p4_v1::StreamMessageRequest {
+            update: Some(p4_v1::stream_message_request::Update::Arbitration(
+                p4_v1::MasterArbitrationUpdate {
+                    device_id: 1,
+                    election_id: Some(election_id(1)),
+                    ..Default::default()
+                },
+            )),
+        };
+        let mut stream = duplicate
+            .stream_channel(tokio_stream::iter([arbitration]))
+            .await
+            .unwrap()
+            .into_inner();
+        let status = stream.message().await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(server.primary_election_id(""), Some(election_id(2)));
+
+        primary.quit().await;
+        drop(primary);
+        let update = backup
+            .get_arbitration(Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(update.status.unwrap().code, Code::Ok as i32);
+        assert_eq!(update.election_id, Some(election_id(1)));
+        assert_eq!(server.primary_election_id(""), Some(election_id(1)));
+
+        backup
+            .p4info_mut()
+            .load(p4runtime::p4::config::v1::P4Info::default());
+        backup.set_forwarding_pipeline_config(vec![]).await.unwrap();
+    }
+
+    #[test]
+    fn enable_validation_after_install() {
+        let server = MockServer::new();
+        server.install_pipeline(p4_v1::ForwardingPipelineConfig {
+            p4info: Some(Default::default()),
+            ..Default::default()
+        });
+        server.insert_entities([table_entry(1, 1)]);
+        let server = server.with_validation();
+
+        assert_eq!(server.entities().len(), 1);
+        let mut state = server.lock();
+        let res = state.write(&p4_v1::WriteRequest {
+            updates: vec![insert(table_entry(2, 1))],
+            ..Default::default()
+        });
+        assert!(matches!(res, Err(Rejection(Code::Unknown, ..))));
+    }
+}
@@ -0,0 +1,264 @@
+//! TLS connection options
+//!
+//! P4Runtime servers in production usually require TLS, often with client
+//! certificates (mTLS). [`TlsOptions`] is set on the client builder and used
+//! by `Client::connect`. Certificates and keys are PEM encoded.
+
+use std::sync::Arc;
+
+use tokio_rustls::rustls::{
+    self,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, CryptoProvider},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    DigitallySignedStruct, SignatureScheme,
+};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity, Uri};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// TLS options of a client
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use p4runtime_client::{client::Client, tls::TlsOptions};
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let tls = TlsOptions::new()
+///     .ca_certificate(std::fs::read("ca.pem")?)
+///     .identity(std::fs::read("client.pem")?, std::fs::read("client.key")?)
+///     .domain_name("switch1.example.com");
+///
+/// let mut client = Client::builder().tls(Some(tls)).build()?;
+/// client.connect("https://10.0.0.1:9559").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TlsOptions {
+    ca_certificate: Option<Vec<u8>>,
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+    domain_name: Option<String>,
+    insecure_skip_verify: bool,
+}
+
+impl TlsOptions {
+    /// Create empty TLS options
+    ///
+    /// A CA certificate is required unless verification is skipped.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the CA bundle used to verify the server certificate
+    pub fn ca_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_certificate = Some(pem.into());
+        self
+    }
+
+    /// Set the client certificate chain and private key for mTLS
+    pub fn identity(mut self, cert_pem: impl Into<Vec<u8>>, key_pem: impl Into<Vec<u8>>) -> Self {
+        self.identity = Some((cert_pem.into(), key_pem.into()));
+        self
+    }
+
+    /// Override the name the server certificate is verified against
+    ///
+    /// By default, the host of the endpoint is used.
+    pub fn domain_name(mut self, domain_name: impl Into<String>) -> Self {
+        self.domain_name = Some(domain_name.into());
+        self
+    }
+
+    /// Skip the verification of the server certificate
+    ///
+    /// The connection is still encrypted, but anyone can impersonate the
+    /// server. Only use it in labs with self-signed certificates.
+    ///
+    /// The settings of an `Endpoint` given to `Client::connect` are then not
+    /// applied, only its URI and the connection options of the client are.
+    pub fn insecure_skip_verify(mut self, skip: bool) -> Self {
+        self.insecure_skip_verify = skip;
+        self
+    }
+
+    /// Build the tonic TLS config
+    pub fn client_tls_config(&self) -> ClientTlsConfig {
+        let mut config = ClientTlsConfig::new();
+
+        if let Some(ca_certificate) = &self.ca_certificate {
+            config = config.ca_certificate(Certificate::from_pem(ca_certificate));
+        }
+        if let Some((cert, key)) = &self.identity {
+            config = config.identity(Identity::from_pem(cert, key));
+        }
+        if let Some(domain_name) = &self.domain_name {
+            config = config.domain_name(domain_name);
+        }
+
+        config
+    }
+
+    /// Prepare an endpoint for TLS
+    ///
+    /// tonic refuses https URIs without its own TLS config, so when
+    /// verification is skipped [`connect`](Self::connect) does TLS in its
+    /// connector instead, and the endpoint is rebuilt with an http URI. tonic
+    /// doesn't expose the other settings of an endpoint, so they are lost; the
+    /// original URI is kept as the origin of the requests.
+    pub(crate) fn prepare(&self, endpoint: Endpoint) -> Result<Endpoint, tonic::transport::Error> {
+        if !self.insecure_skip_verify {
+            return endpoint.tls_config(self.client_tls_config());
+        }
+
+        let origin = endpoint.uri().clone();
+        let mut parts = origin.clone().into_parts();
+        parts.scheme = Some(tonic::codegen::http::uri::Scheme::HTTP);
+        let uri = Uri::from_parts(parts).expect("endpoint URI is valid");
+
+        Ok(Endpoint::from(uri).origin(origin))
+    }
+
+    /// Connect to an endpoint returned by [`prepare`](Self::prepare)
+    pub(crate) async fn connect(
+        &self,
+        endpoint: Endpoint,
+    ) -> Result<Channel, tonic::transport::Error> {
+        if !self.insecure_skip_verify {
+            return endpoint.connect().await;
+        }
+
+        let options = self.clone();
+        endpoint
+            .connect_with_connector(tower::service_fn(move |uri: Uri| {
+                let options = options.clone();
+                async move { options.connect_insecure(uri).await }
+            }))
+            .await
+    }
+
+    async fn connect_insecure(
+        &self,
+        uri: Uri,
+    ) -> Result<
+        hyper_util::rt::TokioIo<tokio_rustls::client::TlsStream<tokio::net::TcpStream>>,
+        BoxError,
+    > {
+        let host = uri.host().ok_or("endpoint has no host")?;
+        let port = uri.port_u16().unwrap_or(443);
+        let server_name = self.domain_name.as_deref().unwrap_or(host);
+        let server_name = ServerName::try_from(server_name.trim_matches(['[', ']']).to_string())?;
+
+        let provider = Arc::new(ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)));
+        let mut config = match &self.identity {
+            Some((cert, key)) => {
+                let certs = rustls_pemfile::certs(&mut &cert[..]).collect::<Result<Vec<_>, _>>()?;
+                let key = rustls_pemfile::private_key(&mut &key[..])?
+                    .ok_or("no private key found in PEM")?;
+                builder.with_client_auth_cert(certs, key)?
+            }
+            None => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols = vec![b"h2".to_vec()];
+
+        let tcp = tokio::net::TcpStream::connect((host.trim_matches(['[', ']']), port)).await?;
+        // As tonic does for its own connections
+        tcp.set_nodelay(true)?;
+        let tls = tokio_rustls::TlsConnector::from(Arc::new(config))
+            .connect(server_name, tcp)
+            .await?;
+
+        Ok(hyper_util::rt::TokioIo::new(tls))
+    }
+}
+
+/// Certificate verifier accepting any server certificate
+///
+/// Handshake signatures are still checked, so the peer must own the key of
+/// the certificate it presents.
+#[derive(Debug)]
+struct SkipServerVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prepare_endpoint() {
+        let endpoint = Endpoint::from_static("https://10.0.0.1:9559");
+
+        let tls = TlsOptions::new().domain_name("switch1.example.com");
+        let prepared = tls.prepare(endpoint.clone()).unwrap();
+        assert_eq!(prepared.uri(), endpoint.uri());
+
+        let tls = tls.insecure_skip_verify(true);
+        let prepared = tls.prepare(endpoint.clone()).unwrap();
+        assert_eq!(prepared.uri(), &Uri::from_static("http://10.0.0.1:9559"));
+
+        let prepared = tls
+            .prepare(Endpoint::from_static("https://[::1]:9559"))
+            .unwrap();
+        assert_eq!(prepared.uri(), &Uri::from_static("http://[::1]:9559"));
+    }
+
+    #[test]
+    fn build_client_tls_config() {
+        let tls = TlsOptions::new()
+            .ca_certificate("-----BEGIN CERTIFICATE-----")
+            .identity("cert", "key")
+            .domain_name("switch1.example.com");
+
+        let config = format!("{:?}", tls.client_tls_config());
+        assert!(config.contains("switch1.example.com"));
+        assert!(!format!("{:?}", TlsOptions::new().client_tls_config()).contains("example"));
+    }
+}
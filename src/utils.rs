@@ -24,6 +24,61 @@ pub fn canonicalize_bitstring(bytes: Vec<u8>) -> Vec<u8> {
     }
 }
 
+/// Canonicalize an unsigned bitstring
+///
+/// Unlike [`canonicalize_bitstring`], only leading 0x00 bytes are removed, so
+/// values like masks keep their leading 0xFF bytes. Zero is a single 0x00
+/// byte.
+pub fn canonicalize_unsigned_bitstring(bytes: Vec<u8>) -> Vec<u8> {
+    match bytes.iter().position(|&x| x != 0x00) {
+        Some(start) => bytes[start..].to_vec(),
+        None if bytes.is_empty() => bytes,
+        None => vec![0x00],
+    }
+}
+
+/// Number of significant bits of an unsigned bitstring
+pub(crate) fn bit_len(value: &[u8]) -> usize {
+    match value.iter().position(|&b| b != 0) {
+        Some(i) => (value.len() - i) * 8 - value[i].leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// Bitwise AND of two unsigned bitstrings, in canonical form
+pub(crate) fn and_bitstring(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let n = a.len().min(b.len());
+    let and = a[a.len() - n..]
+        .iter()
+        .zip(&b[b.len() - n..])
+        .map(|(a, b)| a & b)
+        .collect();
+
+    canonicalize_unsigned_bitstring(and)
+}
+
+/// Mask with the `prefix_len` most significant bits of a `bitwidth` bits
+/// value set
+pub(crate) fn prefix_mask(bitwidth: usize, prefix_len: usize) -> Vec<u8> {
+    let mut mask = vec![0u8; bitwidth.div_ceil(8)];
+    for bit in bitwidth - prefix_len.min(bitwidth)..bitwidth {
+        let i = mask.len() - 1 - bit / 8;
+        mask[i] |= 1 << (bit % 8);
+    }
+
+    canonicalize_unsigned_bitstring(mask)
+}
+
+/// Compare two unsigned bitstrings
+pub(crate) fn cmp_bitstring(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+    fn strip(v: &[u8]) -> &[u8] {
+        &v[v.iter().position(|&b| b != 0).unwrap_or(v.len())..]
+    }
+    let (a, b) = (strip(a), strip(b));
+
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -55,4 +110,20 @@ mod tests {
             vec![0x00]
         )
     }
+
+    #[test]
+    fn test_canonicalize_unsigned_bitstring() {
+        assert_eq!(
+            canonicalize_unsigned_bitstring(0xffffu16.to_be_bytes().to_vec()),
+            vec![0xff, 0xff]
+        );
+        assert_eq!(
+            canonicalize_unsigned_bitstring(0x00ffu16.to_be_bytes().to_vec()),
+            vec![0xff]
+        );
+        assert_eq!(
+            canonicalize_unsigned_bitstring(0u32.to_be_bytes().to_vec()),
+            vec![0x00]
+        );
+    }
 }
@@ -0,0 +1,155 @@
+//! Compile the typed API generated from a P4Info fixture
+//!
+//! After a change of the generator, regenerate the fixture with
+//! `UPDATE_GENERATED=1 cargo test --test codegen`.
+
+use p4runtime_client::{
+    codegen::{Generator, Lpm, ProfileAction, Range, Ternary},
+    p4info::{display::DisplayHints, P4Info},
+    p4runtime::p4::v1 as p4_v1,
+};
+
+mod p4 {
+    include!("codegen/generated.rs");
+}
+
+#[test]
+fn generated_code_is_up_to_date() {
+    let p4info = P4Info::from_text(include_str!("codegen/p4info.txtpb")).unwrap();
+    let code = Generator::new(&p4info)
+        .hints(DisplayHints::guess())
+        .generate()
+        .unwrap();
+
+    if std::env::var_os("UPDATE_GENERATED").is_some() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/codegen/generated.rs");
+        std::fs::write(path, &code).unwrap();
+    }
+    assert!(
+        code == include_str!("codegen/generated.rs"),
+        "generated code is outdated, rerun with UPDATE_GENERATED=1"
+    );
+}
+
+#[test]
+fn generated_table_entries() {
+    use p4::tables::{acl, ecmp, ipv4_lpm, rt_2};
+
+    let entry: p4_v1::TableEntry = ipv4_lpm::Entry {
+        key: ipv4_lpm::Key {
+            hdr_ipv4_dst_addr: Some(Lpm::new([10, 0, 1, 0].into(), 24)),
+        },
+        action: ipv4_lpm::Action::Ipv4Forward {
+            dst_addr: [0x08, 0x00, 0x00, 0x00, 0x01, 0x02],
+            port: 1,
+        },
+        priority: 0,
+    }
+    .into();
+    assert_eq!(entry.table_id, ipv4_lpm::ID);
+    assert_eq!(
+        entry.r#match[0].field_match_type,
+        Some(p4_v1::field_match::FieldMatchType::Lpm(
+            p4_v1::field_match::Lpm {
+                value: vec![10, 0, 1, 0],
+                prefix_len: 24,
+            }
+        ))
+    );
+    let action = ipv4_lpm::Action::Ipv4Forward {
+        dst_addr: [0x08, 0x00, 0x00, 0x00, 0x01, 0x02],
+        port: 1,
+    }
+    .to_action();
+    assert_eq!(action.action_id, 11);
+    assert_eq!(
+        action.params[0].value,
+        vec![0x08, 0x00, 0x00, 0x00, 0x01, 0x02]
+    );
+    assert_eq!(action.params[1].value, vec![1]);
+
+    // Wildcards are omitted
+    let key = acl::Key {
+        hdr_ethernet_src_addr: Some(Ternary::new([0, 0, 0, 0, 0, 1], [0xff; 6])),
+        hdr_ipv6_dst_addr: "2001:db8::1".parse().unwrap(),
+        hdr_tcp_dst_port: Some(Range::new(80, 443)),
+        standard_metadata_ingress_port: None,
+    };
+    let ids: Vec<_> = key.to_field_matches().iter().map(|m| m.field_id).collect();
+    assert_eq!(ids, [1, 2, 3]);
+
+    // Unknown and wide values are raw bitstrings
+    let action = acl::Action::SetTag {
+        tag: vec![1; 32],
+        label: vec![2],
+    }
+    .to_action();
+    assert_eq!(action.params[0].value, vec![1; 32]);
+    assert_eq!(action.params[1].value, vec![2]);
+
+    let entry: p4_v1::TableEntry = ecmp::Entry {
+        key: ecmp::Key { meta_hash: 7 },
+        action: ProfileAction::Group(3),
+        priority: 0,
+    }
+    .into();
+    assert_eq!(
+        entry.action.and_then(|a| a.r#type),
+        Some(p4_v1::table_action::Type::ActionProfileGroupId(3))
+    );
+    let member = ecmp::Action::SetNhop { port: 2 }.to_member(5);
+    assert_eq!(member.action_profile_id, ecmp::ACTION_PROFILE_ID);
+
+    // A table named like an import of the generated module is renamed
+    assert_eq!(rt_2::ID, 4);
+}
+
+#[test]
+fn generated_digests_and_packet_metadata() {
+    use p4::{
+        digests::{MacLearnDigestT, PortDigest},
+        packet_metadata::{PacketIn, PacketOut},
+    };
+
+    let digest = MacLearnDigestT {
+        src_addr: [0, 0, 0, 0, 0, 1],
+        ingress_port: 2,
+        is_new: true,
+        delta: vec![0xff, 0xfe],
+    };
+    let list = p4_v1::DigestList {
+        digest_id: MacLearnDigestT::ID,
+        data: vec![digest.to_data()],
+        ..Default::default()
+    };
+    assert_eq!(MacLearnDigestT::from_digest_list(&list), Some(vec![digest]));
+    assert_eq!(PortDigest::from_digest_list(&list), None);
+    assert_eq!(
+        PortDigest::from_data(&PortDigest { value: 3 }.into()),
+        Some(PortDigest { value: 3 })
+    );
+
+    let packet = p4_v1::PacketIn {
+        payload: vec![0xab],
+        metadata: vec![
+            p4_v1::PacketMetadata {
+                metadata_id: 1,
+                value: vec![1, 0],
+            },
+            p4_v1::PacketMetadata {
+                metadata_id: 2,
+                value: vec![0],
+            },
+        ],
+    };
+    assert_eq!(
+        PacketIn::from_packet_in(&packet),
+        Some(PacketIn {
+            ingress_port: 256,
+            pad: 0,
+        })
+    );
+
+    let packet = PacketOut { egress_port: 3 }.to_packet_out(vec![0xab]);
+    assert_eq!(packet.metadata[0].value, vec![3]);
+}
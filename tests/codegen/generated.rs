@@ -0,0 +1,566 @@
+// @generated by p4runtime-client from a P4Info, do not edit
+
+#[allow(unused_imports)]
+use ::p4runtime_client::{codegen as rt, p4runtime::p4::v1 as p4_v1};
+
+/// Tables
+#[allow(dead_code, unused_imports, unused_mut, clippy::all)]
+pub mod tables {
+    use super::{p4_v1, rt};
+
+    /// Table `MyIngress.ipv4_lpm`
+    pub mod ipv4_lpm {
+        use super::{p4_v1, rt};
+
+        /// Table id
+        pub const ID: u32 = 1;
+
+        /// Match key
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Key {
+            /// `hdr.ipv4.dstAddr`, 32 bits, LPM
+            pub hdr_ipv4_dst_addr: Option<rt::Lpm<::std::net::Ipv4Addr>>,
+        }
+
+        impl Key {
+            /// Field matches of the key, omitting wildcards
+            pub fn to_field_matches(&self) -> Vec<p4_v1::FieldMatch> {
+                let mut matches = Vec::new();
+                matches.extend(self.hdr_ipv4_dst_addr.as_ref().map(|m| m.to_field_match(1)));
+                matches
+            }
+        }
+
+        /// Actions of the table
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Action {
+            /// `MyIngress.ipv4_forward`
+            Ipv4Forward {
+                /// `dstAddr`, 48 bits
+                dst_addr: [u8; 6],
+                /// `port`, 9 bits
+                port: u16,
+            },
+            /// `MyIngress.drop`
+            Drop,
+            /// `NoAction`
+            NoAction,
+        }
+
+        impl Action {
+            /// Action message
+            pub fn to_action(&self) -> p4_v1::Action {
+                match *self {
+                    Action::Ipv4Forward { ref dst_addr, ref port } => p4_v1::Action { action_id: 11, params: vec![rt::param(1, dst_addr), rt::param(2, port)] },
+                    Action::Drop => p4_v1::Action { action_id: 12, params: vec![] },
+                    Action::NoAction => p4_v1::Action { action_id: 10, params: vec![] },
+                }
+            }
+        }
+
+        impl From<Action> for p4_v1::TableAction {
+            fn from(action: Action) -> Self {
+                p4_v1::TableAction {
+                    r#type: Some(p4_v1::table_action::Type::Action(action.to_action())),
+                }
+            }
+        }
+
+        /// Table entry
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Entry {
+            /// Match key
+            pub key: Key,
+            /// Action
+            pub action: Action,
+            /// Priority, for keys with ternary, range or optional matches
+            pub priority: i32,
+        }
+
+        impl From<Entry> for p4_v1::TableEntry {
+            fn from(entry: Entry) -> Self {
+                p4_v1::TableEntry {
+                    table_id: ID,
+                    r#match: entry.key.to_field_matches(),
+                    action: Some(entry.action.into()),
+                    priority: entry.priority,
+                    ..Default::default()
+                }
+            }
+        }
+
+        impl From<Entry> for p4_v1::Entity {
+            fn from(entry: Entry) -> Self {
+                p4_v1::Entity {
+                    entity: Some(p4_v1::entity::Entity::TableEntry(entry.into())),
+                }
+            }
+        }
+
+        /// Default entry of the table, to be modified
+        pub fn default_entry(action: Action) -> p4_v1::TableEntry {
+            p4_v1::TableEntry {
+                table_id: ID,
+                action: Some(action.into()),
+                is_default_action: true,
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Table `MyIngress.acl`
+    pub mod acl {
+        use super::{p4_v1, rt};
+
+        /// Table id
+        pub const ID: u32 = 2;
+
+        /// Match key
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Key {
+            /// `hdr.ethernet.srcAddr`, 48 bits, TERNARY
+            pub hdr_ethernet_src_addr: Option<rt::Ternary<[u8; 6]>>,
+            /// `hdr.ipv6.dstAddr`, 128 bits, EXACT
+            pub hdr_ipv6_dst_addr: ::std::net::Ipv6Addr,
+            /// `hdr.tcp.dstPort`, 16 bits, RANGE
+            pub hdr_tcp_dst_port: Option<rt::Range<u16>>,
+            /// `standard_metadata.ingress_port`, 9 bits, OPTIONAL
+            pub standard_metadata_ingress_port: Option<u16>,
+        }
+
+        impl Key {
+            /// Field matches of the key, omitting wildcards
+            pub fn to_field_matches(&self) -> Vec<p4_v1::FieldMatch> {
+                let mut matches = Vec::new();
+                matches.extend(self.hdr_ethernet_src_addr.as_ref().map(|m| m.to_field_match(1)));
+                matches.push(rt::exact(2, &self.hdr_ipv6_dst_addr));
+                matches.extend(self.hdr_tcp_dst_port.as_ref().map(|m| m.to_field_match(3)));
+                matches.extend(self.standard_metadata_ingress_port.as_ref().map(|v| rt::optional(4, v)));
+                matches
+            }
+        }
+
+        /// Actions of the table
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Action {
+            /// `MyIngress.drop`
+            Drop,
+            /// `MyIngress.set_tag`
+            SetTag {
+                /// `tag`, 256 bits
+                tag: Vec<u8>,
+                /// `label`, 0 bits
+                label: Vec<u8>,
+            },
+        }
+
+        impl Action {
+            /// Action message
+            pub fn to_action(&self) -> p4_v1::Action {
+                match *self {
+                    Action::Drop => p4_v1::Action { action_id: 12, params: vec![] },
+                    Action::SetTag { ref tag, ref label } => p4_v1::Action { action_id: 13, params: vec![rt::param(1, tag), rt::param(2, label)] },
+                }
+            }
+        }
+
+        impl From<Action> for p4_v1::TableAction {
+            fn from(action: Action) -> Self {
+                p4_v1::TableAction {
+                    r#type: Some(p4_v1::table_action::Type::Action(action.to_action())),
+                }
+            }
+        }
+
+        /// Table entry
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Entry {
+            /// Match key
+            pub key: Key,
+            /// Action
+            pub action: Action,
+            /// Priority, for keys with ternary, range or optional matches
+            pub priority: i32,
+        }
+
+        impl From<Entry> for p4_v1::TableEntry {
+            fn from(entry: Entry) -> Self {
+                p4_v1::TableEntry {
+                    table_id: ID,
+                    r#match: entry.key.to_field_matches(),
+                    action: Some(entry.action.into()),
+                    priority: entry.priority,
+                    ..Default::default()
+                }
+            }
+        }
+
+        impl From<Entry> for p4_v1::Entity {
+            fn from(entry: Entry) -> Self {
+                p4_v1::Entity {
+                    entity: Some(p4_v1::entity::Entity::TableEntry(entry.into())),
+                }
+            }
+        }
+
+        /// Default entry of the table, to be modified
+        pub fn default_entry(action: Action) -> p4_v1::TableEntry {
+            p4_v1::TableEntry {
+                table_id: ID,
+                action: Some(action.into()),
+                is_default_action: true,
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Table `MyIngress.ecmp`
+    pub mod ecmp {
+        use super::{p4_v1, rt};
+
+        /// Table id
+        pub const ID: u32 = 3;
+
+        /// Action profile id of the table
+        pub const ACTION_PROFILE_ID: u32 = 20;
+
+        /// Match key
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Key {
+            /// `meta.hash`, 16 bits, EXACT
+            pub meta_hash: u16,
+        }
+
+        impl Key {
+            /// Field matches of the key, omitting wildcards
+            pub fn to_field_matches(&self) -> Vec<p4_v1::FieldMatch> {
+                let mut matches = Vec::new();
+                matches.push(rt::exact(1, &self.meta_hash));
+                matches
+            }
+        }
+
+        /// Actions of the table
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Action {
+            /// `MyIngress.set_nhop`
+            SetNhop {
+                /// `port`, 9 bits
+                port: u16,
+            },
+        }
+
+        impl Action {
+            /// Action message
+            pub fn to_action(&self) -> p4_v1::Action {
+                match *self {
+                    Action::SetNhop { ref port } => p4_v1::Action { action_id: 14, params: vec![rt::param(1, port)] },
+                }
+            }
+
+            /// Action profile member running the action
+            pub fn to_member(&self, member_id: u32) -> p4_v1::ActionProfileMember {
+                p4_v1::ActionProfileMember {
+                    action_profile_id: ACTION_PROFILE_ID,
+                    member_id,
+                    action: Some(self.to_action()),
+                }
+            }
+        }
+
+        impl From<Action> for p4_v1::TableAction {
+            fn from(action: Action) -> Self {
+                p4_v1::TableAction {
+                    r#type: Some(p4_v1::table_action::Type::Action(action.to_action())),
+                }
+            }
+        }
+
+        /// Table entry
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Entry {
+            /// Match key
+            pub key: Key,
+            /// Action
+            pub action: rt::ProfileAction,
+            /// Priority, for keys with ternary, range or optional matches
+            pub priority: i32,
+        }
+
+        impl From<Entry> for p4_v1::TableEntry {
+            fn from(entry: Entry) -> Self {
+                p4_v1::TableEntry {
+                    table_id: ID,
+                    r#match: entry.key.to_field_matches(),
+                    action: Some(entry.action.into()),
+                    priority: entry.priority,
+                    ..Default::default()
+                }
+            }
+        }
+
+        impl From<Entry> for p4_v1::Entity {
+            fn from(entry: Entry) -> Self {
+                p4_v1::Entity {
+                    entity: Some(p4_v1::entity::Entity::TableEntry(entry.into())),
+                }
+            }
+        }
+
+        /// Default entry of the table, to be modified
+        pub fn default_entry(action: rt::ProfileAction) -> p4_v1::TableEntry {
+            p4_v1::TableEntry {
+                table_id: ID,
+                action: Some(action.into()),
+                is_default_action: true,
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Table `MyIngress.rt`
+    pub mod rt_2 {
+        use super::{p4_v1, rt};
+
+        /// Table id
+        pub const ID: u32 = 4;
+
+        /// Match key
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Key {
+            /// `meta.p4_v1`, 8 bits, EXACT
+            pub meta_p4_v1: u8,
+        }
+
+        impl Key {
+            /// Field matches of the key, omitting wildcards
+            pub fn to_field_matches(&self) -> Vec<p4_v1::FieldMatch> {
+                let mut matches = Vec::new();
+                matches.push(rt::exact(1, &self.meta_p4_v1));
+                matches
+            }
+        }
+
+        /// Actions of the table
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Action {
+            /// `NoAction`
+            NoAction,
+        }
+
+        impl Action {
+            /// Action message
+            pub fn to_action(&self) -> p4_v1::Action {
+                match *self {
+                    Action::NoAction => p4_v1::Action { action_id: 10, params: vec![] },
+                }
+            }
+        }
+
+        impl From<Action> for p4_v1::TableAction {
+            fn from(action: Action) -> Self {
+                p4_v1::TableAction {
+                    r#type: Some(p4_v1::table_action::Type::Action(action.to_action())),
+                }
+            }
+        }
+
+        /// Table entry
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Entry {
+            /// Match key
+            pub key: Key,
+            /// Action
+            pub action: Action,
+            /// Priority, for keys with ternary, range or optional matches
+            pub priority: i32,
+        }
+
+        impl From<Entry> for p4_v1::TableEntry {
+            fn from(entry: Entry) -> Self {
+                p4_v1::TableEntry {
+                    table_id: ID,
+                    r#match: entry.key.to_field_matches(),
+                    action: Some(entry.action.into()),
+                    priority: entry.priority,
+                    ..Default::default()
+                }
+            }
+        }
+
+        impl From<Entry> for p4_v1::Entity {
+            fn from(entry: Entry) -> Self {
+                p4_v1::Entity {
+                    entity: Some(p4_v1::entity::Entity::TableEntry(entry.into())),
+                }
+            }
+        }
+
+        /// Default entry of the table, to be modified
+        pub fn default_entry(action: Action) -> p4_v1::TableEntry {
+            p4_v1::TableEntry {
+                table_id: ID,
+                action: Some(action.into()),
+                is_default_action: true,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Digests
+#[allow(dead_code, unused_imports, unused_mut, clippy::all)]
+pub mod digests {
+    use super::{p4_v1, rt};
+
+    /// Digest `mac_learn_digest_t`
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct MacLearnDigestT {
+        /// `srcAddr`
+        pub src_addr: [u8; 6],
+        /// `ingress_port`
+        pub ingress_port: u16,
+        /// `is_new`
+        pub is_new: bool,
+        /// `delta`
+        pub delta: Vec<u8>,
+    }
+
+    impl MacLearnDigestT {
+        /// Digest id
+        pub const ID: u32 = 40;
+
+        /// Decode a digest data
+        pub fn from_data(data: &p4_v1::P4Data) -> Option<Self> {
+            let members = rt::struct_members(data)?;
+            if members.len() != 4 {
+                return None;
+            }
+            Some(Self {
+                src_addr: rt::from_bitstring_data(&members[0])?,
+                ingress_port: rt::from_bitstring_data(&members[1])?,
+                is_new: rt::from_bool_data(&members[2])?,
+                delta: rt::from_bitstring_data(&members[3])?,
+            })
+        }
+
+        /// Decode the data of a digest list, if it is of this digest
+        pub fn from_digest_list(list: &p4_v1::DigestList) -> Option<Vec<Self>> {
+            if list.digest_id != Self::ID {
+                return None;
+            }
+            list.data.iter().map(Self::from_data).collect()
+        }
+
+        /// Encode as digest data
+        pub fn to_data(&self) -> p4_v1::P4Data {
+            rt::struct_data(vec![rt::bitstring_data(&self.src_addr), rt::bitstring_data(&self.ingress_port), rt::bool_data(self.is_new), rt::bitstring_data(&self.delta)])
+        }
+    }
+
+    impl From<MacLearnDigestT> for p4_v1::P4Data {
+        fn from(digest: MacLearnDigestT) -> Self {
+            digest.to_data()
+        }
+    }
+
+    /// Digest `port_digest`
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct PortDigest {
+        /// `value`
+        pub value: u16,
+    }
+
+    impl PortDigest {
+        /// Digest id
+        pub const ID: u32 = 41;
+
+        /// Decode a digest data
+        pub fn from_data(data: &p4_v1::P4Data) -> Option<Self> {
+            Some(Self { value: rt::from_bitstring_data(data)? })
+        }
+
+        /// Decode the data of a digest list, if it is of this digest
+        pub fn from_digest_list(list: &p4_v1::DigestList) -> Option<Vec<Self>> {
+            if list.digest_id != Self::ID {
+                return None;
+            }
+            list.data.iter().map(Self::from_data).collect()
+        }
+
+        /// Encode as digest data
+        pub fn to_data(&self) -> p4_v1::P4Data {
+            rt::bitstring_data(&self.value)
+        }
+    }
+
+    impl From<PortDigest> for p4_v1::P4Data {
+        fn from(digest: PortDigest) -> Self {
+            digest.to_data()
+        }
+    }
+}
+
+/// Controller packet metadata
+#[allow(dead_code, unused_imports, unused_mut, clippy::all)]
+pub mod packet_metadata {
+    use super::{p4_v1, rt};
+
+    /// Controller packet metadata `packet_in`
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct PacketIn {
+        /// `ingress_port`, 9 bits
+        pub ingress_port: u16,
+        /// `_pad`, 7 bits
+        pub pad: u8,
+    }
+
+    impl PacketIn {
+        /// Decode packet metadata
+        pub fn from_metadata(metadata: &[p4_v1::PacketMetadata]) -> Option<Self> {
+            Some(Self {
+                ingress_port: rt::find_metadata(metadata, 1)?,
+                pad: rt::find_metadata(metadata, 2)?,
+            })
+        }
+
+        /// Encode as packet metadata
+        pub fn to_metadata(&self) -> Vec<p4_v1::PacketMetadata> {
+            vec![rt::metadata(1, &self.ingress_port), rt::metadata(2, &self.pad)]
+        }
+
+        /// Decode the metadata of a PacketIn
+        pub fn from_packet_in(packet: &p4_v1::PacketIn) -> Option<Self> {
+            Self::from_metadata(&packet.metadata)
+        }
+    }
+
+    /// Controller packet metadata `packet_out`
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct PacketOut {
+        /// `egress_port`, 9 bits
+        pub egress_port: u16,
+    }
+
+    impl PacketOut {
+        /// Decode packet metadata
+        pub fn from_metadata(metadata: &[p4_v1::PacketMetadata]) -> Option<Self> {
+            Some(Self {
+                egress_port: rt::find_metadata(metadata, 1)?,
+            })
+        }
+
+        /// Encode as packet metadata
+        pub fn to_metadata(&self) -> Vec<p4_v1::PacketMetadata> {
+            vec![rt::metadata(1, &self.egress_port)]
+        }
+
+        /// PacketOut carrying the metadata
+        pub fn to_packet_out(&self, payload: Vec<u8>) -> p4_v1::PacketOut {
+            p4_v1::PacketOut {
+                payload,
+                metadata: self.to_metadata(),
+            }
+        }
+    }
+}